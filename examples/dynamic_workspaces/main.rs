@@ -32,6 +32,7 @@ fn my_layouts() -> Vec<Layout> {
         gapless: true,
         follow_focus: true,
         allow_wrapping: false,
+        reverse_clients: false,
     };
 
     vec![
@@ -77,6 +77,7 @@ fn main() -> Result<()> {
         gapless: true,
         follow_focus: true,
         allow_wrapping: false,
+        reverse_clients: false,
     };
 
     // Default number of clients in the main layout area
@@ -15,14 +15,18 @@ pub use crate::{
         ring::{InsertPoint, Selector},
         screen::Screen,
         workspace::Workspace,
-        xconnection::{ClientMessage, Prop, Result, XConn, XEvent, Xid},
+        xconnection::{Atom, ClientMessage, Prop, Result, WindowState, XConn, XError, XEvent, Xid},
         Layout, LayoutConf,
     },
     draw::Color,
     logging_error_handler, Backward, Forward, Less, More, PenroseError, WindowManager,
 };
 
-pub use std::{cell::Cell, collections::HashMap, fmt};
+pub use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+};
 
 pub type TestWM = WindowManager<TestXConn>;
 pub type TestKeyBindings = KeyBindings<TestXConn>;
@@ -81,7 +85,7 @@ pub fn row_layout(
         .as_rows(clients.len() as u32)
         .iter()
         .zip(clients)
-        .map(|(r, c)| (c.id(), Some(*r)))
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
         .collect()
 }
 
@@ -211,6 +215,89 @@ __impl_stub_xcon! {
     }
 }
 
+// A single 800x600 screen that serves per-client properties and records where each client was
+// positioned along with the border it was given.
+#[derive(Default)]
+pub struct GeometryXConn {
+    props: RefCell<HashMap<(Xid, String), Prop>>,
+    pub positions: RefCell<HashMap<Xid, Region>>,
+    pub borders: RefCell<HashMap<Xid, u32>>,
+}
+
+impl fmt::Debug for GeometryXConn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeometryXConn")
+            .field("positions", &self.positions.borrow())
+            .finish()
+    }
+}
+
+impl GeometryXConn {
+    pub fn with_prop(self, id: Xid, atom: Atom, val: Prop) -> Self {
+        self.set_prop(id, atom, val);
+        self
+    }
+
+    pub fn with_class(self, id: Xid, class: &str) -> Self {
+        self.with_prop(
+            id,
+            Atom::WmClass,
+            Prop::UTF8String(vec![class.into(), class.into()]),
+        )
+    }
+
+    pub fn set_prop(&self, id: Xid, atom: Atom, val: Prop) {
+        self.props
+            .borrow_mut()
+            .insert((id, atom.as_ref().to_string()), val);
+    }
+
+    pub fn remove_prop(&self, id: Xid, atom: Atom) {
+        self.props
+            .borrow_mut()
+            .remove(&(id, atom.as_ref().to_string()));
+    }
+}
+
+__impl_stub_xcon! {
+    for GeometryXConn;
+
+    atom_queries: {}
+    client_properties: {
+        fn mock_get_prop(&self, id: Xid, name: &str) -> Result<Prop> {
+            self.props
+                .borrow()
+                .get(&(id, name.to_string()))
+                .cloned()
+                .ok_or_else(|| XError::MissingProperty(name.into(), id))
+        }
+    }
+    client_handler: {}
+    client_config: {
+        fn mock_position_client(&self, id: Xid, r: Region, border: u32, _stack_above: bool) -> Result<()> {
+            self.positions.borrow_mut().insert(id, r);
+            self.borders.borrow_mut().insert(id, border);
+            Ok(())
+        }
+    }
+    event_handler: {}
+    state: {
+        fn mock_current_screens(&self) -> Result<Vec<Screen>> {
+            Ok(vec![Screen::new(Region::new(0, 0, 800, 600), 0)])
+        }
+
+        fn mock_client_geometry(&self, id: Xid) -> Result<Region> {
+            Ok(self
+                .positions
+                .borrow()
+                .get(&id)
+                .copied()
+                .unwrap_or_else(|| Region::new(0, 0, 100, 100)))
+        }
+    }
+    conn: {}
+}
+
 // A helper for checking that calls to the X server are triggered correctly
 pub struct RecordingXConn(Cell<Vec<(String, Vec<String>)>>);
 
@@ -272,6 +359,11 @@ __impl_stub_xcon! {
             self.add_call("delete_prop", strings!(id, name));
             Ok(())
         }
+
+        fn mock_set_client_state(&self, id: Xid, wm_state: WindowState) -> Result<()> {
+            self.add_call("set_client_state", strings!(id, wm_state));
+            Ok(())
+        }
     }
     client_handler: {
         fn mock_focus_client(&self, id: Xid) -> Result<()> {
@@ -295,6 +387,11 @@ __impl_stub_xcon! {
             Ok(())
         }
 
+        fn mock_set_client_border_pixmap(&self, id: Xid, w: u32, h: u32, _: &[(Region, Color)]) -> Result<()> {
+            self.add_call("set_client_border_pixmap", strings!(id, w, h));
+            Ok(())
+        }
+
         fn mock_position_client(&self, id: Xid, r: Region, border: u32, stack_above: bool) -> Result<()> {
             self.add_call("position_client", strings!(id, r, border, stack_above));
             Ok(())
@@ -325,5 +422,10 @@ __impl_stub_xcon! {
             self.add_call("is_managed_client", strings!(c.id()));
             true
         }
+
+        fn mock_grab_server(&self) -> Result<()> {
+            self.add_call("grab_server", vec![]);
+            Ok(())
+        }
     }
 }
@@ -11,7 +11,7 @@ use crate::{
     },
     Result,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 /**
  * Automatically set the X root window WM_NAME property to be the WM_NAME of the
@@ -260,3 +260,57 @@ where
         wm.try_manage_existing_windows()
     }
 }
+
+/// Write the [WindowManager] state as a line of JSON each time that it changes.
+///
+/// The output is generated using [WindowManager::state] and is intended for consumption by
+/// status bar scripts (such as those used with polybar or lemonbar). A typical setup writes to
+/// stdout and pipes the output of penrose into the bar script, or writes to a named pipe:
+///
+/// ```no_run
+/// # use penrose::{contrib::hooks::StatusWriter, core::hooks::Hook, xcb::XcbConnection};
+/// # fn example() -> penrose::Result<Vec<Box<dyn Hook<XcbConnection>>>> {
+/// let fifo = std::fs::OpenOptions::new().write(true).open("/tmp/penrose-status")?;
+/// let hooks: Vec<Box<dyn Hook<XcbConnection>>> = vec![StatusWriter::new(fifo)];
+/// # Ok(hooks)
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct StatusWriter<W: Write> {
+    writer: W,
+    last: Option<String>,
+}
+
+impl<W: Write> StatusWriter<W> {
+    /// Construct a pre-boxed instance of the StatusWriter hook
+    pub fn new(writer: W) -> Box<Self> {
+        Box::new(Self { writer, last: None })
+    }
+
+    fn write_if_changed<X: XConn>(&mut self, wm: &WindowManager<X>) -> Result<()> {
+        let json = wm.state().to_json();
+        if self.last.as_ref() == Some(&json) {
+            return Ok(());
+        }
+
+        writeln!(self.writer, "{}", json)?;
+        self.writer.flush()?;
+        self.last = Some(json);
+
+        Ok(())
+    }
+}
+
+impl<X, W> Hook<X> for StatusWriter<W>
+where
+    X: XConn,
+    W: Write,
+{
+    fn startup(&mut self, wm: &mut WindowManager<X>) -> Result<()> {
+        self.write_if_changed(wm)
+    }
+
+    fn event_handled(&mut self, wm: &mut WindowManager<X>) -> Result<()> {
+        self.write_if_changed(wm)
+    }
+}
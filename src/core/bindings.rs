@@ -7,9 +7,9 @@ use crate::{
 #[cfg(feature = "keysyms")]
 use penrose_keysyms::XKeySym;
 
-use std::{collections::HashMap, convert::TryFrom};
+use std::{collections::HashMap, convert::TryFrom, fmt};
 
-use strum::EnumIter;
+use strum::{EnumIter, IntoEnumIterator};
 
 /// Some action to be run by a user key binding
 pub type KeyEventHandler<X> = Box<dyn FnMut(&mut WindowManager<X>) -> Result<()>>;
@@ -20,6 +20,17 @@ pub type MouseEventHandler<X> = Box<dyn FnMut(&mut WindowManager<X>, &MouseEvent
 /// User defined key bindings
 pub type KeyBindings<X> = HashMap<KeyCode, KeyEventHandler<X>>;
 
+/// User defined bindings that are triggered by tapping and releasing a modifier key on its own.
+///
+/// Bindings are keyed on the [KeyCode] of the bare modifier key (e.g. `Super_L` with no mask
+/// held) and their action is only run if no other key was pressed between the modifier being
+/// pressed and released. Using the modifier as part of a regular key binding (such as `M-j`) will
+/// not trigger the tap action.
+///
+/// NOTE: while a tap modifier is held, all other key presses are intercepted by penrose rather
+/// than being passed through to the focused client.
+pub type ModifierTapBindings<X> = HashMap<KeyCode, KeyEventHandler<X>>;
+
 /// User defined mouse bindings
 pub type MouseBindings<X> = HashMap<(MouseEventKind, MouseState), MouseEventHandler<X>>;
 
@@ -52,6 +63,18 @@ pub enum KeyPress {
     Left,
     /// Right
     Right,
+    /// XF86AudioRaiseVolume
+    AudioRaiseVolume,
+    /// XF86AudioLowerVolume
+    AudioLowerVolume,
+    /// XF86AudioMute
+    AudioMute,
+    /// XF86AudioPlay
+    AudioPlay,
+    /// XF86MonBrightnessUp
+    MonBrightnessUp,
+    /// XF86MonBrightnessDown
+    MonBrightnessDown,
 }
 
 #[cfg(feature = "keysyms")]
@@ -71,6 +94,12 @@ impl TryFrom<XKeySym> for KeyPress {
             XKeySym::XK_Down | XKeySym::XK_KP_Down => KeyPress::Down,
             XKeySym::XK_Left | XKeySym::XK_KP_Left => KeyPress::Left,
             XKeySym::XK_Right | XKeySym::XK_KP_Right => KeyPress::Right,
+            XKeySym::XF86XK_AudioRaiseVolume => KeyPress::AudioRaiseVolume,
+            XKeySym::XF86XK_AudioLowerVolume => KeyPress::AudioLowerVolume,
+            XKeySym::XF86XK_AudioMute => KeyPress::AudioMute,
+            XKeySym::XF86XK_AudioPlay => KeyPress::AudioPlay,
+            XKeySym::XF86XK_MonBrightnessUp => KeyPress::MonBrightnessUp,
+            XKeySym::XF86XK_MonBrightnessDown => KeyPress::MonBrightnessDown,
             s => KeyPress::Utf8(s.as_utf8_string()?),
         })
     }
@@ -82,6 +111,21 @@ pub type KeyCodeMask = u16;
 /// A u8 X key-code enum value
 pub type KeyCodeValue = u8;
 
+/// The core X protocol masks for CapsLock (Lock) and NumLock (Mod2).
+///
+/// These are stripped from incoming key events before looking up bindings so that having either
+/// lock active does not prevent user key bindings from firing.
+pub const LOCK_MASKS: &[KeyCodeMask] = &[1 << 1, 1 << 4];
+
+// Every combination of the lock masks (including none of them) that needs to be grabbed
+// alongside each user binding so that bindings still fire when one or more locks are active.
+#[cfg(any(feature = "xcb", feature = "x11rb"))]
+pub(crate) fn lock_mask_combinations() -> Vec<KeyCodeMask> {
+    LOCK_MASKS.iter().fold(vec![0], |acc, m| {
+        acc.iter().flat_map(|&a| vec![a, a | m]).collect()
+    })
+}
+
 /// A key press and held modifiers
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct KeyCode {
@@ -99,6 +143,22 @@ impl KeyCode {
             code: self.code,
         }
     }
+
+    /// Create a new [KeyCode] from this one that removes all of the given masks.
+    ///
+    /// This is equivalent to calling [KeyCode::ignoring_modifier] once for each mask.
+    pub fn ignoring_modifiers(&self, masks: &[KeyCodeMask]) -> KeyCode {
+        self.ignoring_modifier(masks.iter().fold(0, |acc, m| acc | m))
+    }
+}
+
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for m in ModifierKey::iter().filter(|&m| self.mask & u16::from(m) > 0) {
+            write!(f, "{}-", m)?;
+        }
+        write!(f, "{}", self.code)
+    }
 }
 
 /// Known mouse buttons for binding actions
@@ -141,6 +201,30 @@ pub enum ModifierKey {
     Meta,
 }
 
+// These are the core X protocol modifier masks and are the same for all back ends
+impl From<ModifierKey> for u16 {
+    fn from(m: ModifierKey) -> u16 {
+        match m {
+            ModifierKey::Ctrl => 1 << 2,
+            ModifierKey::Alt => 1 << 3,
+            ModifierKey::Shift => 1,
+            ModifierKey::Meta => 1 << 6,
+        }
+    }
+}
+
+impl fmt::Display for ModifierKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Ctrl => "C",
+            Self::Alt => "A",
+            Self::Shift => "S",
+            Self::Meta => "M",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl TryFrom<&str> for ModifierKey {
     type Error = PenroseError;
 
@@ -166,8 +250,12 @@ pub struct MouseState {
 
 impl MouseState {
     /// Construct a new MouseState
+    ///
+    /// Modifiers are sorted and deduplicated so that equivalent states always compare (and hash)
+    /// equal when used to look up [MouseBindings].
     pub fn new(button: MouseButton, mut modifiers: Vec<ModifierKey>) -> Self {
         modifiers.sort();
+        modifiers.dedup();
         Self { button, modifiers }
     }
 }
@@ -181,6 +269,8 @@ pub enum MouseEventKind {
     Release,
     /// The mouse was moved while a button was held
     Motion,
+    /// A button was pressed twice in quick succession
+    DoubleClick,
 }
 
 /// A mouse movement or button event
@@ -211,10 +301,83 @@ impl MouseEvent {
     ) -> Self {
         MouseEvent {
             id,
-            rpt: Point::new(rx as u32, ry as u32),
-            wpt: Point::new(ex as u32, ey as u32),
+            rpt: Point::new(rx as i32, ry as i32),
+            wpt: Point::new(ex as i32, ey as i32),
             state,
             kind,
         }
     }
 }
+
+#[cfg(all(test, feature = "keysyms"))]
+mod tests {
+    use super::*;
+
+    test_cases! {
+        xf86_keysyms_are_not_utf8;
+        args: (sym: XKeySym, expected: KeyPress);
+
+        case: raise_volume => (XKeySym::XF86XK_AudioRaiseVolume, KeyPress::AudioRaiseVolume);
+        case: lower_volume => (XKeySym::XF86XK_AudioLowerVolume, KeyPress::AudioLowerVolume);
+        case: mute => (XKeySym::XF86XK_AudioMute, KeyPress::AudioMute);
+
+        body: {
+            assert_eq!(KeyPress::try_from(sym).unwrap(), expected);
+        }
+    }
+    #[test]
+    fn mouse_state_modifiers_are_deduplicated() {
+        let dup = MouseState::new(
+            MouseButton::Left,
+            vec![ModifierKey::Shift, ModifierKey::Shift],
+        );
+
+        assert_eq!(
+            dup,
+            MouseState::new(MouseButton::Left, vec![ModifierKey::Shift])
+        );
+    }
+
+    #[test]
+    fn negative_pointer_coordinates_are_preserved() {
+        let state = MouseState::new(MouseButton::Left, vec![]);
+        let e = MouseEvent::new(1, -50, -20, -5, -1, state, MouseEventKind::Motion);
+
+        assert_eq!(e.rpt, Point::new(-50, -20));
+        assert_eq!(e.wpt, Point::new(-5, -1));
+    }
+
+    #[test]
+    fn ignoring_modifiers_matches_ignoring_each_in_sequence() {
+        let caps = LOCK_MASKS[0];
+        let numlock = LOCK_MASKS[1];
+        let meta = u16::from(ModifierKey::Meta);
+        let k = KeyCode {
+            mask: meta | caps | numlock,
+            code: 42,
+        };
+
+        let stripped = k.ignoring_modifiers(&[caps, numlock]);
+
+        assert_eq!(
+            stripped,
+            k.ignoring_modifier(caps).ignoring_modifier(numlock)
+        );
+        assert_eq!(
+            stripped,
+            KeyCode {
+                mask: meta,
+                code: 42
+            }
+        );
+    }
+
+    #[cfg(any(feature = "xcb", feature = "x11rb"))]
+    #[test]
+    fn lock_mask_combinations_cover_all_subsets() {
+        let mut combos = lock_mask_combinations();
+        combos.sort_unstable();
+
+        assert_eq!(combos, vec![0, 1 << 1, 1 << 4, (1 << 1) | (1 << 4)]);
+    }
+}
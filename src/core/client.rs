@@ -1,5 +1,25 @@
 //! Metadata around X clients and manipulating them
-use crate::core::xconnection::{Atom, Prop, WmHints, WmNormalHints, XClientProperties, Xid};
+use crate::core::{
+    data_types::Region,
+    xconnection::{Atom, Prop, WmHints, WmHintsFlags, WmNormalHints, XClientProperties, Xid},
+};
+
+// The flag in _MOTIF_WM_HINTS indicating that the decorations field is set
+const MWM_HINTS_DECORATIONS: u32 = 1 << 1;
+
+// _MOTIF_WM_HINTS is five u32 values: flags, functions, decorations, input mode and status. A
+// client is undecorated if it sets the decorations flag with no decorations selected.
+pub(crate) fn requests_no_decorations<X>(conn: &X, id: Xid) -> bool
+where
+    X: XClientProperties,
+{
+    match conn.get_prop(id, Atom::MotifWmHints.as_ref()) {
+        Ok(Prop::Bytes(raw)) if raw.len() >= 3 => {
+            raw[0] & MWM_HINTS_DECORATIONS != 0 && raw[2] == 0
+        }
+        _ => false,
+    }
+}
 
 /**
  * Meta-data around a client window that we are handling.
@@ -17,11 +37,17 @@ pub struct Client {
     pub(crate) wm_protocols: Vec<String>, // Can't use Atom as it could be something arbitrary
     pub(crate) wm_hints: Option<WmHints>,
     pub(crate) wm_normal_hints: Option<WmNormalHints>,
+    pub(crate) transient_for: Option<Xid>,
+    pub(crate) floating_region: Option<Region>,
     // state flags
     pub(crate) accepts_focus: bool,
     pub(crate) floating: bool,
     pub(crate) fullscreen: bool,
     pub(crate) mapped: bool,
+    pub(crate) minimized: bool,
+    pub(crate) undecorated: bool,
+    pub(crate) dim_exempt: bool,
+    pub(crate) sticky: bool,
     pub(crate) urgent: bool,
     pub(crate) wm_managed: bool,
 }
@@ -54,14 +80,23 @@ impl Client {
             Ok(Prop::WmHints(hints)) => Some(hints),
             _ => None,
         };
+        let urgent = wm_hints
+            .as_ref()
+            .map(|h| h.flags.contains(WmHintsFlags::URGENCY_HINT))
+            .unwrap_or(false);
         let wm_normal_hints = match conn.get_prop(id, Atom::WmNormalHints.as_ref()) {
             Ok(Prop::WmNormalHints(hints)) => Some(hints),
             _ => None,
         };
+        let transient_for = match conn.get_prop(id, Atom::WmTransientFor.as_ref()) {
+            Ok(Prop::Window(ids)) => ids.first().copied(),
+            _ => None,
+        };
         let wm_protocols = match conn.get_prop(id, Atom::WmProtocols.as_ref()) {
             Ok(Prop::Atom(protocols)) => protocols,
             _ => vec![],
         };
+        let undecorated = requests_no_decorations(conn, id);
 
         Self {
             id,
@@ -72,11 +107,17 @@ impl Client {
             wm_protocols,
             wm_hints,
             wm_normal_hints,
+            transient_for,
+            floating_region: None,
             floating,
             accepts_focus,
             fullscreen: false,
             mapped: false,
-            urgent: false,
+            minimized: false,
+            undecorated,
+            dim_exempt: false,
+            sticky: false,
+            urgent,
             wm_managed: true,
         }
     }
@@ -96,11 +137,59 @@ impl Client {
         &self.wm_name
     }
 
+    /// The _NET_WM_WINDOW_TYPE property of this client
+    pub fn wm_type(&self) -> &[String] {
+        &self.wm_type
+    }
+
     /// Whether or not this client is currently fullscreen
     pub fn is_fullscreen(&self) -> bool {
         self.fullscreen
     }
 
+    /// Whether or not this client is currently floating
+    pub fn is_floating(&self) -> bool {
+        self.floating
+    }
+
+    /// Whether or not this client is shown on every workspace
+    pub fn is_sticky(&self) -> bool {
+        self.sticky
+    }
+
+    /// Whether or not this client is currently minimized
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Whether or not this client has asked (via _MOTIF_WM_HINTS) to not be decorated by the
+    /// window manager, in which case it is positioned without a border.
+    pub fn is_undecorated(&self) -> bool {
+        self.undecorated
+    }
+
+    /// Whether or not this client is excluded from having its opacity reduced when it loses focus.
+    ///
+    /// See [unfocused_opacity][crate::core::config::Config::unfocused_opacity].
+    pub fn is_dim_exempt(&self) -> bool {
+        self.dim_exempt
+    }
+
+    /// The parent window of this client if it set WM_TRANSIENT_FOR when it was mapped
+    pub fn transient_for(&self) -> Option<Xid> {
+        self.transient_for
+    }
+
+    /// The WM_NORMAL_HINTS set by this client, if any
+    pub fn size_hints(&self) -> Option<&WmNormalHints> {
+        self.wm_normal_hints.as_ref()
+    }
+
+    /// Whether or not this client has set the urgency hint since it last had focus
+    pub fn is_urgent(&self) -> bool {
+        self.urgent
+    }
+
     /// The current workspace index that this client is showing on
     pub fn workspace(&self) -> usize {
         self.workspace
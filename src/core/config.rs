@@ -1,10 +1,17 @@
 //! User facing configuration of the penrose [WindowManager][crate::core::manager::WindowManager].
 
 use crate::{
-    core::{layouts::side_stack, Layout, LayoutConf},
-    draw::Color,
+    core::{
+        client::Client, data_types::Region, layouts::side_stack,
+        xconnection::AUTO_FLOAT_WINDOW_TYPES, Layout, LayoutConf,
+    },
+    draw::{BorderStyle, Color},
     PenroseError,
 };
+use std::{collections::HashSet, convert::TryInto, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// The main user facing configuration details.
 ///
@@ -12,15 +19,29 @@ use crate::{
 ///
 /// # Example
 /// ```
-/// use penrose::{Config, draw::Color};
+/// use penrose::{Config, draw::BorderStyle};
 /// use std::convert::TryFrom;
 ///
 /// let config = Config::default();
 ///
-/// assert_eq!(config.border_px(), &2);
-/// assert_eq!(config.focused_border(), &Color::try_from("#cc241d").unwrap());
+/// assert_eq!(config.border_px, 2);
+/// assert_eq!(config.focused_border, BorderStyle::try_from("#cc241d").unwrap());
+///
+/// let config = Config::builder()
+///     .border_px(4)
+///     .focused_border("#ebdbb2")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(config.border_px, 4);
+/// assert_eq!(config.gap_px, Config::default().gap_px);
 /// ```
+///
+/// With the `serde` feature enabled a Config can also be loaded from TOML using
+/// [Config::from_toml_str]. Any fields that are not specified take their default value and
+/// [layouts][Config::layouts] are always left to be set in code.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct Config {
     /// the initial available workspaces.
     ///
@@ -29,30 +50,393 @@ pub struct Config {
     pub workspaces: Vec<String>,
 
     /// the window classes that will always be considered floating
+    ///
+    /// This is a shorthand for a [WindowRule] that floats clients whose WM_CLASS is an exact
+    /// match for one of these classes.
     pub floating_classes: Vec<String>,
 
+    /// the _NET_WM_WINDOW_TYPE atoms (e.g. "_NET_WM_WINDOW_TYPE_DIALOG") of clients that will
+    /// always be considered floating
+    ///
+    /// Transient clients (those with WM_TRANSIENT_FOR set) are always floated and placed in the
+    /// center of their parent.
+    pub floating_window_types: Vec<String>,
+
+    /// rules for placing new clients based on their WM_CLASS or WM_NAME
+    ///
+    /// Rules are checked in order when a new client is managed, after
+    /// [floating_classes][Config::floating_classes], with later matching rules taking
+    /// precedence over earlier ones.
+    pub window_rules: Vec<WindowRule>,
+
     /// the [Layout] functions to be used by each [Workspace][crate::core::workspace::Workspace]
     ///
     /// # Constraints
     /// You must provide at least one layout function
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub layouts: Vec<Layout>,
 
-    /// the focused border color as a hex literal
-    pub focused_border: Color,
-    /// the unfocused border color as a hex literal
-    pub unfocused_border: Color,
+    /// the default [Layout] for each screen, used by workspaces the first time they are shown
+    ///
+    /// Screens without a rule use the first of [layouts][Config::layouts]. Workspaces keep
+    /// whichever layout they are using when they are later moved to a different screen.
+    ///
+    /// # Constraints
+    /// Each symbol must match one of the configured [layouts][Config::layouts]
+    pub screen_layouts: Vec<ScreenLayout>,
+
+    /// the index of the workspace shown on each screen at startup, indexed by screen
+    ///
+    /// Screens without an entry are given the lowest indexed workspace that is not already
+    /// visible. Leaving this empty shows workspaces on screens in order.
+    ///
+    /// # Constraints
+    /// Each index must be unique and refer to one of the configured
+    /// [workspaces][Config::workspaces]
+    pub initial_workspaces: Vec<usize>,
+
+    /// the border style of the focused client: either a solid color as a hex literal or a
+    /// gradient between two colors
+    pub focused_border: BorderStyle,
+    /// the border style of unfocused clients: either a solid color as a hex literal or a
+    /// gradient between two colors
+    pub unfocused_border: BorderStyle,
+    /// the border color for unfocused clients that have set the urgency hint as a hex literal
+    pub urgent_border: Color,
     /// the border width of each window in pixels
     pub border_px: u32,
     /// the gap between tiled windows in pixels
     pub gap_px: u32,
+    /// the gap between tiled windows and the edge of the screen in pixels
+    pub outer_gap_px: u32,
     /// the percentage of the screen to grow the main region by when incrementing
     pub main_ratio_step: f32,
+    /// the smallest ratio that the main region of a layout can be shrunk to
+    pub min_main_ratio: f32,
+    /// the largest ratio that the main region of a layout can be grown to
+    pub max_main_ratio: f32,
     /// whether or not space should be reserved for a status bar
     pub show_bar: bool,
     /// whether or not the reserved space for a status bar is at the top of the screen
     pub top_bar: bool,
     /// the height of the space to be reserved for a status bar in pixels
     pub bar_height: u32,
+    /// the gap in pixels between a status bar and the edges of the screen and tiled clients
+    pub bar_margin_px: u32,
+    /// per screen overrides for [show_bar][Config::show_bar] and [top_bar][Config::top_bar]
+    ///
+    /// Screens without a rule use the global settings. Screens that do not show a bar use their
+    /// full height for laying out clients. Pass the same rules to
+    /// [StatusBar::set_screen_bars][crate::draw::StatusBar::set_screen_bars] so that the built in
+    /// status bar is only drawn where space has been reserved for it.
+    pub screen_bars: Vec<ScreenBar>,
+    /// the maximum time in milliseconds between two presses of a mouse button for them to be
+    /// treated as a double click
+    pub double_click_ms: u64,
+    /// the distance in pixels the mouse must move from where a button was pressed before motion
+    /// bindings are run
+    pub drag_threshold_px: u32,
+    /// whether or not moving the mouse over a client gives it focus
+    pub focus_follows_mouse: bool,
+    /// whether or not the cursor should be moved to the center of a client when it gains focus
+    pub focus_warps_cursor: bool,
+    /// whether or not cycling workspaces wraps around at the first and last workspace
+    pub workspace_switch_wraps: bool,
+    /// whether or not minimized clients should be restored when switching to their workspace
+    pub restore_minimized: bool,
+    /// whether or not tiled clients should be sized according to their WM_NORMAL_HINTS (minimum
+    /// size, resize increments and aspect ratio), centering them within the space given to them
+    /// by the layout if they can not fill it
+    pub respect_size_hints: bool,
+    /// whether or not tiled clients should be shrunk to a multiple of their resize increments
+    /// (such as the character cell size of a terminal), centering them within the space given to
+    /// them by the layout. This is implied by `respect_size_hints`.
+    pub respect_resize_increments: bool,
+    /// the opacity (between 0.0 and 1.0) to set on clients when they lose focus, with the focused
+    /// client always being fully opaque. None to leave client opacity alone.
+    ///
+    /// This requires a running compositor to have any visible effect. Fullscreen clients and those
+    /// marked using [set_client_dim_exempt][1] are never dimmed.
+    ///
+    /// [1]: crate::core::manager::WindowManager::set_client_dim_exempt
+    pub unfocused_opacity: Option<f64>,
+    /// what to do with transient windows that are still open when their parent is closed
+    pub orphaned_transients: TransientPolicy,
+    /// which client on a workspace should gain focus when the focused client is closed
+    pub focus_after_close: FocusAfterClose,
+    /// how long in milliseconds to wait for a client to close after asking it to before it is
+    /// forcibly killed by [close_focused][crate::core::manager::WindowManager::close_focused]
+    pub close_timeout_ms: u64,
+    /// layouts to switch a workspace to when the number of clients on it changes
+    ///
+    /// The first rule matching the new number of clients is used. Manually changing the layout of
+    /// a workspace takes precedence until the number of clients on it next changes.
+    pub auto_layout_rules: Vec<AutoLayoutRule>,
+    /// named clients that can be shown and hidden on the active workspace using
+    /// [toggle_scratchpad][crate::core::manager::WindowManager::toggle_scratchpad]
+    pub scratchpads: Vec<Scratchpad>,
+}
+
+/// How a [WindowRule] identifies the clients that it applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WindowMatch {
+    /// One of the WM_CLASS strings of the client is exactly this string
+    Class(String),
+    /// One of the WM_CLASS strings of the client contains this string
+    ClassContains(String),
+    /// The WM_NAME of the client is exactly this string
+    Name(String),
+    /// The WM_NAME of the client contains this string
+    NameContains(String),
+}
+
+impl WindowMatch {
+    /// Whether or not the given [Client] is matched
+    pub fn matches(&self, client: &Client) -> bool {
+        match self {
+            Self::Class(s) => client.wm_class.iter().any(|c| c == s),
+            Self::ClassContains(s) => client.wm_class.iter().any(|c| c.contains(s.as_str())),
+            Self::Name(s) => &client.wm_name == s,
+            Self::NameContains(s) => client.wm_name.contains(s.as_str()),
+        }
+    }
+}
+
+/// A rule for the initial placement of new clients.
+///
+/// # Example
+/// ```
+/// use penrose::core::{
+///     config::{WindowMatch, WindowRule},
+///     data_types::Region,
+/// };
+///
+/// let rules = vec![
+///     WindowRule::new(WindowMatch::Class("Firefox".into())).on_workspace(1),
+///     WindowRule::new(WindowMatch::NameContains("Picture-in-Picture".into()))
+///         .with_region(Region::new(1400, 800, 480, 270)),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowRule {
+    /// Which clients this rule applies to
+    pub matches: WindowMatch,
+    /// The index of the workspace matching clients should be placed on
+    pub workspace: Option<usize>,
+    /// Whether or not matching clients should be forced to float
+    pub floating: bool,
+    /// The initial position of matching clients: implies floating
+    pub region: Option<Region>,
+}
+
+impl WindowRule {
+    /// Create a new rule that has no effect on the clients that it matches
+    pub fn new(matches: WindowMatch) -> Self {
+        Self {
+            matches,
+            workspace: None,
+            floating: false,
+            region: None,
+        }
+    }
+
+    /// Place matching clients on the workspace with the given index
+    pub fn on_workspace(mut self, wix: usize) -> Self {
+        self.workspace = Some(wix);
+        self
+    }
+
+    /// Force matching clients to float
+    pub fn float(mut self) -> Self {
+        self.floating = true;
+        self
+    }
+
+    /// Float matching clients and give them an initial position
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.floating = true;
+        self.region = Some(region);
+        self
+    }
+}
+
+/// Switch a workspace to the [Layout] with the given symbol when the number of clients on it
+/// falls within a range.
+///
+/// # Example
+/// ```
+/// use penrose::core::config::AutoLayoutRule;
+///
+/// let rules = vec![
+///     AutoLayoutRule::new(0, Some(0), "[float]"),
+///     AutoLayoutRule::new(1, Some(1), "[side]"),
+///     AutoLayoutRule::new(2, None, "[mono]"),
+/// ];
+///
+/// assert!(rules[2].matches(5));
+/// assert!(!rules[1].matches(2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AutoLayoutRule {
+    /// The minimum number of clients this rule applies to
+    pub min_clients: usize,
+    /// The maximum number of clients this rule applies to (inclusive). None for no upper limit
+    pub max_clients: Option<usize>,
+    /// The symbol of the layout to switch to
+    pub symbol: String,
+}
+
+impl AutoLayoutRule {
+    /// Create a new rule for the given range of client counts
+    pub fn new(min_clients: usize, max_clients: Option<usize>, symbol: impl Into<String>) -> Self {
+        Self {
+            min_clients,
+            max_clients,
+            symbol: symbol.into(),
+        }
+    }
+
+    /// Whether or not this rule applies to a workspace holding `n_clients` clients
+    pub fn matches(&self, n_clients: usize) -> bool {
+        n_clients >= self.min_clients && !matches!(self.max_clients, Some(max) if n_clients > max)
+    }
+}
+
+/// The default [Layout] for a screen.
+///
+/// Workspaces switch to this layout the first time they are shown on the screen with the given
+/// index and keep whichever layout they are using if they are later moved to another screen.
+///
+/// # Example
+/// ```
+/// use penrose::core::config::ScreenLayout;
+///
+/// // Use the "[bot]" layout on the second (vertical) monitor
+/// let screen_layouts = vec![ScreenLayout::new(1, "[bot]")];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScreenLayout {
+    /// The index of the screen this rule applies to
+    pub screen: usize,
+    /// The symbol of the layout to use
+    pub symbol: String,
+}
+
+impl ScreenLayout {
+    /// Create a new default layout for the screen at the given index
+    pub fn new(screen: usize, symbol: impl Into<String>) -> Self {
+        Self {
+            screen,
+            symbol: symbol.into(),
+        }
+    }
+}
+
+/// The status bar settings for the screen with a given index, overriding
+/// [show_bar][Config::show_bar] and [top_bar][Config::top_bar].
+///
+/// # Example
+/// ```
+/// use penrose::core::config::ScreenBar;
+///
+/// // Only reserve space for a bar on the primary screen
+/// let screen_bars = vec![ScreenBar::new(1, false, true)];
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScreenBar {
+    /// The index of the screen this rule applies to
+    pub screen: usize,
+    /// Whether or not space should be reserved for a status bar on this screen
+    pub show_bar: bool,
+    /// Whether or not the reserved space is at the top of this screen
+    pub top_bar: bool,
+}
+
+impl ScreenBar {
+    /// Create a new set of status bar settings for the screen at the given index
+    pub fn new(screen: usize, show_bar: bool, top_bar: bool) -> Self {
+        Self {
+            screen,
+            show_bar,
+            top_bar,
+        }
+    }
+}
+
+/// A named client that can be summoned to and dismissed from the active workspace.
+///
+/// The first new client matching `matches` becomes the scratchpad. If there is no such client when
+/// the scratchpad is toggled then `spawn` (if set) is run to start one.
+///
+/// # Example
+/// ```
+/// use penrose::core::config::{Scratchpad, WindowMatch};
+///
+/// let scratchpads = vec![
+///     Scratchpad::new("term", WindowMatch::Class("scratch-term".into()))
+///         .spawn("st -c scratch-term"),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scratchpad {
+    /// The name used to toggle this scratchpad
+    pub name: String,
+    /// Which client is used for this scratchpad
+    pub matches: WindowMatch,
+    /// A command to run to start the client if it is not already running
+    pub spawn: Option<String>,
+}
+
+impl Scratchpad {
+    /// Create a new scratchpad using the first client that matches
+    pub fn new(name: impl Into<String>, matches: WindowMatch) -> Self {
+        Self {
+            name: name.into(),
+            matches,
+            spawn: None,
+        }
+    }
+
+    /// Run the given command to start the client when the scratchpad is first toggled
+    pub fn spawn(mut self, cmd: impl Into<String>) -> Self {
+        self.spawn = Some(cmd.into());
+        self
+    }
+}
+
+/// How the [WindowManager][crate::core::manager::WindowManager] handles transient windows
+/// (dialogs etc) whose parent window has been closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TransientPolicy {
+    /// Ask the transient windows to close along with their parent
+    Close,
+    /// Leave the transient windows open, floating in the center of their screen
+    Float,
+}
+
+/// Which client the [WindowManager][crate::core::manager::WindowManager] focuses when the focused
+/// client on a workspace is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FocusAfterClose {
+    /// Leave focus where the workspace stack puts it: the client that took the place of the
+    /// closed client, or the client above it if the closed client was one of the last two in
+    /// the stack (the default)
+    Clamped,
+    /// The client that took the place of the closed client in the stack, or the new last client
+    /// if the closed client was last
+    NextInStack,
+    /// The client at the head of the stack
+    Master,
+    /// The most recently focused client still on the workspace
+    PreviousFocused,
 }
 
 impl Default for Config {
@@ -66,28 +450,79 @@ impl Default for Config {
                 .into_iter()
                 .map(|s| s.to_string())
                 .collect(),
+            floating_window_types: AUTO_FLOAT_WINDOW_TYPES
+                .iter()
+                .map(|a| a.as_ref().to_string())
+                .collect(),
+            window_rules: vec![],
             layouts: vec![
                 Layout::new("[side]", LayoutConf::default(), side_stack, 1, 0.6),
                 Layout::floating("[----]"),
             ],
+            screen_layouts: vec![],
+            initial_workspaces: vec![],
             focused_border: "#cc241d".try_into().unwrap(),
             unfocused_border: "#3c3836".try_into().unwrap(),
+            urgent_border: "#d65d0e".try_into().unwrap(),
             border_px: 2,
             gap_px: 5,
+            outer_gap_px: 5,
             main_ratio_step: 0.05,
+            min_main_ratio: 0.1,
+            max_main_ratio: 0.9,
             show_bar: true,
             top_bar: true,
             bar_height: 18,
+            bar_margin_px: 0,
+            screen_bars: vec![],
+            double_click_ms: 300,
+            drag_threshold_px: 3,
+            focus_follows_mouse: true,
+            focus_warps_cursor: true,
+            workspace_switch_wraps: true,
+            restore_minimized: false,
+            respect_size_hints: false,
+            respect_resize_increments: false,
+            unfocused_opacity: None,
+            orphaned_transients: TransientPolicy::Close,
+            focus_after_close: FocusAfterClose::Clamped,
+            close_timeout_ms: 3000,
+            auto_layout_rules: vec![],
+            scratchpads: vec![],
         }
     }
 }
 
 impl Config {
+    /// Start building a new Config from the default values using a [ConfigBuilder]
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
     /// Create a range from 1 -> n_workspaces for use in keybindings
     pub fn ws_range(&self) -> std::ops::Range<usize> {
         1..(self.workspaces.len() + 1)
     }
 
+    /// The height of the space reserved for a status bar, including its margin above and below
+    pub fn bar_reserved_px(&self) -> u32 {
+        self.bar_height + 2 * self.bar_margin_px
+    }
+
+    /// The configured [layouts][Config::layouts] with their main ratio bounds narrowed so that
+    /// the main region stays between [min_main_ratio][Config::min_main_ratio] and
+    /// [max_main_ratio][Config::max_main_ratio].
+    pub fn bounded_layouts(&self) -> Vec<Layout> {
+        self.layouts
+            .iter()
+            .map(|l| {
+                let (min, max) = l.ratio_bounds();
+                l.clone()
+                    .with_ratio_bounds(min.max(self.min_main_ratio), max.min(self.max_main_ratio))
+            })
+            .collect()
+    }
+
     /// Validates the configuration and returns an error if it is invalid
     pub fn validate(self) -> Result<Self, PenroseError> {
         if self.workspaces.is_empty() {
@@ -96,18 +531,365 @@ impl Config {
             ));
         }
 
+        let mut seen = HashSet::new();
+        if let Some(name) = self.workspaces.iter().find(|name| !seen.insert(*name)) {
+            return Err(PenroseError::InvalidConfig(format!(
+                "duplicate workspace name: {}",
+                name
+            )));
+        }
+
         if self.layouts.is_empty() {
             return Err(PenroseError::InvalidConfig(
                 "layouts must not be empty".to_string(),
             ));
         }
 
+        let symbols: HashSet<&str> = self.layouts.iter().map(|l| l.symbol.as_str()).collect();
+        if let Some(rule) = self
+            .screen_layouts
+            .iter()
+            .find(|rule| !symbols.contains(rule.symbol.as_str()))
+        {
+            return Err(PenroseError::InvalidConfig(format!(
+                "unknown layout for screen {}: {}",
+                rule.screen, rule.symbol
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        if let Some(wix) = self
+            .initial_workspaces
+            .iter()
+            .find(|&&wix| wix >= self.workspaces.len() || !seen.insert(wix))
+        {
+            return Err(PenroseError::InvalidConfig(format!(
+                "invalid initial workspace: {} is out of range or repeated",
+                wix
+            )));
+        }
+
         if !(0.0..=1.0).contains(&self.main_ratio_step) {
             return Err(PenroseError::InvalidConfig(
                 "main_ratio_step must be between 0.0 and 1.0".to_string(),
             ));
         }
 
+        let ratios = [self.min_main_ratio, self.max_main_ratio];
+        if !ratios.iter().all(|r| (0.0..=1.0).contains(r)) {
+            return Err(PenroseError::InvalidConfig(
+                "min_main_ratio and max_main_ratio must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        if self.min_main_ratio > self.max_main_ratio {
+            return Err(PenroseError::InvalidConfig(
+                "min_main_ratio must not be greater than max_main_ratio".to_string(),
+            ));
+        }
+
+        if matches!(self.unfocused_opacity, Some(o) if !(0.0..=1.0).contains(&o)) {
+            return Err(PenroseError::InvalidConfig(
+                "unfocused_opacity must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
         Ok(self)
     }
+
+    /// Parse a Config from a TOML document and then [validate][Config::validate] it.
+    ///
+    /// Fields missing from the document are set to their default values.
+    #[cfg(feature = "serde")]
+    pub fn from_toml_str(s: &str) -> Result<Self, PenroseError> {
+        toml::from_str::<Self>(s)?.validate()
+    }
+}
+
+/// A fluent builder for [Config] instances.
+///
+/// All values start from [Config::default] and the resulting config is checked using
+/// [Config::validate] when it is built. Fields without a dedicated setter can be modified
+/// directly on the returned [Config].
+///
+/// # Example
+/// ```
+/// use penrose::core::config::ConfigBuilder;
+///
+/// let config = ConfigBuilder::new()
+///     .workspaces(vec!["web", "dev", "chat"])
+///     .gap_px(0)
+///     .show_bar(false)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(config.workspaces, vec!["web", "dev", "chat"]);
+///
+/// assert!(ConfigBuilder::new().unfocused_border("not a color").build().is_err());
+/// ```
+#[derive(Debug)]
+pub struct ConfigBuilder {
+    inner: Config,
+    error: Option<PenroseError>,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! __config_setters {
+    { $($(#[$attr:meta])* $field:ident: $ty:ty;)+ } => {
+        $(
+            $(#[$attr])*
+            pub fn $field(mut self, val: $ty) -> Self {
+                self.inner.$field = val;
+                self
+            }
+        )+
+    }
+}
+
+macro_rules! __config_color_setters {
+    { $($(#[$attr:meta])* $field:ident: $ty:ty;)+ } => {
+        $(
+            $(#[$attr])*
+            pub fn $field<C>(mut self, color: C) -> Self
+            where
+                C: TryInto<$ty>,
+                C::Error: fmt::Display,
+            {
+                match color.try_into() {
+                    Ok(c) => self.inner.$field = c,
+                    Err(e) => self.set_error(format!("invalid {}: {}", stringify!($field), e)),
+                }
+                self
+            }
+        )+
+    }
+}
+
+impl ConfigBuilder {
+    /// Create a new builder starting from the default [Config]
+    pub fn new() -> Self {
+        Self {
+            inner: Config::default(),
+            error: None,
+        }
+    }
+
+    /// Validate and return the resulting [Config].
+    ///
+    /// # Errors
+    /// Fails if any of the setters were given an invalid value or if the resulting config does
+    /// not pass [Config::validate].
+    pub fn build(self) -> Result<Config, PenroseError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => self.inner.validate(),
+        }
+    }
+
+    // Only the first error is reported from build
+    fn set_error(&mut self, msg: String) {
+        if self.error.is_none() {
+            self.error = Some(PenroseError::InvalidConfig(msg));
+        }
+    }
+
+    /// Set the initial workspace names
+    pub fn workspaces<S>(mut self, names: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.inner.workspaces = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the window classes that will always be considered floating
+    pub fn floating_classes<S>(mut self, classes: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.inner.floating_classes = classes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    __config_setters! {
+        /// Set the [Layout]s available on each workspace
+        layouts: Vec<Layout>;
+        /// Set the rules applied to newly mapped clients
+        window_rules: Vec<WindowRule>;
+        /// Set the default [Layout] for workspaces first shown on each screen
+        screen_layouts: Vec<ScreenLayout>;
+        /// Set the index of the workspace shown on each screen at startup
+        initial_workspaces: Vec<usize>;
+        /// Set the border width of each window in pixels
+        border_px: u32;
+        /// Set the gap between tiled windows in pixels
+        gap_px: u32;
+        /// Set the gap between tiled windows and the edge of the screen in pixels
+        outer_gap_px: u32;
+        /// Set the amount to grow or shrink the main region by when changing the main ratio
+        main_ratio_step: f32;
+        /// Set the smallest ratio that the main region of a layout can be shrunk to
+        min_main_ratio: f32;
+        /// Set the largest ratio that the main region of a layout can be grown to
+        max_main_ratio: f32;
+        /// Set whether or not space should be reserved for a status bar
+        show_bar: bool;
+        /// Set whether or not the space reserved for a status bar is at the top of the screen
+        top_bar: bool;
+        /// Set the height of the space reserved for a status bar in pixels
+        bar_height: u32;
+        /// Set the gap in pixels between a status bar and the edges of the screen
+        bar_margin_px: u32;
+        /// Set the per screen overrides for whether or not a status bar is shown and where
+        screen_bars: Vec<ScreenBar>;
+        /// Set whether or not moving the mouse over a client gives it focus
+        focus_follows_mouse: bool;
+        /// Set whether or not the cursor is moved to the center of a client when it gains focus
+        focus_warps_cursor: bool;
+        /// Set whether or not cycling workspaces wraps at the first and last workspace
+        workspace_switch_wraps: bool;
+        /// Set whether or not tiled clients are sized according to their WM_NORMAL_HINTS
+        respect_size_hints: bool;
+        /// Set whether or not tiled clients are snapped to their resize increments
+        respect_resize_increments: bool;
+        /// Set the opacity of unfocused clients (None to leave client opacity alone)
+        unfocused_opacity: Option<f64>;
+    }
+
+    __config_color_setters! {
+        /// Set the border style of the focused client
+        focused_border: BorderStyle;
+        /// Set the border style of unfocused clients
+        unfocused_border: BorderStyle;
+        /// Set the border color of unfocused clients that have set the urgency hint
+        urgent_border: Color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_workspaces(names: &[&str]) -> Config {
+        Config {
+            workspaces: names.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn duplicate_workspace_names_are_invalid() {
+        match config_with_workspaces(&["1", "2", "1"]).validate() {
+            Err(PenroseError::InvalidConfig(msg)) => assert_eq!(msg, "duplicate workspace name: 1"),
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unique_workspace_names_are_valid() {
+        assert!(config_with_workspaces(&["1", "2", "3"]).validate().is_ok());
+    }
+
+    #[test]
+    fn builder_overrides_only_the_given_fields() {
+        let conf = Config::builder()
+            .border_px(4)
+            .workspaces(vec!["a", "b"])
+            .focused_border("#ebdbb2")
+            .build()
+            .unwrap();
+
+        let expected = Config {
+            border_px: 4,
+            workspaces: vec!["a".to_string(), "b".to_string()],
+            focused_border: BorderStyle::try_from("#ebdbb2").unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(conf, expected);
+    }
+
+    test_cases! {
+        builder_rejects_invalid_configs;
+        args: (builder: ConfigBuilder);
+
+        case: invalid_color => (Config::builder().urgent_border("#zzzzzz"));
+        case: invalid_border_style => (Config::builder().focused_border("#zzzzzz"));
+        case: no_workspaces => (Config::builder().workspaces(Vec::<String>::new()));
+        case: no_layouts => (Config::builder().layouts(vec![]));
+        case: bad_ratio_step => (Config::builder().main_ratio_step(2.0));
+        case: bad_max_main_ratio => (Config::builder().max_main_ratio(1.5));
+        case: min_main_ratio_above_max => (Config::builder().min_main_ratio(0.8).max_main_ratio(0.2));
+        case: bad_unfocused_opacity => (Config::builder().unfocused_opacity(Some(1.5)));
+        case: initial_workspace_out_of_range => (Config::builder().initial_workspaces(vec![0, 42]));
+        case: repeated_initial_workspace => (Config::builder().initial_workspaces(vec![1, 1]));
+        case: unknown_screen_layout => (Config::builder().screen_layouts(vec![ScreenLayout::new(1, "[nope]")]));
+
+        body: {
+            assert!(builder.build().is_err());
+        }
+    }
+
+    #[test]
+    fn bar_reserved_px_includes_margin() {
+        let conf = Config {
+            bar_height: 18,
+            bar_margin_px: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(conf.bar_reserved_px(), 26);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_can_be_parsed_from_toml() {
+        let conf = Config::from_toml_str(
+            r##"
+            workspaces = ["web", "dev", "chat"]
+            border_px = 4
+            focused_border = "#ebdbb2"
+
+            [unfocused_border]
+            from = "#282828"
+            to = "#3c3836"
+            direction = "Vertical"
+
+            [[screen_layouts]]
+            screen = 1
+            symbol = "[----]"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(conf.workspaces, vec!["web", "dev", "chat"]);
+        assert_eq!(conf.border_px, 4);
+        assert_eq!(
+            conf.focused_border,
+            BorderStyle::try_from("#ebdbb2").unwrap()
+        );
+        assert_eq!(
+            conf.unfocused_border,
+            BorderStyle::gradient(
+                0x282828FF,
+                0x3C3836FF,
+                crate::draw::GradientDirection::Vertical
+            )
+        );
+        assert_eq!(conf.gap_px, Config::default().gap_px);
+        assert_eq!(conf.layouts.len(), 2);
+        assert_eq!(conf.screen_layouts, vec![ScreenLayout::new(1, "[----]")]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_toml_config_is_rejected() {
+        assert!(Config::from_toml_str("workspaces = []").is_err());
+        assert!(Config::from_toml_str("border_px = \"wide\"").is_err());
+    }
 }
@@ -1,11 +1,58 @@
 //! Simple data types and enums
 use crate::{
-    core::xconnection::{Atom, Xid},
+    core::{
+        helpers::json_string,
+        xconnection::{Atom, Xid},
+    },
     Result,
 };
 
 /// Output of a Layout function: the new position a window should take
-pub type ResizeAction = (Xid, Option<Region>);
+///
+/// A `ResizeAction` can be created from an `(Xid, Option<Region>)` tuple using `From` for
+/// compatability with layout functions written before stacking hints were supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResizeAction {
+    /// The client being positioned
+    pub id: Xid,
+    /// The region the client should occupy or `None` if it should be hidden
+    pub region: Option<Region>,
+    /// An optional change to make to the stacking order of the client once all clients in the
+    /// layout have been positioned
+    pub stacking: Option<StackHint>,
+}
+
+impl ResizeAction {
+    /// Create a new ResizeAction with no stacking hint
+    pub fn new(id: Xid, region: Option<Region>) -> Self {
+        Self {
+            id,
+            region,
+            stacking: None,
+        }
+    }
+
+    /// Set the stacking hint for this action
+    pub fn with_stacking(mut self, hint: StackHint) -> Self {
+        self.stacking = Some(hint);
+        self
+    }
+}
+
+impl From<(Xid, Option<Region>)> for ResizeAction {
+    fn from(action: (Xid, Option<Region>)) -> Self {
+        Self::new(action.0, action.1)
+    }
+}
+
+/// Where a client should be placed in the stacking order after a layout has been applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StackHint {
+    /// Raise the client above its peers
+    Top,
+    /// Lower the client below its peers
+    Bottom,
+}
 
 /// An X window ID
 pub type WinId = u32;
@@ -39,14 +86,18 @@ pub enum RelativePosition {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Point {
     /// An absolute x coordinate relative to the root window
-    pub x: u32,
+    ///
+    /// This may be negative when the pointer is to the left of the root window origin.
+    pub x: i32,
     /// An absolute y coordinate relative to the root window
-    pub y: u32,
+    ///
+    /// This may be negative when the pointer is above the root window origin.
+    pub y: i32,
 }
 
 impl Point {
     /// Create a new Point.
-    pub fn new(x: u32, y: u32) -> Self {
+    pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
 }
@@ -59,6 +110,56 @@ impl Default for Point {
 
 /* Argument enums */
 
+/// A half or quarter of the screen that a floating client can be snapped to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SnapRegion {
+    /// The left half of the screen
+    LeftHalf,
+    /// The right half of the screen
+    RightHalf,
+    /// The top half of the screen
+    TopHalf,
+    /// The bottom half of the screen
+    BottomHalf,
+    /// The top left quarter of the screen
+    TopLeft,
+    /// The top right quarter of the screen
+    TopRight,
+    /// The bottom left quarter of the screen
+    BottomLeft,
+    /// The bottom right quarter of the screen
+    BottomRight,
+}
+
+impl SnapRegion {
+    /// The sub-region of `r` that this SnapRegion refers to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Region, SnapRegion};
+    ///
+    /// let r = Region::new(0, 0, 800, 600);
+    ///
+    /// assert_eq!(SnapRegion::LeftHalf.region_within(&r), Region::new(0, 0, 400, 600));
+    /// assert_eq!(SnapRegion::TopRight.region_within(&r), Region::new(400, 0, 400, 300));
+    /// ```
+    pub fn region_within(&self, r: &Region) -> Region {
+        let [tl, tr, bl, br] = r.quadrants();
+
+        match self {
+            Self::LeftHalf => r.as_columns(2)[0],
+            Self::RightHalf => r.as_columns(2)[1],
+            Self::TopHalf => r.as_rows(2)[0],
+            Self::BottomHalf => r.as_rows(2)[1],
+            Self::TopLeft => tl,
+            Self::TopRight => tr,
+            Self::BottomLeft => bl,
+            Self::BottomRight => br,
+        }
+    }
+}
+
 /// Increment / decrement a value
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Change {
@@ -79,8 +180,22 @@ pub enum Border {
     Unfocused,
 }
 
+/// An edge of a [Region]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// the top edge
+    Top,
+    /// the bottom edge
+    Bottom,
+    /// the left edge
+    Left,
+    /// the right edge
+    Right,
+}
+
 /// An X window / screen position: top left corner + extent
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Region {
     /// The x-coordinate of the top left corner of this region
     pub x: u32,
@@ -194,7 +309,10 @@ impl Region {
     /// assert!(!r1.contains_point(&Point::new(0, 0)));
     /// ```
     pub fn contains_point(&self, p: &Point) -> bool {
-        (self.x..(self.x + self.w)).contains(&p.x) && (self.y..(self.y + self.h)).contains(&p.y)
+        let (x, y) = (self.x as i64, self.y as i64);
+        let (w, h) = (self.w as i64, self.h as i64);
+
+        (x..(x + w)).contains(&(p.x as i64)) && (y..(y + h)).contains(&(p.y as i64))
     }
 
     /// Center this region inside of `enclosing`.
@@ -235,6 +353,10 @@ impl Region {
 
     /// Split this `Region` into evenly sized rows.
     ///
+    /// If the height of this region is not evenly divisible by `n_rows`, the remaining pixels
+    /// are distributed one at a time to the first few rows so that the rows exactly cover
+    /// `self`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -252,14 +374,17 @@ impl Region {
         if n_rows <= 1 {
             return vec![*self];
         }
-        let h = self.h / n_rows as u32;
-        (0..n_rows)
-            .map(|n| Region::new(self.x, (self.y + n as u32 * h) as u32, self.w, h))
+        split_evenly(self.y, self.h, n_rows)
+            .map(|(y, h)| Region::new(self.x, y, self.w, h))
             .collect()
     }
 
     /// Split this `Region` into evenly sized columns.
     ///
+    /// If the width of this region is not evenly divisible by `n_columns`, the remaining pixels
+    /// are distributed one at a time to the first few columns so that the columns exactly cover
+    /// `self`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -277,9 +402,8 @@ impl Region {
         if n_columns <= 1 {
             return vec![*self];
         }
-        let w = self.w / n_columns as u32;
-        (0..n_columns)
-            .map(|n| Region::new((self.x + n as u32 * w) as u32, self.y, w, self.h))
+        split_evenly(self.x, self.w, n_columns)
+            .map(|(x, w)| Region::new(x, self.y, w, self.h))
             .collect()
     }
 
@@ -362,4 +486,582 @@ impl Region {
             ))
         }
     }
+
+    /// Reserve a band of `px` pixels along the given edge of this region, returning the
+    /// reserved band and the remaining usable region (in that order).
+    ///
+    /// Reserving more than the size of the region reserves all of it, leaving a zero area
+    /// usable region positioned at the opposite edge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Edge, Region};
+    ///
+    /// let r = Region::new(10, 10, 100, 50);
+    /// let (bar, usable) = r.split_reserving(Edge::Top, 18);
+    ///
+    /// assert_eq!(bar, Region::new(10, 10, 100, 18));
+    /// assert_eq!(usable, Region::new(10, 28, 100, 32));
+    ///
+    /// let (_, usable) = r.split_reserving(Edge::Left, 200);
+    /// assert_eq!(usable, Region::new(110, 10, 0, 50));
+    /// ```
+    pub fn split_reserving(&self, edge: Edge, px: u32) -> (Self, Self) {
+        let (x, y, w, h) = self.values();
+
+        match edge {
+            Edge::Top => {
+                let px = px.min(h);
+                (Self::new(x, y, w, px), Self::new(x, y + px, w, h - px))
+            }
+            Edge::Bottom => {
+                let px = px.min(h);
+                (Self::new(x, y + h - px, w, px), Self::new(x, y, w, h - px))
+            }
+            Edge::Left => {
+                let px = px.min(w);
+                (Self::new(x, y, px, h), Self::new(x + px, y, w - px, h))
+            }
+            Edge::Right => {
+                let px = px.min(w);
+                (Self::new(x + w - px, y, px, h), Self::new(x, y, w - px, h))
+            }
+        }
+    }
+
+    /// The point at the center of this `Region`, rounded towards the top left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Point, Region};
+    ///
+    /// assert_eq!(Region::new(10, 20, 100, 51).center(), Point::new(60, 45));
+    /// ```
+    pub fn center(&self) -> Point {
+        Point::new((self.x + self.w / 2) as i32, (self.y + self.h / 2) as i32)
+    }
+
+    /// Move this `Region` so that it lies within `bounds`, shrinking it only if it is larger
+    /// than `bounds`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Region;
+    ///
+    /// let bounds = Region::new(0, 0, 100, 100);
+    ///
+    /// assert_eq!(Region::new(80, 10, 40, 40).clamped_within(&bounds), Region::new(60, 10, 40, 40));
+    /// assert_eq!(Region::new(10, 10, 200, 40).clamped_within(&bounds), Region::new(0, 10, 100, 40));
+    /// ```
+    pub fn clamped_within(&self, bounds: &Region) -> Region {
+        let w = self.w.min(bounds.w);
+        let h = self.h.min(bounds.h);
+        let x = self.x.max(bounds.x).min(bounds.x + bounds.w - w);
+        let y = self.y.max(bounds.y).min(bounds.y + bounds.h - h);
+
+        Region::new(x, y, w, h)
+    }
+
+    /// Inset all four edges of this `Region` by `px`.
+    ///
+    /// If the region is too small to be shrunk by `px` on both sides then each axis is shrunk as
+    /// far as possible while leaving it at least 1 pixel in size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Region;
+    ///
+    /// let r = Region::new(10, 20, 100, 50);
+    ///
+    /// assert_eq!(r.shrink_by(5), Region::new(15, 25, 90, 40));
+    /// assert_eq!(r.shrink_by(30), Region::new(40, 44, 40, 2));
+    /// ```
+    pub fn shrink_by(&self, px: u32) -> Region {
+        let pad_x = px.min(self.w.saturating_sub(1) / 2);
+        let pad_y = px.min(self.h.saturating_sub(1) / 2);
+
+        Region::new(
+            self.x + pad_x,
+            self.y + pad_y,
+            self.w - 2 * pad_x,
+            self.h - 2 * pad_y,
+        )
+    }
+
+    /// Move all four edges of this `Region` outwards by `px`.
+    ///
+    /// The left and top edges stop at 0 rather than underflowing, so a region close to the
+    /// origin grows by less than `px` on those sides.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Region;
+    ///
+    /// assert_eq!(Region::new(10, 20, 100, 50).grow_by(5), Region::new(5, 15, 110, 60));
+    /// assert_eq!(Region::new(2, 20, 100, 50).grow_by(5), Region::new(0, 15, 107, 60));
+    /// ```
+    pub fn grow_by(&self, px: u32) -> Region {
+        let (left, top) = (self.x.min(px), self.y.min(px));
+
+        Region::new(
+            self.x - left,
+            self.y - top,
+            self.w.saturating_add(left).saturating_add(px),
+            self.h.saturating_add(top).saturating_add(px),
+        )
+    }
+
+    /// Split this `Region` into four quadrants around its center point.
+    ///
+    /// Quadrants are returned in the order top-left, top-right, bottom-left, bottom-right. When
+    /// the width or height is odd, the extra pixel is given to the left or top quadrants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Region;
+    ///
+    /// let [tl, tr, bl, br] = Region::new(0, 0, 100, 50).quadrants();
+    ///
+    /// assert_eq!(tl, Region::new(0, 0, 50, 25));
+    /// assert_eq!(tr, Region::new(50, 0, 50, 25));
+    /// assert_eq!(bl, Region::new(0, 25, 50, 25));
+    /// assert_eq!(br, Region::new(50, 25, 50, 25));
+    /// ```
+    pub fn quadrants(&self) -> [Region; 4] {
+        let (w1, h1) = (self.w - self.w / 2, self.h - self.h / 2);
+        let (w2, h2) = (self.w - w1, self.h - h1);
+        let (x2, y2) = (self.x + w1, self.y + h1);
+
+        [
+            Region::new(self.x, self.y, w1, h1),
+            Region::new(x2, self.y, w2, h1),
+            Region::new(self.x, y2, w1, h2),
+            Region::new(x2, y2, w2, h2),
+        ]
+    }
+}
+
+// Split a length starting at 'start' into 'n' (offset, length) pairs, giving one extra pixel to
+// each of the first 'len % n' parts so that together they exactly cover the original length.
+fn split_evenly(start: u32, len: u32, n: u32) -> impl Iterator<Item = (u32, u32)> {
+    let (base, rem) = (len / n, len % n);
+
+    (0..n).map(move |i| {
+        let offset = start + i * base + i.min(rem);
+        let size = if i < rem { base + 1 } else { base };
+
+        (offset, size)
+    })
+}
+
+/// Space at the edges of the root window that has been reserved by a dock or panel using the
+/// _NET_WM_STRUT_PARTIAL or _NET_WM_STRUT properties.
+///
+/// Each edge reserves a band of the given width, measured from that edge of the root window,
+/// between a start and end coordinate (inclusive) along the edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Strut {
+    /// Pixels reserved at the left edge and the (start, end) y coordinates they span
+    pub left: (u32, u32, u32),
+    /// Pixels reserved at the right edge and the (start, end) y coordinates they span
+    pub right: (u32, u32, u32),
+    /// Pixels reserved at the top edge and the (start, end) x coordinates they span
+    pub top: (u32, u32, u32),
+    /// Pixels reserved at the bottom edge and the (start, end) x coordinates they span
+    pub bottom: (u32, u32, u32),
+}
+
+impl Strut {
+    /// Parse the values of a _NET_WM_STRUT_PARTIAL (12 values) or _NET_WM_STRUT (4 values)
+    /// property. Struts set using _NET_WM_STRUT span the full length of each edge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Strut;
+    ///
+    /// let partial = Strut::from_cardinals(&[0, 0, 30, 0, 0, 0, 0, 0, 0, 799, 0, 0]).unwrap();
+    /// assert_eq!(partial.top, (30, 0, 799));
+    ///
+    /// let full = Strut::from_cardinals(&[0, 0, 30, 0]).unwrap();
+    /// assert_eq!(full.top, (30, 0, u32::MAX));
+    ///
+    /// assert!(Strut::from_cardinals(&[1, 2, 3]).is_none());
+    /// ```
+    pub fn from_cardinals(vals: &[u32]) -> Option<Self> {
+        match *vals {
+            [l, r, t, b, ly0, ly1, ry0, ry1, tx0, tx1, bx0, bx1] => Some(Self {
+                left: (l, ly0, ly1),
+                right: (r, ry0, ry1),
+                top: (t, tx0, tx1),
+                bottom: (b, bx0, bx1),
+            }),
+            [l, r, t, b] => Some(Self {
+                left: (l, 0, u32::MAX),
+                right: (r, 0, u32::MAX),
+                top: (t, 0, u32::MAX),
+                bottom: (b, 0, u32::MAX),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Shrink `region` (a screen within `root`) so that it does not overlap the space reserved
+    /// by this strut.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Region, Strut};
+    ///
+    /// let root = Region::new(0, 0, 1600, 600);
+    /// let left = Region::new(0, 0, 800, 600);
+    /// let right = Region::new(800, 0, 800, 600);
+    ///
+    /// // a bar along the top of the left hand screen only
+    /// let s = Strut::from_cardinals(&[0, 0, 30, 0, 0, 0, 0, 0, 0, 799, 0, 0]).unwrap();
+    ///
+    /// assert_eq!(s.reserve_within(left, root), Region::new(0, 30, 800, 570));
+    /// assert_eq!(s.reserve_within(right, root), right);
+    /// ```
+    pub fn reserve_within(&self, region: Region, root: Region) -> Region {
+        let (x, y, w, h) = region.values();
+        let (mut x0, mut y0, mut x1, mut y1) = (x, y, x + w, y + h);
+        let spans = |(_, start, end): (u32, u32, u32), lo: u32, hi: u32| start < hi && end >= lo;
+
+        if self.left.0 > 0 && spans(self.left, y, y + h) {
+            x0 = x0.max(root.x + self.left.0);
+        }
+        if self.right.0 > 0 && spans(self.right, y, y + h) {
+            x1 = x1.min((root.x + root.w).saturating_sub(self.right.0));
+        }
+        if self.top.0 > 0 && spans(self.top, x, x + w) {
+            y0 = y0.max(root.y + self.top.0);
+        }
+        if self.bottom.0 > 0 && spans(self.bottom, x, x + w) {
+            y1 = y1.min((root.y + root.h).saturating_sub(self.bottom.0));
+        }
+
+        if x0 >= x1 || y0 >= y1 {
+            return region; // a strut covering the whole screen is ignored
+        }
+
+        Region::new(x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+/// A read-only copy of the details of a single managed client.
+///
+/// Generated using
+/// [WindowManager::client_snapshot][crate::core::manager::WindowManager::client_snapshot].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientInfo {
+    /// The X window ID of the client
+    pub id: Xid,
+    /// The index of the workspace the client is on
+    pub workspace: usize,
+    /// Whether or not the client currently has focus
+    pub focused: bool,
+    /// Whether or not the client is floating
+    pub floating: bool,
+    /// The first WM_CLASS string of the client
+    pub class: String,
+    /// The WM_NAME of the client
+    pub title: String,
+}
+
+/// The adjustable settings of a single layout, as saved in a [WorkspaceLayoutState]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutState {
+    /// The symbol of the layout these settings belong to
+    pub symbol: String,
+    /// The number of clients in the main area
+    pub max_main: u32,
+    /// The ratio of the main area relative to the secondary area
+    pub ratio: f32,
+}
+
+/// The layout state of a single workspace that can be saved and restored across restarts.
+///
+/// Generated using
+/// [WindowManager::layout_state][crate::core::manager::WindowManager::layout_state].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkspaceLayoutState {
+    /// The name of the workspace
+    pub name: String,
+    /// The symbol of the active layout on the workspace
+    pub active: String,
+    /// The settings of each layout available on the workspace
+    pub layouts: Vec<LayoutState>,
+}
+
+/// The state of a single workspace as reported in a [WmState]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkspaceState {
+    /// The name of the workspace
+    pub name: String,
+    /// Whether or not the workspace has any clients
+    pub occupied: bool,
+    /// Whether or not this is the active workspace
+    pub focused: bool,
+}
+
+/// A snapshot of the [WindowManager][crate::core::manager::WindowManager] state that is of
+/// interest to external status bars.
+///
+/// Generated using [WindowManager::state][crate::core::manager::WindowManager::state].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WmState {
+    /// Every known workspace in order
+    pub workspaces: Vec<WorkspaceState>,
+    /// The symbol of the layout in use on the active workspace
+    pub layout: String,
+    /// The title of the focused client (if there is one)
+    pub focused_title: Option<String>,
+}
+
+impl WmState {
+    /// Render this state as a single line of JSON.
+    ///
+    /// ```
+    /// # use penrose::core::data_types::{WmState, WorkspaceState};
+    /// let state = WmState {
+    ///     workspaces: vec![WorkspaceState { name: "1".into(), occupied: true, focused: true }],
+    ///     layout: "[side]".into(),
+    ///     focused_title: None,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     state.to_json(),
+    ///     r#"{"workspaces":[{"name":"1","occupied":true,"focused":true}],"layout":"[side]","focused_title":null}"#
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        let workspaces: Vec<String> = self
+            .workspaces
+            .iter()
+            .map(|ws| {
+                format!(
+                    r#"{{"name":{},"occupied":{},"focused":{}}}"#,
+                    json_string(&ws.name),
+                    ws.occupied,
+                    ws.focused
+                )
+            })
+            .collect();
+
+        format!(
+            r#"{{"workspaces":[{}],"layout":{},"focused_title":{}}}"#,
+            workspaces.join(","),
+            json_string(&self.layout),
+            self.focused_title
+                .as_deref()
+                .map(json_string)
+                .unwrap_or_else(|| "null".to_string())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    test_cases! {
+        split_reserving;
+        args: (edge: Edge, px: u32, reserved: Region, usable: Region);
+
+        case: top => (Edge::Top, 18, Region::new(10, 20, 100, 18), Region::new(10, 38, 100, 32));
+        case: bottom => (Edge::Bottom, 18, Region::new(10, 52, 100, 18), Region::new(10, 20, 100, 32));
+        case: left => (Edge::Left, 30, Region::new(10, 20, 30, 50), Region::new(40, 20, 70, 50));
+        case: right => (Edge::Right, 30, Region::new(80, 20, 30, 50), Region::new(10, 20, 70, 50));
+        case: nothing => (Edge::Top, 0, Region::new(10, 20, 100, 0), Region::new(10, 20, 100, 50));
+        case: too_tall => (Edge::Top, 80, Region::new(10, 20, 100, 50), Region::new(10, 70, 100, 0));
+        case: too_wide => (Edge::Right, 200, Region::new(10, 20, 100, 50), Region::new(10, 20, 0, 50));
+
+        body: {
+            let r = Region::new(10, 20, 100, 50);
+            assert_eq!(r.split_reserving(edge, px), (reserved, usable));
+        }
+    }
+
+    test_cases! {
+        shrink_by;
+        args: (r: Region, px: u32, expected: Region);
+
+        case: zero => (Region::new(10, 20, 100, 50), 0, Region::new(10, 20, 100, 50));
+        case: normal => (Region::new(10, 20, 100, 50), 10, Region::new(20, 30, 80, 30));
+        case: clamped_height => (Region::new(10, 20, 100, 50), 25, Region::new(35, 44, 50, 2));
+        case: clamped_both_odd => (Region::new(0, 0, 11, 7), 100, Region::new(5, 3, 1, 1));
+        case: zero_size => (Region::new(5, 5, 0, 0), 3, Region::new(5, 5, 0, 0));
+
+        body: {
+            assert_eq!(r.shrink_by(px), expected);
+        }
+    }
+
+    test_cases! {
+        grow_by;
+        args: (r: Region, px: u32, expected: Region);
+
+        case: zero => (Region::new(10, 20, 100, 50), 0, Region::new(10, 20, 100, 50));
+        case: normal => (Region::new(10, 20, 100, 50), 10, Region::new(0, 10, 120, 70));
+        case: at_origin => (Region::new(0, 0, 100, 50), 10, Region::new(0, 0, 110, 60));
+        case: saturates => (Region::new(0, 0, u32::MAX - 1, 50), 10, Region::new(0, 0, u32::MAX, 60));
+
+        body: {
+            assert_eq!(r.grow_by(px), expected);
+        }
+    }
+
+    #[test]
+    fn shrink_by_undoes_grow_by_away_from_the_origin() {
+        let r = Region::new(50, 60, 100, 80);
+        assert_eq!(r.grow_by(20).shrink_by(20), r);
+    }
+
+    test_cases! {
+        quadrants;
+        args: (r: Region, expected: [Region; 4]);
+
+        case: even => (
+            Region::new(10, 20, 100, 50),
+            [
+                Region::new(10, 20, 50, 25),
+                Region::new(60, 20, 50, 25),
+                Region::new(10, 45, 50, 25),
+                Region::new(60, 45, 50, 25),
+            ]
+        );
+        case: odd => (
+            Region::new(0, 0, 101, 51),
+            [
+                Region::new(0, 0, 51, 26),
+                Region::new(51, 0, 50, 26),
+                Region::new(0, 26, 51, 25),
+                Region::new(51, 26, 50, 25),
+            ]
+        );
+        case: single_pixel => (
+            Region::new(5, 5, 1, 1),
+            [
+                Region::new(5, 5, 1, 1),
+                Region::new(6, 5, 0, 1),
+                Region::new(5, 6, 1, 0),
+                Region::new(6, 6, 0, 0),
+            ]
+        );
+
+        body: {
+            assert_eq!(r.quadrants(), expected);
+        }
+    }
+
+    test_cases! {
+        split_regions_cover_the_original;
+        args: (r: Region, n: u32);
+
+        case: rows_even => (Region::new(0, 0, 100, 1000), 4);
+        case: rows_remainder => (Region::new(0, 0, 100, 1000), 3);
+        case: more_rows_than_pixels => (Region::new(0, 10, 100, 3), 5);
+
+        body: {
+            let rows = r.as_rows(n);
+            let heights: Vec<u32> = rows.iter().map(|r| r.h).collect();
+            let (min, max) = (heights.iter().min().unwrap(), heights.iter().max().unwrap());
+
+            assert_eq!(heights.iter().sum::<u32>(), r.h);
+            assert!(max - min <= 1);
+            assert_eq!(rows.last().map(|l| l.y + l.h), Some(r.y + r.h));
+
+            let t = Region::new(r.y, r.x, r.h, r.w);
+            let cols = t.as_columns(n);
+            let widths: Vec<u32> = cols.iter().map(|c| c.w).collect();
+
+            assert_eq!(widths, heights);
+            assert_eq!(cols.last().map(|l| l.x + l.w), Some(t.x + t.w));
+        }
+    }
+
+    #[test]
+    fn as_rows_distributes_the_remainder_to_the_first_rows() {
+        let rows = Region::new(0, 0, 100, 1000).as_rows(3);
+
+        assert_eq!(
+            rows,
+            vec![
+                Region::new(0, 0, 100, 334),
+                Region::new(0, 334, 100, 333),
+                Region::new(0, 667, 100, 333),
+            ]
+        );
+    }
+
+    test_cases! {
+        strut_reserve_within;
+        args: (vals: &[u32], screen: Region, expected: Region);
+
+        case: top => (&[0, 0, 30, 0], Region::new(0, 0, 800, 600), Region::new(0, 30, 800, 570));
+        case: bottom => (&[0, 0, 0, 20], Region::new(0, 0, 800, 600), Region::new(0, 0, 800, 580));
+        case: left => (&[40, 0, 0, 0], Region::new(0, 0, 800, 600), Region::new(40, 0, 760, 600));
+        case: right_of_root => (&[0, 40, 0, 0], Region::new(0, 0, 800, 600), Region::new(0, 0, 800, 600));
+        case: right_on_second_screen => (&[0, 40, 0, 0], Region::new(800, 0, 800, 600), Region::new(800, 0, 760, 600));
+        case: partial_other_screen => (
+            &[0, 0, 30, 0, 0, 0, 0, 0, 800, 1599, 0, 0],
+            Region::new(0, 0, 800, 600),
+            Region::new(0, 0, 800, 600)
+        );
+        case: partial_this_screen => (
+            &[0, 0, 30, 0, 0, 0, 0, 0, 800, 1599, 0, 0],
+            Region::new(800, 0, 800, 600),
+            Region::new(800, 30, 800, 570)
+        );
+        case: within_existing_bar => (&[0, 0, 10, 0], Region::new(0, 18, 800, 582), Region::new(0, 18, 800, 582));
+        case: covering_whole_screen => (&[0, 0, 600, 0], Region::new(0, 0, 800, 600), Region::new(0, 0, 800, 600));
+
+        body: {
+            let root = Region::new(0, 0, 1600, 600);
+            let strut = Strut::from_cardinals(vals).unwrap();
+            assert_eq!(strut.reserve_within(screen, root), expected);
+        }
+    }
+
+    #[test]
+    fn wm_state_json() {
+        let state = WmState {
+            workspaces: vec![
+                WorkspaceState {
+                    name: "main".into(),
+                    occupied: true,
+                    focused: false,
+                },
+                WorkspaceState {
+                    name: "web".into(),
+                    occupied: false,
+                    focused: true,
+                },
+            ],
+            layout: "[mono]".into(),
+            focused_title: Some("say \"hi\"\n\\o/".into()),
+        };
+
+        let expected = concat!(
+            r#"{"workspaces":["#,
+            r#"{"name":"main","occupied":true,"focused":false},"#,
+            r#"{"name":"web","occupied":false,"focused":true}"#,
+            r#"],"layout":"[mono]","focused_title":"say \"hi\"\n\\o/"}"#,
+        );
+
+        assert_eq!(state.to_json(), expected);
+    }
 }
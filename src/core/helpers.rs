@@ -4,8 +4,13 @@ use crate::{
     ErrorHandler, PenroseError, Result,
 };
 
+use nix::{
+    libc,
+    unistd::{fork, setsid, ForkResult},
+};
 use std::{
-    io::Read,
+    io::{self, Read},
+    os::unix::process::CommandExt,
     process::{Command, Stdio},
 };
 
@@ -50,6 +55,133 @@ pub fn spawn_with_args<S: Into<String>>(cmd: S, args: &[&str]) -> Result<()> {
     }
 }
 
+/// Split a command string into a program and its arguments using shell style quoting.
+///
+/// Arguments are separated by whitespace unless it is inside single or double quotes. Single
+/// quotes preserve their contents exactly while a backslash can be used to escape the next
+/// character either outside of quotes or inside double quotes. No other shell expansion (such as
+/// environment variables, globs or `~`) is performed.
+///
+/// # Example
+/// ```
+/// use penrose::core::helpers::split_args;
+///
+/// assert_eq!(
+///     split_args(r#"notify-send "hello world" 'it''s'"#).unwrap(),
+///     vec!["notify-send", "hello world", "its"]
+/// );
+/// ```
+pub fn split_args(s: &str) -> Result<Vec<String>> {
+    let unterminated = || perror!("unterminated quote in command: {}", s);
+    let mut args = vec![];
+    let mut current: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next().ok_or_else(unterminated)? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next().ok_or_else(unterminated)? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or_else(unterminated)? {
+                            c @ ('"' | '\\') => arg.push(c),
+                            c => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                        },
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => current.get_or_insert_with(String::new).push(c),
+                None => return Err(perror!("trailing escape in command: {}", s)),
+            },
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+
+    Ok(args)
+}
+
+/// Run an external command detached from penrose, with additional environment variables and an
+/// optional working directory.
+///
+/// `cmd` is split into a program and its arguments using [split_args]. The command inherits the
+/// environment of penrose along with the variables in `env`, and its stdin, stdout and stderr
+/// are all redirected to /dev/null.
+///
+/// # Detachment
+/// The command is started using a double fork: penrose forks an intermediate process that starts
+/// a new session and then forks again before exiting immediately, leaving its own child to run
+/// `cmd`. The intermediate process is reaped before this function returns, so the command never
+/// becomes a zombie process of penrose regardless of how `SIGCHLD` is being handled. The command
+/// itself is re-parented to init and has no controlling terminal, so it keeps running if penrose
+/// exits or is restarted.
+///
+/// Errors starting the command (such as the program not existing or `cwd` not being a valid
+/// directory) are returned, but its exit status is not available once it is running.
+///
+/// ```no_run
+/// use penrose::core::helpers::spawn_detached;
+///
+/// spawn_detached("alacritty --title 'scratch'", &[("TERM", "xterm")], Some("/tmp")).unwrap();
+/// ```
+pub fn spawn_detached<S: Into<String>>(
+    cmd: S,
+    env: &[(&str, &str)],
+    cwd: Option<&str>,
+) -> Result<()> {
+    let cmd = cmd.into();
+    let argv = split_args(&cmd)?;
+    let (prog, args) = argv
+        .split_first()
+        .ok_or_else(|| perror!("unable to spawn an empty command"))?;
+
+    info!(?cmd, ?env, ?cwd, "spawning detached subprocess");
+    let mut command = Command::new(prog);
+    command
+        .args(args)
+        .envs(env.iter().copied())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+
+    // SAFETY: only async-signal-safe functions are called between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            setsid().map_err(io::Error::from)?;
+            match fork().map_err(io::Error::from)? {
+                ForkResult::Parent { .. } => libc::_exit(0),
+                ForkResult::Child => Ok(()),
+            }
+        });
+    }
+
+    let mut child = command.spawn()?;
+    // The intermediate process exits as soon as it has forked so this does not block. If SIGCHLD
+    // is being ignored then it has already been reaped and the resulting error is expected.
+    let _ = child.wait();
+
+    Ok(())
+}
+
 /// Run an external command and return its output.
 ///
 /// NOTE: std::process::Command::output will not work within penrose due to the
@@ -138,7 +270,104 @@ pub fn index_selectors<'a, T>(len: usize) -> Vec<Selector<'a, T>> {
     (0..len).map(Selector::Index).collect()
 }
 
+/// Quote and escape a string for inclusion in hand written JSON output
+pub(crate) fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
 /// A simple error handler that just logs the error to the penrose log stream
 pub fn logging_error_handler() -> ErrorHandler {
     Box::new(|e: PenroseError| error!("{}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    test_cases! {
+        split_args;
+        args: (s: &str, expected: &[&str]);
+
+        case: empty => ("", &[]);
+        case: single => ("dmenu_run", &["dmenu_run"]);
+        case: extra_whitespace => ("  st  -e\thtop ", &["st", "-e", "htop"]);
+        case: double_quotes => (r#"notify-send "hello world""#, &["notify-send", "hello world"]);
+        case: single_quotes => (r#"sh -c 'echo "$HOME" \n'"#, &["sh", "-c", r#"echo "$HOME" \n"#]);
+        case: escapes_in_double_quotes => (r#"echo "say \"hi\" \n""#, &["echo", r#"say "hi" \n"#]);
+        case: escaped_space => (r"ls my\ dir", &["ls", "my dir"]);
+        case: adjacent_quotes_join => (r#"echo a"b c"'d'"#, &["echo", "ab cd"]);
+        case: empty_quotes => (r#"echo """#, &["echo", ""]);
+
+        body: {
+            assert_eq!(split_args(s).unwrap(), expected);
+        }
+    }
+
+    test_cases! {
+        split_args_rejects;
+        args: (s: &str);
+
+        case: unterminated_single_quote => ("echo 'oops");
+        case: unterminated_double_quote => (r#"echo "oops"#);
+        case: trailing_escape => (r"echo oops\");
+
+        body: {
+            assert!(split_args(s).is_err());
+        }
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(json_string("a\nb\t"), r#""a\nb\u0009""#);
+    }
+
+    #[test]
+    fn spawn_detached_rejects_empty_commands() {
+        assert!(spawn_detached("  ", &[], None).is_err());
+    }
+
+    #[test]
+    fn spawn_detached_reports_missing_programs() {
+        assert!(spawn_detached("penrose-no-such-program", &[], None).is_err());
+    }
+
+    #[test]
+    fn spawn_detached_sets_env_and_working_directory() {
+        let dir = std::env::temp_dir().join(format!("penrose-spawn-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("out");
+
+        spawn_detached(
+            r#"sh -c 'echo "$PENROSE_TEST" > out'"#,
+            &[("PENROSE_TEST", "detached")],
+            dir.to_str(),
+        )
+        .unwrap();
+
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&out).unwrap_or_default();
+            if !contents.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents, "detached\n");
+    }
+}
@@ -119,6 +119,7 @@
 
 use crate::{
     core::{
+        client::Client,
         data_types::Region,
         manager::WindowManager,
         xconnection::{XConn, Xid},
@@ -139,15 +140,19 @@ pub enum HookName {
     RemoveClient(Xid),
     ClientAddedToWorkspace(Xid, usize),
     ClientNameUpdated(Xid, String, bool),
+    ClientTitleChanged(Xid, String),
     LayoutApplied(usize, usize),
     LayoutChange(usize),
     WorkspaceChange(usize, usize),
+    ScreenWorkspaceChange(usize, usize, usize),
     WorkspacesUpdated(Vec<String>, usize),
     ScreenChange,
     ScreenUpdated,
     RanderNotify,
     FocusChange(u32),
+    ClientFocusChanged(Option<Xid>, Option<Xid>),
     EventHandled,
+    StateChanged,
 }
 
 /// Utility type for defining hooks in your penrose configuration.
@@ -201,9 +206,12 @@ impl<X: XConn> Hooks<X> {
 pub trait Hook<X: XConn> {
     /// # Trigger Point
     ///
-    /// Called once at [WindowManager] startup in [grab_keys_and_run][1] after setting up signal handlers
-    /// and grabbing key / mouse bindings but before entering the main event loop that polls for
-    /// [XEvents][2].
+    /// Called once at [WindowManager] startup in [grab_keys_and_run][1] after setting up signal handlers,
+    /// grabbing key / mouse bindings and applying the initial layout but before entering the main
+    /// event loop that polls for [XEvents][2].
+    ///
+    /// Calling `grab_keys_and_run` again (e.g. after [exit][5] when restarting in place) will not
+    /// re-run startup hooks unless [reset_startup_hooks][6] has been called.
     ///
     /// # Example Uses
     ///
@@ -216,11 +224,37 @@ pub trait Hook<X: XConn> {
     /// [2]: crate::core::xconnection::XEvent
     /// [3]: crate::core::workspace::Workspace
     /// [4]: crate::core::screen::Screen
+    /// [5]: crate::core::manager::WindowManager::exit
+    /// [6]: crate::core::manager::WindowManager::reset_startup_hooks
     #[allow(unused_variables)]
     fn startup(&mut self, wm: &mut WindowManager<X>) -> Result<()> {
         Ok(())
     }
 
+    /// # Trigger Point
+    ///
+    /// Called when a window requests to be mapped, before it is managed. If any hook returns
+    /// `false` the window is ignored entirely: it is mapped but never added to the client set,
+    /// placed on a workspace or laid out, and later map requests for it are honoured without
+    /// consulting hooks again. Windows that already exist when penrose starts (see
+    /// [ManageExistingClients][2]) are not passed to this hook.
+    ///
+    /// The [Client][1] passed here is a candidate that is not yet known to the WindowManager so
+    /// it can be inspected but not modified. Returning an error is logged and treated as allowing
+    /// the client to be managed.
+    ///
+    /// # Example Uses
+    ///
+    /// Leaving overlays or notification windows that draw themselves alone based on their class,
+    /// name or window type.
+    ///
+    /// [1]: crate::core::client::Client
+    /// [2]: crate::contrib::hooks::ManageExistingClients
+    #[allow(unused_variables)]
+    fn should_manage(&mut self, wm: &mut WindowManager<X>, client: &Client) -> Result<bool> {
+        Ok(true)
+    }
+
     /// # Trigger Point
     ///
     /// Called when a new [Client][5] has been created in response to map request and all penrose
@@ -317,6 +351,27 @@ pub trait Hook<X: XConn> {
         Ok(())
     }
 
+    /// # Trigger Point
+    ///
+    /// Called when the WM_NAME or _NET_WM_NAME of a known client changes to a new value.
+    ///
+    /// Unlike [Hook::client_name_updated], this is not called for the root window or when a
+    /// client sets its name to the value it already had.
+    ///
+    /// # Example Uses
+    ///
+    /// Keeping a status bar showing the title of the focused client up to date without redrawing
+    /// on every property update.
+    #[allow(unused_variables)]
+    fn client_title_changed(
+        &mut self,
+        wm: &mut WindowManager<X>,
+        id: Xid,
+        title: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// # Trigger Point
     ///
     /// Called after a [Layout][1] is applied to the active Workspace.
@@ -401,6 +456,33 @@ pub trait Hook<X: XConn> {
         Ok(())
     }
 
+    /// # Trigger Point
+    ///
+    /// Called for each [Screen][1] whose visible [Workspace][2] changes as the result of focusing
+    /// a workspace. When the requested workspace is already shown on another screen the two
+    /// screens swap workspaces and this is called once for each of them.
+    ///
+    /// Arguments are the previous and new workspace indices followed by the index of the screen
+    /// that changed. This is not called for the workspaces assigned to screens at startup or
+    /// when screens are re-detected, or when a visible workspace is removed.
+    ///
+    /// # Example Uses
+    ///
+    /// Setting a per-workspace wallpaper on the screen that changed.
+    ///
+    /// [1]: crate::core::screen::Screen
+    /// [2]: crate::core::workspace::Workspace
+    #[allow(unused_variables)]
+    fn screen_workspace_change(
+        &mut self,
+        wm: &mut WindowManager<X>,
+        previous_workspace: usize,
+        new_workspace: usize,
+        screen_index: usize,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// # Trigger Point
     ///
     /// Called whenever a [Workspace][1] is dynamically added or removed from the list of known
@@ -495,6 +577,32 @@ pub trait Hook<X: XConn> {
         Ok(())
     }
 
+    /// # Trigger Point
+    ///
+    /// Called when focus moves from one [Client][1] to another, either within a workspace or as
+    /// the result of switching workspace.
+    ///
+    /// Arguments are the previously focused Client ID and the newly focused Client ID, either of
+    /// which will be `None` if there was no focused client (e.g. when switching to or from an empty
+    /// workspace). Unlike [Hook::focus_change], this is not called when focus is set to the client
+    /// that already had it.
+    ///
+    /// # Example Uses
+    ///
+    /// Running per-client actions such as adjusting the opacity of the clients gaining and losing
+    /// focus.
+    ///
+    /// [1]: crate::core::client::Client
+    #[allow(unused_variables)]
+    fn client_focus_changed(
+        &mut self,
+        wm: &mut WindowManager<X>,
+        prev: Option<Xid>,
+        new: Option<Xid>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// # Trigger Point
     ///
     /// Called at the bottom of the main [WindowManager] event loop after each [XEvent][1] is handled.
@@ -509,4 +617,25 @@ pub trait Hook<X: XConn> {
     fn event_handled(&mut self, wm: &mut WindowManager<X>) -> Result<()> {
         Ok(())
     }
+
+    /// # Trigger Point
+    ///
+    /// Called at most once per iteration of the main [WindowManager] event loop, after
+    /// [Hook::event_handled], if handling the [XEvent][1] changed the known clients, the focused
+    /// client or its title, the workspaces, the active layouts or the state of a client (floating,
+    /// fullscreen, minimized, sticky or its position in the stack).
+    ///
+    /// No details of what changed are provided: the current state can be queried using methods
+    /// such as [WindowManager::state] and [WindowManager::client_snapshot].
+    ///
+    /// # Example Uses
+    ///
+    /// Updating an external status bar once per event rather than once for every individual
+    /// change that is made while the event is being handled.
+    ///
+    /// [1]: crate::core::xconnection::XEvent
+    #[allow(unused_variables)]
+    fn state_changed(&mut self, wm: &mut WindowManager<X>) -> Result<()> {
+        Ok(())
+    }
 }
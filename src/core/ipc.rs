@@ -0,0 +1,490 @@
+//! A unix domain socket for controlling penrose from external scripts.
+//!
+//! Commands are written to the socket one per line and each receives a single line response:
+//! `ok` for actions, a JSON value for queries or `error: <reason>` if something went wrong.
+//!
+//! ```text
+//! $ echo "focus-workspace 2" | socat - UNIX-CONNECT:/tmp/penrose.sock
+//! ok
+//! $ echo "query focused" | socat - UNIX-CONNECT:/tmp/penrose.sock
+//! {"id":4194313,"workspace":2,"class":"Alacritty","title":"zsh"}
+//! ```
+//!
+//! The socket is read on a background thread but the [WindowManager] itself is never touched from
+//! anywhere other than the main event loop: parsed commands are queued on a channel and run in
+//! between X events. While the X server is idle the event loop periodically wakes up to check for
+//! queued commands. To have commands run immediately instead, you can provide a waker to
+//! [IpcServer::bind_with_waker] that is called each time a command is queued. Sending a client
+//! message to the root window from a second X connection is enough to wake the loop up.
+use crate::{
+    core::{
+        helpers::json_string,
+        manager::WindowManager,
+        ring::{Direction, Selector},
+        xconnection::XConn,
+    },
+    PenroseError, Result,
+};
+
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+/// Information that can be requested using `query <target>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcQuery {
+    /// The focused client: `query focused`
+    Focused,
+    /// The active workspace: `query workspace`
+    Workspace,
+    /// All known workspaces: `query workspaces`
+    Workspaces,
+}
+
+/// A single command accepted over the IPC socket.
+///
+/// Workspace indices are zero based, matching [Selector::Index] and the output of `query`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcCommand {
+    /// `focus-workspace <index>`
+    FocusWorkspace(usize),
+    /// `cycle-workspace next|prev`
+    CycleWorkspace(Direction),
+    /// `client-to-workspace <index>`
+    ClientToWorkspace(usize),
+    /// `focus-client next|prev`
+    CycleClient(Direction),
+    /// `cycle-layout next|prev`
+    CycleLayout(Direction),
+    /// `toggle-float`
+    ToggleFloat,
+    /// `toggle-fullscreen`
+    ToggleFullscreen,
+    /// `toggle-scratchpad <name>`
+    ToggleScratchpad(String),
+    /// `add-workspace <name>`: responds with the index of the new workspace
+    AddWorkspace(String),
+    /// `remove-workspace <index>`
+    RemoveWorkspace(usize),
+    /// `rename-workspace <index> <name>`
+    RenameWorkspace(usize, String),
+    /// `close`
+    Close,
+    /// `kill`
+    Kill,
+    /// `exit`
+    Exit,
+    /// `query focused|workspace|workspaces`
+    Query(IpcQuery),
+}
+
+impl FromStr for IpcCommand {
+    type Err = PenroseError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || PenroseError::InvalidIpcCommand(s.trim().to_string());
+        let direction = |arg: &str| match arg {
+            "next" => Ok(Direction::Forward),
+            "prev" => Ok(Direction::Backward),
+            _ => Err(invalid()),
+        };
+        let index = |arg: &str| arg.parse::<usize>().map_err(|_| invalid());
+
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let command = match words.as_slice() {
+            ["focus-workspace", ix] => Self::FocusWorkspace(index(ix)?),
+            ["cycle-workspace", d] => Self::CycleWorkspace(direction(d)?),
+            ["client-to-workspace", ix] => Self::ClientToWorkspace(index(ix)?),
+            ["focus-client", d] => Self::CycleClient(direction(d)?),
+            ["cycle-layout", d] => Self::CycleLayout(direction(d)?),
+            ["toggle-float"] => Self::ToggleFloat,
+            ["toggle-fullscreen"] => Self::ToggleFullscreen,
+            ["toggle-scratchpad", name] => Self::ToggleScratchpad(name.to_string()),
+            ["add-workspace", name] => Self::AddWorkspace(name.to_string()),
+            ["remove-workspace", ix] => Self::RemoveWorkspace(index(ix)?),
+            ["rename-workspace", ix, name] => Self::RenameWorkspace(index(ix)?, name.to_string()),
+            ["close"] => Self::Close,
+            ["kill"] => Self::Kill,
+            ["exit"] => Self::Exit,
+            ["query", "focused"] => Self::Query(IpcQuery::Focused),
+            ["query", "workspace"] => Self::Query(IpcQuery::Workspace),
+            ["query", "workspaces"] => Self::Query(IpcQuery::Workspaces),
+            _ => return Err(invalid()),
+        };
+
+        Ok(command)
+    }
+}
+
+impl IpcCommand {
+    /// Run this command against the given [WindowManager], returning the response that should be
+    /// sent back to the caller.
+    pub fn run<X: XConn>(&self, wm: &mut WindowManager<X>) -> Result<String> {
+        match self {
+            Self::FocusWorkspace(ix) => wm.focus_workspace(&Selector::Index(*ix))?,
+            Self::CycleWorkspace(d) => wm.cycle_workspace(*d)?,
+            Self::ClientToWorkspace(ix) => wm.client_to_workspace(&Selector::Index(*ix))?,
+            Self::CycleClient(d) => wm.cycle_client(*d)?,
+            Self::CycleLayout(d) => wm.cycle_layout(*d)?,
+            Self::ToggleFloat => wm.toggle_client_floating(&Selector::Focused)?,
+            Self::ToggleFullscreen => wm.toggle_client_fullscreen(&Selector::Focused)?,
+            Self::ToggleScratchpad(name) => wm.toggle_scratchpad(name)?,
+            Self::AddWorkspace(name) => return Ok(wm.create_workspace(name.as_str())?.to_string()),
+            Self::RemoveWorkspace(ix) => {
+                wm.remove_workspace(&Selector::Index(*ix))?;
+            }
+            Self::RenameWorkspace(ix, name) => {
+                wm.set_workspace_name(name.as_str(), &Selector::Index(*ix))?
+            }
+            Self::Close => wm.close_focused()?,
+            Self::Kill => wm.kill_client()?,
+            Self::Exit => wm.exit()?,
+            Self::Query(q) => return Ok(query(wm, *q)),
+        }
+
+        Ok("ok".to_string())
+    }
+}
+
+fn query<X: XConn>(wm: &WindowManager<X>, q: IpcQuery) -> String {
+    match q {
+        IpcQuery::Focused => match wm.focused_client() {
+            Some(c) => format!(
+                r#"{{"id":{},"workspace":{},"class":{},"title":{}}}"#,
+                c.id(),
+                c.workspace(),
+                json_string(c.wm_class()),
+                json_string(c.wm_name())
+            ),
+            None => "null".to_string(),
+        },
+
+        IpcQuery::Workspace => {
+            let ws = wm.active_workspace();
+            format!(
+                r#"{{"index":{},"name":{},"layout":{},"clients":{}}}"#,
+                wm.screen(&Selector::Focused).map(|s| s.wix).unwrap_or(0),
+                json_string(ws.name()),
+                json_string(ws.layout_symbol()),
+                ws.len()
+            )
+        }
+
+        IpcQuery::Workspaces => {
+            let visible = wm.focused_workspaces();
+            let workspaces: Vec<String> = wm
+                .all_workspaces(&Selector::Any)
+                .iter()
+                .enumerate()
+                .map(|(ix, ws)| {
+                    format!(
+                        r#"{{"index":{},"name":{},"clients":{},"visible":{}}}"#,
+                        ix,
+                        json_string(ws.name()),
+                        ws.len(),
+                        visible.contains(&ix)
+                    )
+                })
+                .collect();
+
+            format!("[{}]", workspaces.join(","))
+        }
+    }
+}
+
+/// A parsed command waiting to be run by the event loop along with a channel for the response.
+#[derive(Debug)]
+pub(crate) struct IpcRequest {
+    pub(crate) command: IpcCommand,
+    reply: Sender<String>,
+}
+
+impl IpcRequest {
+    pub(crate) fn respond(self, response: String) {
+        // The caller may have already hung up: nothing to do if so
+        let _ = self.reply.send(response);
+    }
+}
+
+/// A unix domain socket accepting [IpcCommand]s.
+///
+/// Register with a [WindowManager] using [WindowManager::set_ipc_server]. The socket file is
+/// removed when the server is dropped.
+pub struct IpcServer {
+    path: PathBuf,
+    requests: Receiver<IpcRequest>,
+}
+
+impl fmt::Debug for IpcServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IpcServer")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl IpcServer {
+    /// Listen for commands on a socket at `path`.
+    ///
+    /// A socket left behind by a previous instance is removed first. An error is returned if
+    /// `path` exists but is not a socket, or if another process is still listening on it.
+    pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_with_waker(path, || ())
+    }
+
+    /// Listen for commands on a socket at `path`, calling `waker` each time that a command is
+    /// queued for the event loop.
+    pub fn bind_with_waker<F>(path: impl AsRef<Path>, waker: F) -> Result<Self>
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        remove_stale_socket(&path)?;
+
+        let listener = UnixListener::bind(&path)?;
+        let (tx, requests) = channel();
+        let waker: Arc<dyn Fn() + Send + Sync> = Arc::new(waker);
+
+        debug!(?path, "listening for IPC commands");
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let (tx, waker) = (tx.clone(), Arc::clone(&waker));
+                        thread::spawn(move || handle_connection(stream, tx, waker));
+                    }
+                    Err(e) => warn!(%e, "unable to accept IPC connection"),
+                }
+            }
+        });
+
+        Ok(Self { path, requests })
+    }
+
+    /// The path of the underlying socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn pending(&self) -> Vec<IpcRequest> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// A socket left behind by a previous run is replaced but anything else found at `path`, including
+// a socket that another instance is still listening on, is left alone.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !meta.file_type().is_socket() {
+        let msg = format!("{} already exists and is not a socket", path.display());
+        return Err(io::Error::new(ErrorKind::AlreadyExists, msg).into());
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        let msg = format!("another process is already listening on {}", path.display());
+        return Err(io::Error::new(ErrorKind::AddrInUse, msg).into());
+    }
+
+    Ok(fs::remove_file(path)?)
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    tx: Sender<IpcRequest>,
+    waker: Arc<dyn Fn() + Send + Sync>,
+) {
+    let mut writer = match stream.try_clone() {
+        Ok(s) => s,
+        Err(e) => return warn!(%e, "unable to clone IPC stream"),
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        let response = match line.parse() {
+            Ok(command) => {
+                let (reply, response) = channel();
+                if tx.send(IpcRequest { command, reply }).is_err() {
+                    return; // the window manager has shut down
+                }
+                waker();
+                match response.recv() {
+                    Ok(response) => response,
+                    Err(_) => return,
+                }
+            }
+            Err(e) => format!("error: {}", e),
+        };
+
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::__test_helpers::*;
+
+    test_cases! {
+        parse;
+        args: (s: &str, expected: Option<IpcCommand>);
+
+        case: focus_workspace => ("focus-workspace 3", Some(IpcCommand::FocusWorkspace(3)));
+        case: extra_whitespace => ("  close \n", Some(IpcCommand::Close));
+        case: cycle_next => ("cycle-workspace next", Some(IpcCommand::CycleWorkspace(Direction::Forward)));
+        case: cycle_prev => ("focus-client prev", Some(IpcCommand::CycleClient(Direction::Backward)));
+        case: scratchpad => ("toggle-scratchpad term", Some(IpcCommand::ToggleScratchpad("term".into())));
+        case: query => ("query focused", Some(IpcCommand::Query(IpcQuery::Focused)));
+        case: add_workspace => ("add-workspace scratch", Some(IpcCommand::AddWorkspace("scratch".into())));
+        case: rename_workspace => ("rename-workspace 2 web", Some(IpcCommand::RenameWorkspace(2, "web".into())));
+        case: rename_missing_name => ("rename-workspace 2", None);
+        case: empty => ("", None);
+        case: unknown => ("frobnicate", None);
+        case: missing_arg => ("focus-workspace", None);
+        case: bad_index => ("focus-workspace three", None);
+        case: bad_direction => ("cycle-layout sideways", None);
+        case: unexpected_arg => ("toggle-float now", None);
+        case: too_many_args => ("focus-workspace 1 2", None);
+        case: unknown_query => ("query everything", None);
+
+        body: {
+            assert_eq!(s.parse::<IpcCommand>().ok(), expected);
+        }
+    }
+
+    #[test]
+    fn commands_map_onto_window_manager_methods() {
+        let mut wm = test_windowmanager(1, vec![]);
+
+        let response = IpcCommand::FocusWorkspace(2).run(&mut wm).unwrap();
+        assert_eq!(response, "ok");
+        assert_eq!(wm.screen(&Selector::Focused).unwrap().wix, 2);
+
+        IpcCommand::CycleWorkspace(Direction::Backward)
+            .run(&mut wm)
+            .unwrap();
+        assert_eq!(wm.screen(&Selector::Focused).unwrap().wix, 1);
+    }
+
+    #[test]
+    fn queries_return_json() {
+        let mut wm = test_windowmanager(1, vec![]);
+
+        let focused = IpcCommand::Query(IpcQuery::Focused).run(&mut wm).unwrap();
+        assert_eq!(focused, "null");
+
+        IpcCommand::FocusWorkspace(1).run(&mut wm).unwrap();
+        let workspace = IpcCommand::Query(IpcQuery::Workspace).run(&mut wm).unwrap();
+        let expected = format!(
+            r#"{{"index":1,"name":"2","layout":{},"clients":0}}"#,
+            json_string(wm.current_layout_symbol())
+        );
+        assert_eq!(workspace, expected);
+
+        let workspaces = IpcCommand::Query(IpcQuery::Workspaces)
+            .run(&mut wm)
+            .unwrap();
+        assert!(workspaces.starts_with(r#"[{"index":0,"name":"1","clients":0,"visible":false},"#));
+        assert!(workspaces.contains(r#"{"index":1,"name":"2","clients":0,"visible":true}"#));
+    }
+
+    #[test]
+    fn requests_are_queued_for_the_event_loop() {
+        let path =
+            std::env::temp_dir().join(format!("penrose-ipc-test-{}.sock", std::process::id()));
+        let server = IpcServer::bind(&path).unwrap();
+        let mut wm = test_windowmanager(1, vec![]);
+
+        let client = thread::spawn({
+            let path = path.clone();
+            move || {
+                let mut stream = UnixStream::connect(path).unwrap();
+                writeln!(stream, "nonsense\nfocus-workspace 3").unwrap();
+                let mut lines = BufReader::new(stream).lines();
+                let first = lines.next().unwrap().unwrap();
+                let second = lines.next().unwrap().unwrap();
+                (first, second)
+            }
+        });
+
+        let request = loop {
+            if let Some(request) = server.pending().pop() {
+                break request;
+            }
+            thread::yield_now();
+        };
+        let response = request.command.run(&mut wm).unwrap();
+        request.respond(response);
+
+        let (first, second) = client.join().unwrap();
+        assert!(first.starts_with("error: "));
+        assert_eq!(second, "ok");
+        assert_eq!(wm.screen(&Selector::Focused).unwrap().wix, 3);
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    fn test_socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("penrose-ipc-{}-{}.sock", name, std::process::id()))
+    }
+
+    #[test]
+    fn stale_sockets_are_replaced() {
+        let path = test_socket_path("stale");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let server = IpcServer::bind(&path).unwrap();
+        assert!(UnixStream::connect(&path).is_ok());
+        drop(server);
+    }
+
+    #[test]
+    fn sockets_with_a_live_listener_are_not_replaced() {
+        let path = test_socket_path("live");
+        let _server = IpcServer::bind(&path).unwrap();
+
+        assert!(IpcServer::bind(&path).is_err());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn files_that_are_not_sockets_are_not_removed() {
+        let path = test_socket_path("file");
+        fs::write(&path, "not a socket").unwrap();
+
+        assert!(IpcServer::bind(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a socket");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use crate::core::{
     xconnection::Xid,
 };
 
-use std::{cmp, fmt};
+use std::{cmp, fmt, panic};
 
 /// When and how a Layout should be applied.
 ///
@@ -20,6 +20,9 @@ pub struct LayoutConf {
     pub follow_focus: bool,
     /// Should cycling clients wrap at the first and last client?
     pub allow_wrapping: bool,
+    /// Should the order of clients passed to the layout function be reversed? By default the
+    /// order matches the client stack of the workspace being laid out.
+    pub reverse_clients: bool,
 }
 
 impl Default for LayoutConf {
@@ -29,6 +32,7 @@ impl Default for LayoutConf {
             gapless: false,
             follow_focus: false,
             allow_wrapping: true,
+            reverse_clients: false,
         }
     }
 }
@@ -37,7 +41,7 @@ impl Default for LayoutConf {
 ///
 /// Will be called with the current client list, the active client ID (if there is one), the size
 /// of the screen that the workspace is shown on and the current values of n_main and ratio for
-/// this layout.
+/// this layout. The client list is never empty: see [Layout::arrange].
 pub type LayoutFunc = fn(&[&Client], Option<Xid>, &Region, u32, f32) -> Vec<ResizeAction>;
 
 /// Responsible for arranging Clients within a Workspace.
@@ -60,9 +64,14 @@ pub type LayoutFunc = fn(&[&Client], Option<Xid>, &Region, u32, f32) -> Vec<Resi
 pub struct Layout {
     pub(crate) conf: LayoutConf,
     pub(crate) symbol: String,
+    // Should clients that are not positioned by this layout be moved back within the screen when
+    // it is applied?
+    pub(crate) bound_floating: bool,
     max_main: u32,
     ratio: f32,
+    ratio_bounds: (f32, f32),
     f: Option<LayoutFunc>,
+    fallback: Option<LayoutFunc>,
 }
 
 impl cmp::PartialEq<Layout> for Layout {
@@ -70,8 +79,10 @@ impl cmp::PartialEq<Layout> for Layout {
     fn eq(&self, other: &Layout) -> bool {
         self.conf == other.conf
             && self.symbol == other.symbol
+            && self.bound_floating == other.bound_floating
             && self.max_main == other.max_main
             && self.ratio == other.ratio
+            && self.ratio_bounds == other.ratio_bounds
     }
 }
 
@@ -80,8 +91,10 @@ impl fmt::Debug for Layout {
         f.debug_struct("Layout")
             .field("kind", &self.conf)
             .field("symbol", &self.symbol)
+            .field("bound_floating", &self.bound_floating)
             .field("max_main", &self.max_main)
             .field("ratio", &self.ratio)
+            .field("ratio_bounds", &self.ratio_bounds)
             .field("f", &stringify!(&self.f))
             .finish()
     }
@@ -99,9 +112,12 @@ impl Layout {
         Self {
             symbol: symbol.into(),
             conf,
+            bound_floating: false,
             max_main,
             ratio,
+            ratio_bounds: (0.0, 1.0),
             f: Some(f),
+            fallback: None,
         }
     }
 
@@ -114,21 +130,122 @@ impl Layout {
                 gapless: false,
                 follow_focus: false,
                 allow_wrapping: true,
+                reverse_clients: false,
             },
+            bound_floating: false,
             f: Some(super::layouts::floating),
+            fallback: None,
             max_main: 1,
             ratio: 1.0,
+            ratio_bounds: (0.0, 1.0),
         }
     }
 
+    /// A floating layout that will not attempt to manage windows other than moving them back
+    /// within the bounds of the screen when it is applied
+    pub fn floating_bounded(symbol: impl Into<String>) -> Self {
+        let mut layout = Self::floating(symbol);
+        layout.bound_floating = true;
+
+        layout
+    }
+
+    /// A tabbed layout using [deck][super::layouts::deck] that is re-applied whenever focus
+    /// changes, bringing the newly focused client to the front.
+    pub fn deck(symbol: impl Into<String>) -> Self {
+        let conf = LayoutConf {
+            follow_focus: true,
+            ..Default::default()
+        };
+
+        Self::new(symbol, conf, super::layouts::deck, 1, 1.0)
+    }
+
+    /// Constrain the main ratio of this layout to lie between `min` and `max` (inclusive).
+    ///
+    /// By default the ratio is only clamped to the range 0.0 to 1.0, which allows the main area
+    /// to collapse entirely or take up the whole screen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::{layouts::side_stack, Layout, LayoutConf};
+    ///
+    /// let layout = Layout::new("[side]", LayoutConf::default(), side_stack, 1, 0.6)
+    ///     .with_ratio_bounds(0.2, 0.8);
+    /// ```
+    pub fn with_ratio_bounds(mut self, min: f32, max: f32) -> Self {
+        let (min, max) = (min.max(0.0), max.min(1.0));
+        self.ratio_bounds = (min.min(max), max);
+        self.ratio = self.ratio.max(self.ratio_bounds.0).min(self.ratio_bounds.1);
+
+        self
+    }
+
+    /// Set a layout function to use in place of the main one if it panics.
+    ///
+    /// See [arrange][Layout::arrange] for the default behaviour when no fallback is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::{layouts::{monocle, side_stack}, Layout, LayoutConf};
+    ///
+    /// let layout = Layout::new("[side]", LayoutConf::default(), side_stack, 1, 0.6)
+    ///     .with_panic_fallback(monocle);
+    /// ```
+    pub fn with_panic_fallback(mut self, f: LayoutFunc) -> Self {
+        self.fallback = Some(f);
+        self
+    }
+
     /// Apply the layout function held by this `Layout` using the current max_main and ratio
+    ///
+    /// If `clients` is empty then no actions are returned and the layout function is not called.
+    /// If [reverse_clients][LayoutConf::reverse_clients] is set for this layout then the order of
+    /// `clients` is reversed before they are passed to the layout function.
+    ///
+    /// If the layout function panics, the panic is logged and the fallback function set using
+    /// [with_panic_fallback][Layout::with_panic_fallback] is used instead. If there is no
+    /// fallback (or it also panics) then no resize actions are returned, leaving all clients
+    /// where they currently are.
     pub fn arrange(
         &self,
         clients: &[&Client],
         focused: Option<Xid>,
         r: &Region,
     ) -> Vec<ResizeAction> {
-        (self.f.expect("missing layout function"))(clients, focused, r, self.max_main, self.ratio)
+        let f = self.f.expect("missing layout function");
+        if clients.is_empty() {
+            return vec![];
+        }
+
+        let reversed: Vec<&Client>;
+        let clients = if self.conf.reverse_clients {
+            reversed = clients.iter().rev().copied().collect();
+            &reversed[..]
+        } else {
+            clients
+        };
+
+        let run = |f: LayoutFunc| {
+            panic::catch_unwind(|| f(clients, focused, r, self.max_main, self.ratio))
+        };
+
+        match run(f) {
+            Ok(actions) => actions,
+            Err(_) => {
+                error!(symbol = %self.symbol, "layout function panicked");
+                match self.fallback.map(run) {
+                    Some(Ok(actions)) => actions,
+                    Some(Err(_)) => {
+                        error!(symbol = %self.symbol, "fallback layout function panicked");
+                        vec![]
+                    }
+                    None => vec![],
+                }
+            }
+        }
     }
 
     /// Increase/decrease the number of clients in the main area by 1
@@ -144,18 +261,43 @@ impl Layout {
     }
 
     /// Increase/decrease the size of the main area relative to secondary.
-    /// (clamps at 1.0 and 0.0 respectively)
+    ///
+    /// The new ratio is rounded to 3 decimal places to avoid accumulating floating point error
+    /// and then clamped to the bounds set by [with_ratio_bounds][Layout::with_ratio_bounds]
+    /// (0.0 and 1.0 by default). The window manager narrows these bounds to lie within the
+    /// [min_main_ratio][crate::core::config::Config::min_main_ratio] and
+    /// [max_main_ratio][crate::core::config::Config::max_main_ratio] of its config.
     pub fn update_main_ratio(&mut self, change: Change, step: f32) {
-        match change {
-            Change::More => self.ratio += step,
-            Change::Less => self.ratio -= step,
-        }
+        let ratio = match change {
+            Change::More => self.ratio + step,
+            Change::Less => self.ratio - step,
+        };
 
-        if self.ratio < 0.0 {
-            self.ratio = 0.0
-        } else if self.ratio > 1.0 {
-            self.ratio = 1.0;
-        }
+        let (min, max) = self.ratio_bounds;
+        self.ratio = ((ratio * 1000.0).round() / 1000.0).max(min).min(max);
+    }
+
+    /// The (min, max) bounds that the main ratio of this layout is clamped to.
+    pub fn ratio_bounds(&self) -> (f32, f32) {
+        self.ratio_bounds
+    }
+
+    /// The current ratio of the main area relative to secondary.
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    /// The current number of clients in the main area.
+    pub fn max_main(&self) -> u32 {
+        self.max_main
+    }
+
+    /// Restore a previously saved max_main and ratio, clamping the ratio to the bounds set by
+    /// [with_ratio_bounds][Layout::with_ratio_bounds].
+    pub fn restore(&mut self, max_main: u32, ratio: f32) {
+        let (min, max) = self.ratio_bounds;
+        self.max_main = max_main;
+        self.ratio = ratio.max(min).min(max);
     }
 }
 
@@ -172,3 +314,122 @@ pub fn client_breakdown<T>(clients: &[T], n_main: u32) -> (u32, u32) {
         (n_main, n - n_main)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{layout::layouts::side_stack, xconnection::MockXConn};
+
+    fn layout(ratio: f32) -> Layout {
+        Layout::new("t", LayoutConf::default(), side_stack, 1, ratio)
+    }
+
+    #[test]
+    fn main_ratio_does_not_drift() {
+        let mut l = layout(0.6);
+
+        for _ in 0..20 {
+            l.update_main_ratio(Change::More, 0.05);
+            l.update_main_ratio(Change::Less, 0.05);
+        }
+        assert_eq!(l.ratio(), 0.6);
+
+        for _ in 0..8 {
+            l.update_main_ratio(Change::More, 0.05);
+        }
+        assert_eq!(l.ratio(), 1.0);
+
+        for _ in 0..8 {
+            l.update_main_ratio(Change::Less, 0.05);
+        }
+        assert_eq!(l.ratio(), 0.6);
+    }
+
+    test_cases! {
+        main_ratio_bounds;
+        args: (change: Change, steps: usize, expected: f32);
+
+        case: clamped_at_max => (Change::More, 20, 0.8);
+        case: clamped_at_min => (Change::Less, 20, 0.2);
+        case: within_bounds => (Change::More, 2, 0.7);
+
+        body: {
+            let mut l = layout(0.6).with_ratio_bounds(0.2, 0.8);
+            for _ in 0..steps {
+                l.update_main_ratio(change, 0.05);
+            }
+
+            assert_eq!(l.ratio(), expected);
+        }
+    }
+
+    fn panicking(_: &[&Client], _: Option<Xid>, _: &Region, _: u32, _: f32) -> Vec<ResizeAction> {
+        panic!("bad layout")
+    }
+
+    fn fallback(_: &[&Client], _: Option<Xid>, _: &Region, _: u32, _: f32) -> Vec<ResizeAction> {
+        vec![ResizeAction::new(42, None)]
+    }
+
+    #[test]
+    fn panicking_layouts_return_no_actions() {
+        let conn = MockXConn::new(vec![], vec![], vec![]);
+        let client = Client::new(&conn, 1, 0, &[]);
+        let l = Layout::new("t", LayoutConf::default(), panicking, 1, 0.6);
+
+        assert!(l.arrange(&[&client], None, &Region::default()).is_empty());
+    }
+
+    #[test]
+    fn panicking_layouts_use_the_fallback_if_set() {
+        let conn = MockXConn::new(vec![], vec![], vec![]);
+        let client = Client::new(&conn, 1, 0, &[]);
+        let l = Layout::new("t", LayoutConf::default(), panicking, 1, 0.6)
+            .with_panic_fallback(fallback);
+
+        assert_eq!(
+            l.arrange(&[&client], None, &Region::default()),
+            vec![ResizeAction::new(42, None)]
+        );
+    }
+
+    #[test]
+    fn layout_functions_are_not_called_without_clients() {
+        let l = Layout::new("t", LayoutConf::default(), fallback, 1, 0.6);
+
+        assert!(l.arrange(&[], None, &Region::default()).is_empty());
+    }
+
+    test_cases! {
+        reverse_clients;
+        args: (reverse: bool, expected_main: Xid);
+
+        case: stack_order => (false, 1);
+        case: reversed => (true, 3);
+
+        body: {
+            let conn = MockXConn::new(vec![], vec![], vec![]);
+            let clients: Vec<Client> = (1..=3).map(|id| Client::new(&conn, id, 0, &[])).collect();
+            let refs: Vec<&Client> = clients.iter().collect();
+            let conf = LayoutConf {
+                reverse_clients: reverse,
+                ..Default::default()
+            };
+            let l = Layout::new("t", conf, side_stack, 1, 0.6);
+
+            let actions = l.arrange(&refs, Some(2), &Region::new(0, 0, 1000, 600));
+            let main = actions
+                .iter()
+                .find(|a| a.region == Some(Region::new(0, 0, 600, 600)))
+                .map(|a| a.id);
+
+            assert_eq!(actions.len(), 3);
+            assert_eq!(main, Some(expected_main));
+        }
+    }
+
+    #[test]
+    fn initial_ratio_is_clamped_to_bounds() {
+        assert_eq!(layout(0.95).with_ratio_bounds(0.2, 0.8).ratio(), 0.8);
+    }
+}
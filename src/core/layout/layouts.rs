@@ -1,6 +1,6 @@
 use crate::{
     core::{
-        data_types::{Region, ResizeAction},
+        data_types::{Region, ResizeAction, StackHint},
         Client,
     },
     Xid,
@@ -31,7 +31,7 @@ pub(crate) fn mock_layout(
         .map(|(i, c)| {
             let (x, y, w, h) = region.values();
             let _k = i as u32;
-            (c.id(), Some(Region::new(x + _k, y + _k, w - _k, h - _k)))
+            ResizeAction::new(c.id(), Some(Region::new(x + _k, y + _k, w - _k, h - _k)))
         })
         .collect()
 }
@@ -57,7 +57,7 @@ pub fn side_stack(
             .as_rows(n)
             .iter()
             .zip(clients)
-            .map(|(r, c)| (c.id(), Some(*r)))
+            .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
             .collect();
     }
 
@@ -68,7 +68,7 @@ pub fn side_stack(
         .into_iter()
         .chain(stack.as_rows(n.saturating_sub(max_main)))
         .zip(clients)
-        .map(|(r, c)| (c.id(), Some(r)))
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(r)))
         .collect()
 }
 
@@ -88,7 +88,7 @@ pub fn bottom_stack(
             .as_columns(n)
             .iter()
             .zip(clients)
-            .map(|(r, c)| (c.id(), Some(*r)))
+            .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
             .collect();
     }
 
@@ -99,7 +99,7 @@ pub fn bottom_stack(
         .into_iter()
         .chain(stack.as_columns(n.saturating_sub(max_main)))
         .zip(clients)
-        .map(|(r, c)| (c.id(), Some(r)))
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(r)))
         .collect()
 }
 
@@ -119,9 +119,10 @@ pub fn monocle(
             .map(|c| {
                 let cid = c.id();
                 if cid == fid {
-                    (cid, Some(Region::new(mx, my, mw, mh)))
+                    ResizeAction::new(cid, Some(Region::new(mx, my, mw, mh)))
+                        .with_stacking(StackHint::Top)
                 } else {
-                    (cid, None)
+                    ResizeAction::new(cid, None)
                 }
             })
             .collect()
@@ -130,6 +131,77 @@ pub fn monocle(
     }
 }
 
+/// A three column layout with the main clients stacked in a central column and the remaining
+/// clients split between two columns either side of it.
+///
+/// The central column takes up `ratio` of the screen width and the remaining space is shared
+/// evenly between the left and right columns. The first `max_main` clients are placed in the
+/// central column, with the rest being balanced across the right and then left columns. With two
+/// or fewer clients (or if all clients fit in the main column) this behaves like [side_stack].
+pub fn three_column(
+    clients: &[&Client],
+    focused: Option<Xid>,
+    monitor_region: &Region,
+    max_main: u32,
+    ratio: f32,
+) -> Vec<ResizeAction> {
+    let n = clients.len() as u32;
+
+    if n <= 2 || n <= max_main || max_main == 0 {
+        return side_stack(clients, focused, monitor_region, max_main, ratio);
+    }
+
+    let main_w = ((monitor_region.w as f32) * ratio) as u32;
+    let side_w = (monitor_region.w - main_w) / 2;
+    let (left, rest) = monitor_region.split_at_width(side_w).unwrap();
+    let (main, right) = rest.split_at_width(main_w).unwrap();
+
+    let n_stack = n - max_main;
+    let n_right = n_stack.div_ceil(2);
+
+    main.as_rows(max_main)
+        .into_iter()
+        .chain(right.as_rows(n_right))
+        .chain(left.as_rows(n_stack - n_right))
+        .zip(clients)
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(r)))
+        .collect()
+}
+
+/// A "deck" layout that gives every client the full available space, stacking them on top of one
+/// another with the focused client (or the first client if none of them are focused) on top.
+///
+/// Unlike [monocle], the clients that are not on top are left mapped and rely on z-order to be
+/// hidden, effectively giving a tabbed view of the workspace. The focused client is always the
+/// last action returned so that it is raised above the rest. See [Layout::deck][1] for a layout
+/// that re-applies this on focus changes.
+///
+/// [1]: crate::core::layout::Layout::deck
+pub fn deck(
+    clients: &[&Client],
+    focused: Option<Xid>,
+    monitor_region: &Region,
+    _: u32,
+    _: f32,
+) -> Vec<ResizeAction> {
+    let top = match focused
+        .filter(|&id| clients.iter().any(|c| c.id() == id))
+        .or_else(|| clients.first().map(|c| c.id()))
+    {
+        Some(id) => id,
+        None => return vec![],
+    };
+
+    clients
+        .iter()
+        .filter(|c| c.id() != top)
+        .map(|c| ResizeAction::new(c.id(), Some(*monitor_region)))
+        .chain(std::iter::once(
+            ResizeAction::new(top, Some(*monitor_region)).with_stacking(StackHint::Top),
+        ))
+        .collect()
+}
+
 /// A layout that aims to mimic the feel of having multiple pieces of paper fanned out on a desk.
 ///
 /// Without access to the custom hardware required for 10gui, we instead have to rely on the WM
@@ -145,7 +217,7 @@ pub fn paper(
 ) -> Vec<ResizeAction> {
     let n = clients.len();
     if n == 1 {
-        return vec![(clients[0].id(), Some(*monitor_region))];
+        return vec![ResizeAction::new(clients[0].id(), Some(*monitor_region))];
     }
 
     let (mx, my, mw, mh) = monitor_region.values();
@@ -162,13 +234,13 @@ pub fn paper(
             let cid = c.id();
             if cid == fid {
                 after_focused = true;
-                (cid, Some(Region::new(mx + i as u32 * step, my, cw, mh)))
+                ResizeAction::new(cid, Some(Region::new(mx + i as u32 * step, my, cw, mh)))
             } else {
                 let mut x = mx + i as u32 * step;
                 if after_focused {
                     x += cw - step
                 };
-                (cid, Some(Region::new(x, my, step, mh)))
+                ResizeAction::new(cid, Some(Region::new(x, my, step, mh)))
             }
         })
         .collect()
@@ -187,9 +259,9 @@ fn dwindle_recurisive(
                 .enumerate()
                 .map(|(i, c)| {
                     if i == 0 {
-                        (c.id(), Some(*region))
+                        ResizeAction::new(c.id(), Some(*region))
                     } else {
-                        (c.id(), None)
+                        ResizeAction::new(c.id(), None)
                     }
                 })
                 .collect()
@@ -204,13 +276,13 @@ fn dwindle_recurisive(
 
             let mut vec =
                 dwindle_recurisive(&clients[..clients.len() - 1], &other, !horizontal, min_size);
-            vec.push((clients.last().unwrap().id(), Some(main)));
+            vec.push(ResizeAction::new(clients.last().unwrap().id(), Some(main)));
             vec
         }
     } else {
         clients
             .get(0)
-            .map(|c| vec![(c.id(), Some(*region))])
+            .map(|c| vec![ResizeAction::new(c.id(), Some(*region))])
             .unwrap_or_else(Vec::new)
     }
 }
@@ -245,7 +317,7 @@ pub fn top_stack(
             .as_columns(n)
             .iter()
             .zip(clients)
-            .map(|(r, c)| (c.id(), Some(*r)))
+            .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
             .collect();
     }
 
@@ -256,7 +328,7 @@ pub fn top_stack(
         .into_iter()
         .chain(stack.as_columns(n.saturating_sub(max_main)))
         .zip(clients)
-        .map(|(r, c)| (c.id(), Some(r)))
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(r)))
         .collect()
 }
 
@@ -276,7 +348,7 @@ pub fn left_stack(
             .as_rows(n)
             .iter()
             .zip(clients)
-            .map(|(r, c)| (c.id(), Some(*r)))
+            .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
             .collect();
     }
 
@@ -287,6 +359,74 @@ pub fn left_stack(
         .into_iter()
         .chain(stack.as_rows(n.saturating_sub(max_main)))
         .zip(clients)
-        .map(|(r, c)| (c.id(), Some(r)))
+        .map(|(r, c)| ResizeAction::new(c.id(), Some(r)))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::xconnection::MockXConn;
+
+    test_cases! {
+        deck;
+        args: (focused: Option<Xid>, expected_top: Xid);
+
+        case: focused_client_on_top => (Some(2), 2);
+        case: first_client_when_unfocused => (None, 1);
+        case: first_client_when_focus_is_elsewhere => (Some(42), 1);
+
+        body: {
+            let conn = MockXConn::new(vec![], vec![], vec![]);
+            let clients: Vec<Client> = (1..=3).map(|id| Client::new(&conn, id, 0, &[])).collect();
+            let refs: Vec<&Client> = clients.iter().collect();
+            let r = Region::new(0, 0, 1000, 600);
+
+            let actions = deck(&refs, focused, &r, 1, 0.6);
+            let top = actions.last().unwrap();
+
+            assert_eq!(actions.len(), 3);
+            assert!(actions.iter().all(|a| a.region == Some(r)));
+            assert_eq!(top.id, expected_top);
+            assert_eq!(top.stacking, Some(StackHint::Top));
+        }
+    }
+
+    test_cases! {
+        three_column;
+        args: (n_clients: u32, max_main: u32, expected: Vec<Region>);
+
+        case: degrades_to_side_stack => (2, 1, vec![
+            Region::new(0, 0, 600, 600),
+            Region::new(600, 0, 400, 600),
+        ]);
+        case: one_client_each_side => (3, 1, vec![
+            Region::new(200, 0, 600, 600),
+            Region::new(800, 0, 200, 600),
+            Region::new(0, 0, 200, 600),
+        ]);
+        case: stacked_main_and_balanced_sides => (5, 2, vec![
+            Region::new(200, 0, 600, 300),
+            Region::new(200, 300, 600, 300),
+            Region::new(800, 0, 200, 300),
+            Region::new(800, 300, 200, 300),
+            Region::new(0, 0, 200, 600),
+        ]);
+
+        body: {
+            let conn = MockXConn::new(vec![], vec![], vec![]);
+            let clients: Vec<Client> = (1..=n_clients)
+                .map(|id| Client::new(&conn, id, 0, &[]))
+                .collect();
+            let refs: Vec<&Client> = clients.iter().collect();
+            let r = Region::new(0, 0, 1000, 600);
+
+            let regions: Vec<Region> = three_column(&refs, None, &r, max_main, 0.6)
+                .into_iter()
+                .map(|a| a.region.unwrap())
+                .collect();
+
+            assert_eq!(regions, expected);
+        }
+    }
+}
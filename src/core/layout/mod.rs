@@ -31,7 +31,7 @@
 //!         .as_rows(clients.len() as u32)
 //!         .iter()
 //!         .zip(clients)
-//!         .map(|(r, c)| (c.id(), Some(*r)))
+//!         .map(|(r, c)| ResizeAction::new(c.id(), Some(*r)))
 //!         .collect()
 //! }
 //! ```
@@ -43,8 +43,14 @@
 //! `WindowManager`. If we provide `None` for any of the clients, that client will then instead be
 //! hidden.
 //!
+//! Layout functions are only called when there is at least one client to position, so there is
+//! no need to handle an empty client list: [Layout::arrange][8] returns no actions without calling
+//! the layout function in that case.
+//!
 //! *Note, windows are positioned and mapped in order, meaning that later clients will overlap
-//! those that have already been positioned if any of the Regions overlap one another.*
+//! those that have already been positioned if any of the Regions overlap one another. If a
+//! layout needs explicit control over stacking it can attach a [StackHint][6] to an action
+//! using [with_stacking][7], which is applied after all clients have been positioned.*
 //!
 //! This simple `rows` layout is a sub-set of the behaviour provided by the built in
 //! [side_stack][5] layout (in effect, clamping `max_main` at 0).
@@ -54,6 +60,9 @@
 //! [3]: crate::core::data_types::Region
 //! [4]: crate::core::data_types::Region::as_rows
 //! [5]: crate::core::layout::side_stack
+//! [6]: crate::core::data_types::StackHint
+//! [7]: crate::core::data_types::ResizeAction::with_stacking
+//! [8]: crate::core::layout::Layout::arrange
 
 #[allow(clippy::module_inception)]
 mod layout;
@@ -4,6 +4,9 @@
 ///
 /// NOTE: this explicitly redirects stderr to /dev/null
 ///
+/// Additional environment variables and a working directory can also be given, in which case
+/// the program is run using [spawn_detached][crate::core::helpers::spawn_detached].
+///
 /// ```no_run
 /// # #[macro_use] extern crate penrose;
 /// # use penrose::__test_helpers::*;
@@ -11,6 +14,10 @@
 /// # Box::new(
 /// run_external!("dmenu_run")
 /// # )}
+/// # fn example_with_env() -> TestKeyHandler {
+/// # Box::new(
+/// run_external!("st -e 'nvim notes.md'", &[("EDITOR", "nvim")], Some("/home/me/notes"))
+/// # )}
 /// ```
 #[macro_export]
 macro_rules! run_external {
@@ -19,6 +26,12 @@ macro_rules! run_external {
             $crate::core::helpers::spawn($cmd)
         }) as $crate::core::bindings::KeyEventHandler<_>
     }};
+
+    ($cmd:expr, $env:expr, $cwd:expr) => {{
+        Box::new(move |_: &mut $crate::core::manager::WindowManager<_>| {
+            $crate::core::helpers::spawn_detached($cmd, $env, $cwd)
+        }) as $crate::core::bindings::KeyEventHandler<_>
+    }};
 }
 
 /// Kick off an internal method on the window manager as part of a key binding
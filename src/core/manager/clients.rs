@@ -1,17 +1,17 @@
 //! State and management of clients being managed by Penrose.
 use crate::{
     core::{
-        client::Client,
-        data_types::Region,
+        client::{requests_no_decorations, Client},
+        data_types::{Region, StackHint},
         hooks::HookName,
         manager::event::EventAction,
         ring::Selector,
         xconnection::{
-            Atom, ClientMessageKind, Prop, XClientConfig, XClientHandler, XClientProperties,
-            XEventHandler, XState, Xid,
+            Atom, ClientMessageKind, Prop, WmHintsFlags, XClientConfig, XClientHandler,
+            XClientProperties, XEventHandler, XState, Xid,
         },
     },
-    draw::Color,
+    draw::BorderStyle,
     Result,
 };
 use std::collections::HashMap;
@@ -20,18 +20,105 @@ use tracing::{trace, warn};
 #[derive(Debug)]
 pub struct Clients {
     inner: HashMap<Xid, Client>,
+    // Known client IDs in the order they were first inserted
+    mapping_order: Vec<Xid>,
+    // Known client IDs from bottom to top of the stack
+    stacking_order: Vec<Xid>,
+    // Urgent client IDs in the order that they became urgent
+    urgent_order: Vec<Xid>,
+    // Client IDs in the order that they were last focused, most recent last
+    focus_history: Vec<Xid>,
     focused_client_id: Option<Xid>,
-    focused_border: Color,
-    unfocused_border: Color,
+    focused_border: BorderStyle,
+    unfocused_border: BorderStyle,
+    urgent_border: BorderStyle,
+    border_px: u32,
 }
 
 impl Clients {
-    pub fn new(focused_border: impl Into<Color>, unfocused_border: impl Into<Color>) -> Self {
+    pub fn new(
+        focused_border: impl Into<BorderStyle>,
+        unfocused_border: impl Into<BorderStyle>,
+        urgent_border: impl Into<BorderStyle>,
+        border_px: u32,
+    ) -> Self {
         Self {
             inner: HashMap::new(),
+            mapping_order: vec![],
+            stacking_order: vec![],
+            urgent_order: vec![],
+            focus_history: vec![],
             focused_client_id: None,
             focused_border: focused_border.into(),
             unfocused_border: unfocused_border.into(),
+            urgent_border: urgent_border.into(),
+            border_px,
+        }
+    }
+
+    // Focus takes priority over urgency: clients lose their urgent state once focused.
+    fn border_style(&self, focused: bool, urgent: bool) -> BorderStyle {
+        match (focused, urgent) {
+            (true, _) => self.focused_border,
+            (false, true) => self.urgent_border,
+            (false, false) => self.unfocused_border,
+        }
+    }
+
+    fn set_border<X>(&self, id: Xid, style: BorderStyle, conn: &X) -> Result<()>
+    where
+        X: XClientConfig + XState,
+    {
+        match style {
+            BorderStyle::Solid(color) => Ok(conn.set_client_border_color(id, color)?),
+            BorderStyle::Gradient { .. } => {
+                let (_, _, w, h) = conn.client_geometry(id)?.values();
+                self.draw_border_pixmap(id, style, w, h, conn)
+            }
+        }
+    }
+
+    // Gradient borders are drawn into a pixmap that needs to match the size of the client
+    fn draw_border_pixmap<X>(
+        &self,
+        id: Xid,
+        style: BorderStyle,
+        w: u32,
+        h: u32,
+        conn: &X,
+    ) -> Result<()>
+    where
+        X: XClientConfig,
+    {
+        let border_px = match self.inner.get(&id) {
+            Some(c) if c.undecorated => 0,
+            _ => self.border_px,
+        };
+        let fills = style.border_fills(w, h, border_px);
+        if fills.is_empty() {
+            return Ok(());
+        }
+
+        let (pw, ph) = (w + 2 * border_px, h + 2 * border_px);
+        Ok(conn.set_client_border_pixmap(id, pw, ph, &fills)?)
+    }
+
+    // The given client has been moved or resized so any gradient border it has needs redrawing
+    pub fn client_geometry_changed<X>(&self, id: Xid, r: Region, conn: &X) -> Result<()>
+    where
+        X: XClientConfig,
+    {
+        let urgent = match self.inner.get(&id) {
+            Some(c) => c.urgent,
+            None => return Ok(()),
+        };
+
+        match self.border_style(self.focused_client_id == Some(id), urgent) {
+            style @ BorderStyle::Gradient { .. } => {
+                let (_, _, w, h) = r.values();
+                self.draw_border_pixmap(id, style, w, h, conn)
+            }
+            BorderStyle::Solid(_) => Ok(()),
         }
     }
 
@@ -98,10 +185,13 @@ impl Clients {
 
     pub fn set_focused<X>(&mut self, id: Xid, conn: &X) -> Option<Xid>
     where
-        X: XClientConfig,
+        X: XClientConfig + XState,
     {
         let prev = self.focused_client_id;
         self.focused_client_id = Some(id);
+        self.set_urgent(id, false);
+        self.focus_history.retain(|&i| i != id);
+        self.focus_history.push(id);
 
         if let Some(prev_id) = prev {
             if id != prev_id {
@@ -118,7 +208,16 @@ impl Clients {
     }
 
     pub fn insert(&mut self, id: Xid, c: Client) -> Option<Client> {
-        self.inner.insert(id, c)
+        let urgent = c.urgent;
+        let prev = self.inner.insert(id, c);
+        if prev.is_none() {
+            // newly mapped windows are placed on top of the stack by the X server
+            self.mapping_order.push(id);
+            self.stacking_order.push(id);
+        }
+        self.set_urgent(id, urgent);
+
+        prev
     }
 
     pub fn remove(&mut self, id: Xid) -> Option<Client> {
@@ -126,9 +225,62 @@ impl Clients {
             self.focused_client_id = None;
         }
 
+        self.mapping_order.retain(|&i| i != id);
+        self.stacking_order.retain(|&i| i != id);
+        self.urgent_order.retain(|&i| i != id);
+        self.focus_history.retain(|&i| i != id);
         self.inner.remove(&id)
     }
 
+    // Update the urgent state of a known client, tracking the order in which clients become urgent
+    pub fn set_urgent(&mut self, id: Xid, urgent: bool) {
+        match self.inner.get_mut(&id) {
+            Some(c) => c.urgent = urgent,
+            None => return,
+        }
+
+        if !urgent {
+            self.urgent_order.retain(|&i| i != id);
+        } else if !self.urgent_order.contains(&id) {
+            self.urgent_order.push(id);
+        }
+    }
+
+    // The client that most recently became urgent and has not had focus since
+    pub fn most_recently_urgent(&self) -> Option<Xid> {
+        self.urgent_order.last().copied()
+    }
+
+    // The most recently focused of the given clients
+    pub fn previously_focused(&self, candidates: &[Xid]) -> Option<Xid> {
+        self.focus_history
+            .iter()
+            .rev()
+            .find(|id| candidates.contains(id))
+            .copied()
+    }
+
+    // Track a change in stacking order made by raising or lowering a client
+    pub fn restack(&mut self, id: Xid, hint: StackHint) {
+        if !self.is_known(id) {
+            return;
+        }
+
+        self.stacking_order.retain(|&i| i != id);
+        match hint {
+            StackHint::Top => self.stacking_order.push(id),
+            StackHint::Bottom => self.stacking_order.insert(0, id),
+        }
+    }
+
+    pub fn ids_in_mapping_order(&self) -> &[Xid] {
+        &self.mapping_order
+    }
+
+    pub fn ids_in_stacking_order(&self) -> &[Xid] {
+        &self.stacking_order
+    }
+
     pub fn get(&self, id: Xid) -> Option<&Client> {
         self.inner.get(&id)
     }
@@ -186,20 +338,24 @@ impl Clients {
             if let Err(e) = conn.focus_client(id) {
                 warn!("unable to focus client {}: {}", id, e);
             }
-            conn.change_prop(
-                conn.root(),
-                Atom::NetActiveWindow.as_ref(),
-                Prop::Window(vec![id]),
-            )?;
-            let fb = self.focused_border;
-            if let Err(e) = conn.set_client_border_color(id, fb) {
-                warn!("unable to set client border color for {}: {}", id, e);
-            }
         } else {
             let msg = ClientMessageKind::TakeFocus(id).as_message(conn)?;
             conn.send_client_event(msg)?;
         }
 
+        // Clients using the TakeFocus protocol are still the active window
+        conn.change_prop(
+            conn.root(),
+            Atom::NetActiveWindow.as_ref(),
+            Prop::Window(vec![id]),
+        )?;
+
+        if accepts_focus {
+            if let Err(e) = self.set_border(id, self.focused_border, conn) {
+                warn!("unable to set client border for {}: {}", id, e);
+            }
+        }
+
         // TODO: should this be running the FocusChange hook?
         Ok(())
     }
@@ -220,40 +376,106 @@ impl Clients {
     #[tracing::instrument(level = "trace", skip(self, conn))]
     pub fn client_lost_focus<X>(&mut self, id: Xid, conn: &X)
     where
-        X: XClientConfig,
+        X: XClientConfig + XState,
     {
         if self.focused_client_id == Some(id) {
             self.focused_client_id = None;
         }
 
-        if self.inner.contains_key(&id) {
-            let ub = self.unfocused_border;
+        if let Some(urgent) = self.inner.get(&id).map(|c| c.urgent) {
+            let ub = self.border_style(false, urgent);
             // The target window may have lost focus because it has just been closed and
             // we have not yet updated our state.
-            conn.set_client_border_color(id, ub).unwrap_or(());
+            self.set_border(id, ub, conn).unwrap_or(());
         }
     }
 
-    // The given window ID has had its EWMH name updated by something
+    // The given window ID has had its WM_HINTS updated so re-check whether it is urgent
+    pub fn client_hints_changed<X>(&mut self, id: Xid, conn: &X) -> Result<()>
+    where
+        X: XClientConfig + XClientProperties + XState,
+    {
+        let hints = match conn.get_prop(id, Atom::WmHints.as_ref()) {
+            Ok(Prop::WmHints(hints)) => Some(hints),
+            _ => None,
+        };
+        let focused = self.focused_client_id == Some(id);
+        let urgent = !focused
+            && hints
+                .as_ref()
+                .map(|h| h.flags.contains(WmHintsFlags::URGENCY_HINT))
+                .unwrap_or(false);
+
+        match self.inner.get_mut(&id) {
+            Some(c) => c.wm_hints = hints,
+            None => return Ok(()),
+        }
+        self.set_urgent(id, urgent);
+
+        self.set_border(id, self.border_style(focused, urgent), conn)
+    }
+
+    // The given window ID has had its WM_NORMAL_HINTS updated: returns the workspace of the client
+    // if it is known to us.
+    pub fn client_size_hints_changed<X>(&mut self, id: Xid, conn: &X) -> Option<usize>
+    where
+        X: XClientProperties,
+    {
+        let hints = match conn.get_prop(id, Atom::WmNormalHints.as_ref()) {
+            Ok(Prop::WmNormalHints(hints)) => Some(hints),
+            _ => None,
+        };
+
+        self.inner.get_mut(&id).map(|c| {
+            c.wm_normal_hints = hints;
+            c.workspace
+        })
+    }
+
+    // The given window ID has had its _MOTIF_WM_HINTS updated: returns the workspace of the client
+    // if it is known to us and whether or not it should now be undecorated.
+    pub fn client_decorations_changed<X>(&mut self, id: Xid, conn: &X) -> Option<(usize, bool)>
+    where
+        X: XClientProperties,
+    {
+        let undecorated = requests_no_decorations(conn, id);
+
+        self.inner.get_mut(&id).map(|c| {
+            c.undecorated = undecorated;
+            (c.workspace, undecorated)
+        })
+    }
+
+    // The given window ID has had its EWMH name updated by something: title changes are only
+    // reported for known clients whose name is now different to the one we have stored.
     pub fn client_name_changed<X>(
         &mut self,
         id: Xid,
         is_root: bool,
         conn: &X,
-    ) -> Result<EventAction>
+    ) -> Result<Vec<EventAction>>
     where
         X: XClientProperties,
     {
         let name = conn.client_name(id)?;
+        let mut title_changed = false;
         if !is_root {
             if let Some(c) = self.inner.get_mut(&id) {
+                title_changed = c.wm_name() != name;
                 c.set_name(&name)
             }
         }
 
-        Ok(EventAction::RunHook(HookName::ClientNameUpdated(
-            id, name, is_root,
-        )))
+        let mut actions = vec![EventAction::RunHook(HookName::ClientNameUpdated(
+            id,
+            name.clone(),
+            is_root,
+        ))];
+        if title_changed {
+            actions.push(EventAction::RunHook(HookName::ClientTitleChanged(id, name)));
+        }
+
+        Ok(actions)
     }
 
     pub fn toggle_fullscreen<X>(
@@ -308,19 +530,134 @@ impl Clients {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::xconnection::{self, *};
-    use std::cell::Cell;
+    use crate::{
+        core::xconnection::{self, *},
+        draw::GradientDirection,
+    };
+    use std::cell::{Cell, RefCell};
 
     #[test]
     fn client_lost_focus_on_focused_clears_focused_client_id() {
         let conn = MockXConn::new(vec![], vec![], vec![]);
-        let mut clients = Clients::new(0xffffff, 0x000000);
+        let mut clients = Clients::new(0xffffff, 0x000000, 0xff0000, 2);
 
         clients.focused_client_id = Some(42);
         clients.client_lost_focus(42, &conn);
         assert!(clients.focused_client_id.is_none());
     }
 
+    test_cases! {
+        border_style;
+        args: (focused: bool, urgent: bool, expected: u32);
+
+        case: focused => (true, false, 0xffffff);
+        case: focused_and_urgent => (true, true, 0xffffff);
+        case: urgent => (false, true, 0xff0000);
+        case: normal => (false, false, 0x000000);
+
+        body: {
+            let clients = Clients::new(0xffffff, 0x000000, 0xff0000, 2);
+            assert_eq!(clients.border_style(focused, urgent), BorderStyle::from(expected));
+        }
+    }
+
+    test_cases! {
+        gradient_borders_are_redrawn_when_clients_are_resized;
+        args: (focused: BorderStyle, expected: Vec<(String, Vec<String>)>);
+
+        case: solid => (0xffffff.into(), vec![]);
+        case: gradient => (
+            BorderStyle::gradient(0xffffff, 0x000000, GradientDirection::Vertical),
+            vec![("set_client_border_pixmap".into(), strings!(42, 104, 54))]
+        );
+
+        body: {
+            let conn = crate::__test_helpers::RecordingXConn::init();
+            let mut clients = Clients::new(focused, 0x000000, 0xff0000, 2);
+            clients.insert(42, Client::new(&conn, 42, 0, &[]));
+            clients.focused_client_id = Some(42);
+
+            clients
+                .client_geometry_changed(42, Region::new(10, 10, 100, 50), &conn)
+                .unwrap();
+
+            assert_eq!(conn.calls(), expected);
+        }
+    }
+
+    #[test]
+    fn focusing_a_client_clears_urgency() {
+        let conn = MockXConn::new(vec![], vec![], vec![]);
+        let mut clients = Clients::new(0xffffff, 0x000000, 0xff0000, 2);
+        let mut client = Client::new(&conn, 42, 0, &[]);
+        client.urgent = true;
+        clients.insert(42, client);
+
+        assert_eq!(clients.most_recently_urgent(), Some(42));
+
+        clients.set_focused(42, &conn);
+        assert!(!clients.get(42).unwrap().is_urgent());
+        assert_eq!(clients.most_recently_urgent(), None);
+    }
+
+    struct TitleXConn(RefCell<String>);
+
+    __impl_stub_xcon! {
+        for TitleXConn;
+
+        atom_queries: {}
+        client_properties: {
+            fn mock_get_prop(&self, id: Xid, name: &str) -> xconnection::Result<Prop> {
+                if name == Atom::NetWmName.as_ref() {
+                    Ok(Prop::UTF8String(vec![self.0.borrow().clone()]))
+                } else {
+                    Err(XError::MissingProperty(name.into(), id))
+                }
+            }
+        }
+        client_handler: {}
+        client_config: {}
+        event_handler: {}
+        state: {}
+        conn: {}
+    }
+
+    test_cases! {
+        client_title_changed;
+        args: (new_title: &str, id: Xid, is_root: bool, expect_changed: bool);
+
+        case: new_title => ("updated", 42, false, true);
+        case: same_title => ("original", 42, false, false);
+        case: unknown_client => ("updated", 99, false, false);
+        case: root_window => ("updated", 42, true, false);
+
+        body: {
+            let conn = TitleXConn(RefCell::new("original".into()));
+            let mut clients = Clients::new(0xffffff, 0x000000, 0xff0000, 2);
+            clients.insert(42, Client::new(&conn, 42, 0, &[]));
+
+            conn.0.replace(new_title.into());
+            let actions = clients.client_name_changed(id, is_root, &conn).unwrap();
+
+            let mut expected = vec![EventAction::RunHook(HookName::ClientNameUpdated(
+                id,
+                new_title.into(),
+                is_root,
+            ))];
+            if expect_changed {
+                expected.push(EventAction::RunHook(HookName::ClientTitleChanged(
+                    id,
+                    new_title.into(),
+                )));
+            }
+
+            assert_eq!(actions, expected);
+            if !is_root && id == 42 {
+                assert_eq!(clients.get(42).unwrap().wm_name(), new_title);
+            }
+        }
+    }
+
     struct RecordingXConn {
         positions: Cell<Vec<(Xid, Region)>>,
         maps: Cell<Vec<Xid>>,
@@ -400,9 +737,15 @@ mod tests {
                     (id, client)
                 })
                 .collect(),
+                mapping_order: ids.clone(),
+                stacking_order: ids.clone(),
+                urgent_order: vec![],
+                focus_history: vec![],
                 focused_client_id: None,
                 focused_border: 0xffffff.into(),
                 unfocused_border: 0x000000.into(),
+                urgent_border: 0xff0000.into(),
+                border_px: 2,
             };
 
             let r = Region::new(0, 0, 1000, 800);
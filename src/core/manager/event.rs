@@ -29,8 +29,16 @@ pub enum EventAction {
     ClientFocusLost(Xid),
     /// An X window gained focus
     ClientFocusGained(Xid),
+    /// An X window had its _MOTIF_WM_HINTS property changed
+    ClientDecorationsChanged(Xid),
+    /// An X window was moved or resized
+    ClientGeometryChanged(Xid, Region),
+    /// An X window had its WM_HINTS property changed
+    ClientHintsChanged(Xid),
     /// An X window had its WM_NAME or _NET_WM_NAME property changed
     ClientNameChanged(Xid, bool),
+    /// An X window had its WM_NORMAL_HINTS property changed
+    ClientSizeHintsChanged(Xid),
     /// Move the given client to the workspace at the given index
     ClientToWorkspace(Xid, usize),
     /// An X window was destroyed
@@ -39,6 +47,8 @@ pub enum EventAction {
     DetectScreens,
     /// A client should have focus
     FocusIn(Xid),
+    /// A client has requested to be iconified
+    IconifyClient(Xid),
     /// The workspace on each screen should be layed out again
     LayoutVisible,
     /// Layout the workspace at the given index
@@ -51,6 +61,8 @@ pub enum EventAction {
     RunHook(HookName),
     /// A grabbed keybinding was triggered
     RunKeyBinding(KeyCode),
+    /// A grabbed modifier key was released: run its tap binding if nothing else was pressed
+    RunModifierTapBinding(KeyCode),
     /// A grabbed mouse state was triggered
     RunMouseBinding(MouseEvent),
     /// The active client should be set to this id
@@ -77,6 +89,7 @@ pub(super) fn process_next_event<X: XConn>(
         XEvent::Expose(_) => vec![], // FIXME: work out if this needs handling in the WindowManager
         XEvent::FocusIn(id) => vec![EventAction::FocusIn(id)],
         XEvent::KeyPress(code) => vec![EventAction::RunKeyBinding(code)],
+        XEvent::KeyRelease(code) => vec![EventAction::RunModifierTapBinding(code)],
         XEvent::Leave(p) => vec![
             EventAction::ClientFocusLost(p.id),
             EventAction::SetScreenFromPoint(Some(p.abs)),
@@ -110,6 +123,10 @@ fn process_client_message<X: XConn>(wm: &WindowManager<X>, msg: ClientMessage) -
         Ok(Atom::NetActiveWindow) => vec![EventAction::SetActiveClient(msg.id)],
         Ok(Atom::NetCurrentDesktop) => vec![EventAction::SetActiveWorkspace(data.as_usize()[0])],
         Ok(Atom::NetWmDesktop) => vec![EventAction::ClientToWorkspace(msg.id, data.as_usize()[0])],
+        // ICCCM 4.1.4: IconicState == 3
+        Ok(Atom::WmChangeState) if data.as_usize()[0] == 3 => {
+            vec![EventAction::IconifyClient(msg.id)]
+        }
         Ok(Atom::NetWmState) if is_fullscreen(&data.as_u32()[1..3]) => {
             // _NET_WM_STATE_ADD == 1, _NET_WM_STATE_TOGGLE == 2
             let should_fullscreen = [1, 2].contains(&data.as_usize()[0]);
@@ -127,7 +144,7 @@ fn process_configure_notify(evt: ConfigureEvent) -> Vec<EventAction> {
     if evt.is_root {
         vec![EventAction::DetectScreens]
     } else {
-        vec![]
+        vec![EventAction::ClientGeometryChanged(evt.id, evt.r)]
     }
 }
 
@@ -139,7 +156,17 @@ fn process_configure_request(evt: ConfigureEvent) -> Vec<EventAction> {
     }
 }
 
+// Entering a window only moves focus to it if focus follows the mouse, no mouse buttons are held
+// (we could be part way through dragging another client) and the pointer has moved since the
+// last workspace switch (otherwise this is a client being mapped under a stationary cursor).
 fn process_enter_notify<X: XConn>(wm: &WindowManager<X>, p: PointerChange) -> Vec<EventAction> {
+    if !wm.config.focus_follows_mouse
+        || !wm.drag_origins.is_empty()
+        || wm.focus_suppressed_at == Some(p.abs)
+    {
+        return vec![EventAction::SetScreenFromPoint(Some(p.abs))];
+    }
+
     let mut actions = vec![
         EventAction::ClientFocusGained(p.id),
         EventAction::SetScreenFromPoint(Some(p.abs)),
@@ -174,6 +201,13 @@ fn process_property_notify(evt: PropertyEvent) -> Vec<EventAction> {
         Ok(a) if a == Atom::WmName || a == Atom::NetWmName => {
             vec![EventAction::ClientNameChanged(evt.id, evt.is_root)]
         }
+        Ok(Atom::WmHints) if !evt.is_root => vec![EventAction::ClientHintsChanged(evt.id)],
+        Ok(Atom::WmNormalHints) if !evt.is_root => {
+            vec![EventAction::ClientSizeHintsChanged(evt.id)]
+        }
+        Ok(Atom::MotifWmHints) if !evt.is_root => {
+            vec![EventAction::ClientDecorationsChanged(evt.id)]
+        }
         // TODO: handle other property changes and possibly allow users to process
         //       unknown events?
         _ => vec![EventAction::UnknownPropertyChange(
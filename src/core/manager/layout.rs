@@ -1,9 +1,15 @@
 use crate::{
     core::{
         config::Config,
+        data_types::{Region, ResizeAction, StackHint},
         hooks::HookName,
-        manager::{event::EventAction, util::pad_region},
-        xconnection::XConn,
+        layout::LayoutConf,
+        manager::{
+            event::EventAction,
+            util::{clamp_client_to_region, pad_region},
+        },
+        workspace::ArrangeActions,
+        xconnection::{XConn, Xid},
     },
     Result, WindowManager,
 };
@@ -27,34 +33,116 @@ pub(super) fn apply_layout<X: XConn>(
         None => return Ok(None),
     };
 
+    let mut clients = wm.clients.clients_for_ids(&wm.workspaces[wix].client_ids());
+    clients.retain(|c| !c.minimized);
+
+    // A fullscreen client covers the whole screen so the layout is suspended until it exits
+    if clients.iter().any(|c| c.fullscreen) {
+        return Ok(None);
+    }
+
+    let screen_region = s.region(true);
+    let (lc, aa) = wm
+        .workspaces
+        .get_arrange_actions(wix, screen_region, &clients)?;
+
+    let unpositioned: Vec<Xid> = if wm.workspaces[wix].bounds_floating() {
+        clients
+            .iter()
+            .map(|c| c.id())
+            .filter(|&id| !aa.actions.iter().any(|a| a.id == id))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    // Grab the server while repositioning so that all of the changes land at once, making sure
+    // that we always release the grab even if positioning one of the clients fails.
+    wm.conn.grab_server()?;
+    let res = position_clients(wm, lc, aa, unpositioned, screen_region);
+    let ungrabbed = wm.conn.ungrab_server();
+    res?;
+    ungrabbed?;
+
+    Ok(Some(EventAction::RunHook(HookName::LayoutApplied(wix, i))))
+}
+
+// Position, map and restack clients following the actions returned by a layout
+fn position_clients<X: XConn>(
+    wm: &mut WindowManager<X>,
+    lc: LayoutConf,
+    aa: ArrangeActions,
+    unpositioned: Vec<Xid>,
+    screen_region: Region,
+) -> Result<()> {
     let Config {
-        show_bar,
-        border_px,
-        gap_px,
+        respect_size_hints,
+        respect_resize_increments,
         ..
     } = wm.config;
+    let (gap_px, outer_gap_px) = (wm.gap_px, wm.outer_gap_px);
 
-    let (lc, aa) = wm.workspaces.get_arrange_actions(
-        wix,
-        s.region(show_bar),
-        &wm.clients.clients_for_ids(&wm.workspaces[wix].client_ids()),
-    )?;
+    for id in unpositioned {
+        trace!(id, "clamping unpositioned client to the screen");
+        let border_px = wm.client_border_px(id);
+        clamp_client_to_region(&wm.conn, id, screen_region, border_px)?;
+    }
 
-    for (id, region) in aa.actions {
+    let mut restack = vec![];
+    for ResizeAction {
+        id,
+        region,
+        stacking,
+    } in aa.actions
+    {
         trace!(id, ?region, "positioning client");
+        if let Some(hint) = stacking {
+            restack.push((id, hint));
+        }
         if let Some(region) = region {
-            let reg = pad_region(&region, lc.gapless, gap_px, border_px);
-            wm.conn.position_client(id, reg, border_px, false)?;
+            let border_px = wm.client_border_px(id);
+            let mut reg = pad_region(
+                &region,
+                &screen_region,
+                lc.gapless,
+                gap_px,
+                outer_gap_px,
+                border_px,
+            );
+            if respect_size_hints || respect_resize_increments {
+                if let Some(hints) = wm.clients.get(id).and_then(|c| c.size_hints()) {
+                    reg = if respect_size_hints {
+                        hints.constrain(reg)
+                    } else {
+                        hints.snap_to_increments(reg)
+                    };
+                }
+            }
+            // Skip clients that are already where the layout wants them
+            if wm.geometry_cache.borrow().get(&id) != Some(&(reg, border_px)) {
+                wm.conn.position_client(id, reg, border_px, false)?;
+                wm.geometry_cache.borrow_mut().insert(id, (reg, border_px));
+            }
             wm.clients.map_if_needed(id, &wm.conn)?;
         } else {
             wm.clients.unmap_if_needed(id, &wm.conn)?;
         }
     }
 
-    for id in aa.floating {
+    for (id, hint) in restack {
+        match hint {
+            StackHint::Top => wm.conn.raise_client(id)?,
+            StackHint::Bottom => wm.conn.lower_client(id)?,
+        }
+        wm.clients.restack(id, hint);
+    }
+
+    for id in wm.floating_raise_order(&aa.floating) {
         debug!(id, "mapping floating client above tiled");
         wm.conn.raise_client(id)?;
+        wm.clients.restack(id, StackHint::Top);
     }
+    wm.update_client_stacking()?;
 
-    Ok(Some(EventAction::RunHook(HookName::LayoutApplied(wix, i))))
+    Ok(())
 }
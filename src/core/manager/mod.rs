@@ -1,20 +1,36 @@
 //! The main user API and control logic for Penrose.
+#[cfg(feature = "ipc")]
+use crate::core::ipc::IpcServer;
 use crate::{
     core::{
-        bindings::{KeyBindings, KeyCode, MouseBindings, MouseEvent},
+        bindings::{
+            KeyBindings, KeyCode, ModifierTapBindings, MouseBindings, MouseButton, MouseEvent,
+            MouseEventKind, LOCK_MASKS,
+        },
         client::Client,
-        config::Config,
-        data_types::{Change, Point, Region},
+        config::{Config, FocusAfterClose, TransientPolicy},
+        data_types::{
+            Change, ClientInfo, Point, Region, RelativePosition, SnapRegion, StackHint, Strut,
+            WmState, WorkspaceLayoutState, WorkspaceState,
+        },
+        helpers::spawn,
         hooks::{HookName, Hooks},
+        layout::LayoutConf,
         ring::{Direction, InsertPoint, Selector},
         screen::Screen,
         workspace::Workspace,
-        xconnection::{Atom, ClientMessageKind, WindowState, XConn, Xid},
+        xconnection::{Atom, ClientMessageKind, MapState, Prop, WindowState, XConn, Xid},
     },
+    draw::{preview::preview_regions, KeyPressDraw},
     ErrorHandler, PenroseError, Result,
 };
 use nix::sys::signal::{signal, SigHandler, Signal};
-use std::fmt;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+    time::{Duration, Instant},
+};
 use tracing::Level;
 
 mod clients;
@@ -29,10 +45,32 @@ use event::process_next_event;
 use event::EventAction;
 use layout::{apply_layout, layout_visible};
 use screens::Screens;
+use util::{adjust_floating_region, center_on_screen, pad_region, points_within};
 use workspaces::Workspaces;
 
 use super::hooks::HooksVec;
 
+// The maximum distance in pixels along either axis between two presses of a mouse button for them
+// to be treated as a double click
+const DOUBLE_CLICK_DISTANCE_PX: u32 = 5;
+
+// The smallest width or height in pixels that a floating client can be resized to from the keyboard
+const MIN_FLOATING_PX: u32 = 20;
+
+// The largest gap in pixels that can be set between clients using adjust_gaps
+const MAX_GAP_PX: u32 = 100;
+
+// How often the event loop wakes up to check for queued IPC commands while the X server is idle
+#[cfg(feature = "ipc")]
+const IPC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// TOML documents need a table at the top level so saved layout state is wrapped in a struct
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedLayoutState {
+    workspaces: Vec<WorkspaceLayoutState>,
+}
+
 /// WindowManager is the primary struct / owner of the event loop for penrose.
 ///
 /// It handles most (if not all) of the communication with the underlying [XConn], responding to
@@ -61,8 +99,39 @@ pub struct WindowManager<X: XConn> {
     ///
     /// Will only be None while a hook is running.
     pub(super) hooks: Option<Hooks<X>>,
-    pub(super) previous_workspace: usize,
+    /// Bindings that are run when a bare modifier key is tapped.
+    ///
+    /// Will only be None while a binding is running.
+    pub(super) modifier_tap_bindings: Option<ModifierTapBindings<X>>,
+    pub(super) pending_modifier_tap: Option<KeyCode>,
+    pub(super) last_mouse_press: Option<(MouseButton, Point, Instant)>,
+    pub(super) drag_origins: HashMap<MouseButton, (Point, bool)>,
+    pub(super) focus_suppressed_at: Option<Point>,
+    pub(super) pending_iconify: HashSet<Xid>,
+    pub(super) scratchpads: HashMap<String, Xid>,
+    pub(super) pending_close: HashMap<Xid, Instant>,
+    pub(super) struts: HashMap<Xid, Strut>,
+    pub(super) ignored_clients: HashSet<Xid>,
+    /// The last (region, border) applied to each tiled client by a layout.
+    ///
+    /// Held in a RefCell so that entries can be invalidated from `&self` methods.
+    pub(super) geometry_cache: RefCell<HashMap<Xid, (Region, u32)>>,
+    /// Workspaces that have been shown on a screen at least once.
+    pub(super) shown_workspaces: HashSet<usize>,
+    #[cfg(feature = "ipc")]
+    pub(super) ipc: Option<IpcServer>,
+    /// The workspace each screen was showing before its current one, keyed by screen index.
+    pub(super) previous_workspaces: HashMap<usize, usize>,
+    /// The inner and outer gaps currently applied to tiled clients, overriding the config values.
+    pub(super) gap_px: u32,
+    pub(super) outer_gap_px: u32,
+    /// The inner and outer gaps to restore when gaps are toggled back on.
+    pub(super) hidden_gaps: Option<(u32, u32)>,
+    /// Set when a hook is run or a client, workspace or layout is modified in a way that should
+    /// trigger Hook::state_changed at the end of the current iteration of the event loop.
+    pub(super) state_changed: bool,
     pub(super) running: bool,
+    pub(super) startup_hooks_run: bool,
     pub(super) error_handler: ErrorHandler,
     pub(super) hydrated: bool,
 }
@@ -76,8 +145,28 @@ impl<X: XConn> fmt::Debug for WindowManager<X> {
             .field("screens", &self.screens)
             .field("workspaces", &self.workspaces)
             .field("hooks", &stringify!(self.hooks))
-            .field("previous_workspace", &self.previous_workspace)
+            .field(
+                "modifier_tap_bindings",
+                &stringify!(self.modifier_tap_bindings),
+            )
+            .field("pending_modifier_tap", &self.pending_modifier_tap)
+            .field("last_mouse_press", &self.last_mouse_press)
+            .field("drag_origins", &self.drag_origins)
+            .field("focus_suppressed_at", &self.focus_suppressed_at)
+            .field("pending_iconify", &self.pending_iconify)
+            .field("scratchpads", &self.scratchpads)
+            .field("pending_close", &self.pending_close)
+            .field("struts", &self.struts)
+            .field("ignored_clients", &self.ignored_clients)
+            .field("geometry_cache", &self.geometry_cache)
+            .field("shown_workspaces", &self.shown_workspaces)
+            .field("previous_workspaces", &self.previous_workspaces)
+            .field("gap_px", &self.gap_px)
+            .field("outer_gap_px", &self.outer_gap_px)
+            .field("hidden_gaps", &self.hidden_gaps)
+            .field("state_changed", &self.state_changed)
             .field("running", &self.running)
+            .field("startup_hooks_run", &self.startup_hooks_run)
             .finish()
     }
 }
@@ -86,7 +175,7 @@ impl<X: XConn> WindowManager<X> {
     /// Construct a new window manager instance using a chosen [XConn] backed to communicate
     /// with the X server.
     pub fn new(config: Config, conn: X, hooks: HooksVec<X>, error_handler: ErrorHandler) -> Self {
-        let layouts = config.layouts.clone();
+        let layouts = config.bounded_layouts();
 
         trace!("building initial workspaces");
         let workspaces = Workspaces::new(
@@ -98,8 +187,16 @@ impl<X: XConn> WindowManager<X> {
             config.main_ratio_step,
         );
 
-        let screens = Screens::new(config.bar_height, config.top_bar);
-        let clients = Clients::new(config.focused_border, config.unfocused_border);
+        let mut screens = Screens::new(config.bar_reserved_px(), config.top_bar);
+        screens.set_initial_workspaces(&config.initial_workspaces);
+        screens.set_screen_bars(config.show_bar, config.screen_bars.clone());
+        let clients = Clients::new(
+            config.focused_border,
+            config.unfocused_border,
+            config.urgent_border,
+            config.border_px,
+        );
+        let (gap_px, outer_gap_px) = (config.gap_px, config.outer_gap_px);
 
         Self {
             conn,
@@ -107,9 +204,28 @@ impl<X: XConn> WindowManager<X> {
             clients,
             screens,
             workspaces,
-            previous_workspace: 0,
+            previous_workspaces: HashMap::new(),
+            gap_px,
+            outer_gap_px,
+            hidden_gaps: None,
             hooks: Some(Hooks::new(hooks)),
+            modifier_tap_bindings: Some(ModifierTapBindings::new()),
+            pending_modifier_tap: None,
+            last_mouse_press: None,
+            drag_origins: HashMap::new(),
+            focus_suppressed_at: None,
+            pending_iconify: HashSet::new(),
+            scratchpads: HashMap::new(),
+            pending_close: HashMap::new(),
+            struts: HashMap::new(),
+            ignored_clients: HashSet::new(),
+            geometry_cache: RefCell::new(HashMap::new()),
+            shown_workspaces: HashSet::new(),
+            #[cfg(feature = "ipc")]
+            ipc: None,
+            state_changed: false,
             running: false,
+            startup_hooks_run: false,
             hydrated: true,
             error_handler,
         }
@@ -133,6 +249,8 @@ impl<X: XConn> WindowManager<X> {
 
         trace!("Setting EWMH properties");
         self.conn.set_wm_properties(&self.config.workspaces)?;
+        self.conn
+            .set_current_workspace(self.screens.active_ws_index())?;
 
         trace!("Forcing cursor to first screen");
         Ok(self.conn.warp_cursor(None, &self.screens.inner[0])?)
@@ -141,27 +259,83 @@ impl<X: XConn> WindowManager<X> {
     #[tracing::instrument(level = "debug", err, skip(self))]
     pub(crate) fn try_manage_existing_windows(&mut self) -> Result<()> {
         let classes = str_slice!(self.config.floating_classes);
+        let mut managed = vec![];
         for mut c in self.conn.active_managed_clients(classes)?.into_iter() {
             let id = c.id();
             self.add_client_to_workspace(c.workspace(), id)?;
             self.conn.unmap_client_if_needed(Some(&mut c))?;
             self.clients.insert(id, c);
             self.conn.mark_new_client(id)?;
+            managed.push(id);
+        }
+
+        for id in self.conn.active_clients()? {
+            let viewable = matches!(
+                self.conn.get_window_attributes(id),
+                Ok(a) if a.map_state == MapState::Viewable
+            );
+            if let (true, Some(strut)) = (viewable, self.read_strut(id)) {
+                self.struts.insert(id, strut);
+            }
         }
+        self.screens
+            .set_struts(self.struts.values().copied().collect());
 
-        if let Some(id) = self.workspaces.focused_client(0) {
+        if let Some(id) = self.focusable_client(self.screens.active_ws_index()) {
             self.update_focus(id)?;
         }
+        for id in managed {
+            self.dim_if_unfocused(id)?;
+        }
 
         self.update_known_x_clients()?;
         self.layout_visible()?;
         Ok(())
     }
 
+    // Check with all registered hooks that a new client should be managed. Hooks are unavailable
+    // while another hook is running in which case the client is always managed.
+    fn hooks_allow_managing(&mut self, client: &Client) -> bool {
+        let mut hooks = match self.hooks.take() {
+            Some(hooks) => hooks,
+            None => return true,
+        };
+
+        let mut allowed = true;
+        for hook in hooks.hooks.iter_mut() {
+            match hook.should_manage(self, client) {
+                Ok(true) => (),
+                Ok(false) => {
+                    allowed = false;
+                    break;
+                }
+                Err(e) => (self.error_handler)(e),
+            }
+        }
+
+        self.hooks = Some(hooks);
+        allowed
+    }
+
     #[tracing::instrument(level = "trace", skip(self))]
     fn run_hook(&mut self, hook_name: HookName) {
         use HookName::*;
 
+        if matches!(
+            hook_name,
+            NewClient(_)
+                | RemoveClient(_)
+                | ClientAddedToWorkspace(..)
+                | LayoutChange(_)
+                | WorkspaceChange(..)
+                | ScreenWorkspaceChange(..)
+                | WorkspacesUpdated(..)
+                | ClientFocusChanged(..)
+                | ClientTitleChanged(..)
+        ) {
+            self.state_changed = true;
+        }
+
         let mut hooks = self.hooks.take().unwrap();
 
         let result = match hook_name {
@@ -174,6 +348,9 @@ impl<X: XConn> WindowManager<X> {
             ClientNameUpdated(id, name, is_root) => {
                 hooks.run_on_hook(|h| h.client_name_updated(self, id, &name, is_root))
             }
+            ClientTitleChanged(id, title) => {
+                hooks.run_on_hook(|h| h.client_title_changed(self, id, &title))
+            }
             LayoutApplied(wix, scix) => hooks.run_on_hook(|h| h.layout_applied(self, wix, scix)),
             LayoutChange(wix) => {
                 let i = self.active_screen_index();
@@ -182,6 +359,9 @@ impl<X: XConn> WindowManager<X> {
             WorkspaceChange(active, index) => {
                 hooks.run_on_hook(|h| h.workspace_change(self, active, index))
             }
+            ScreenWorkspaceChange(prev, new, scix) => {
+                hooks.run_on_hook(|h| h.screen_workspace_change(self, prev, new, scix))
+            }
             WorkspacesUpdated(names, wix) => {
                 hooks.run_on_hook(|h| h.workspaces_updated(self, str_slice!(names), wix))
             }
@@ -195,7 +375,11 @@ impl<X: XConn> WindowManager<X> {
             }
             RanderNotify => hooks.run_on_hook(|h| h.randr_notify(self)),
             FocusChange(root) => hooks.run_on_hook(|h| h.focus_change(self, root)),
+            ClientFocusChanged(prev, new) => {
+                hooks.run_on_hook(|h| h.client_focus_changed(self, prev, new))
+            }
             EventHandled => hooks.run_on_hook(|h| h.event_handled(self)),
+            StateChanged => hooks.run_on_hook(|h| h.state_changed(self)),
         };
 
         self.hooks = Some(hooks);
@@ -227,9 +411,17 @@ impl<X: XConn> WindowManager<X> {
         match action {
             ClientFocusGained(id) => self.update_focus(id)?,
             ClientFocusLost(id) => self.clients.client_lost_focus(id, &self.conn),
+            ClientDecorationsChanged(id) => self.client_decorations_changed(id)?,
+            ClientGeometryChanged(id, r) => {
+                self.clients.client_geometry_changed(id, r, &self.conn)?
+            }
+            ClientHintsChanged(id) => self.clients.client_hints_changed(id, &self.conn)?,
+            ClientSizeHintsChanged(id) => self.client_size_hints_changed(id)?,
             ClientNameChanged(id, is_root) => {
-                let action = self.clients.client_name_changed(id, is_root, &self.conn)?;
-                self.handle_event_action(action, None, None)?
+                let actions = self.clients.client_name_changed(id, is_root, &self.conn)?;
+                for action in actions {
+                    self.handle_event_action(action, None, None)?;
+                }
             }
             ClientToWorkspace(id, wix) => self.move_client_to_workspace(id, wix)?,
             DestroyClient(id) => self.remove_client(id)?,
@@ -238,15 +430,21 @@ impl<X: XConn> WindowManager<X> {
                 self.detect_screens()?
             }
             FocusIn(id) => self.clients.focus_in(id, &self.conn)?,
+            IconifyClient(id) => self.handle_iconify_request(id)?,
             LayoutVisible => self.layout_visible()?,
             LayoutWorkspace(wix) => self.apply_layout(wix)?,
             MapWindow(id) => self.handle_map_request(id)?,
             MoveClientIfFloating(id, r) => self.handle_move_if_floating(id, r)?,
             RunHook(hook_name) => self.run_hook(hook_name),
             RunKeyBinding(e) => match key_bindings {
-                Some(kb) => self.run_key_binding(e, kb),
+                Some(kb) => {
+                    let e = e.ignoring_modifiers(LOCK_MASKS);
+                    self.track_modifier_tap(e);
+                    self.run_key_binding(e, kb)
+                }
                 None => return Err(perror!("keybindings can only be triggered from X events")),
             },
+            RunModifierTapBinding(e) => self.run_modifier_tap_binding(e),
             RunMouseBinding(e) => match mouse_bindings {
                 Some(mb) => self.run_mouse_binding(e, mb),
                 None => return Err(perror!("mousebindings can only be triggered from X events")),
@@ -299,17 +497,41 @@ impl<X: XConn> WindowManager<X> {
 
         trace!("grabbing key and mouse bindings");
         self.conn.grab_keys(&key_bindings, &mouse_bindings)?;
+        if let Some(taps) = self.modifier_tap_bindings.as_ref() {
+            if !taps.is_empty() {
+                self.conn
+                    .grab_modifier_taps(&taps.keys().copied().collect::<Vec<_>>())?;
+            }
+        }
 
-        trace!("forcing focus to first workspace");
-        self.focus_workspace(&Selector::Index(0))?;
+        trace!("forcing focus to the workspace on the first screen");
+        let initial = self.screens.get(0).map(|s| s.wix).unwrap_or(0);
+        // The initial workspace is already shown on the first screen so focus_workspace returns
+        // early without focusing it in the workspace ring.
+        self.workspaces.focus(&Selector::Index(initial));
+        self.focus_workspace(&Selector::Index(initial))?;
 
-        self.run_hook(HookName::Startup);
+        trace!("applying initial layout");
+        self.layout_visible()?;
+
+        if !self.startup_hooks_run {
+            self.startup_hooks_run = true;
+            self.run_hook(HookName::Startup);
+        }
         self.running = true;
+        self.state_changed = false;
 
         trace!("entering main event loop");
         while self.running {
-            match self.conn.wait_for_event() {
-                Ok(event) => {
+            // Wake up when a client that was asked to close is due to be killed or IPC commands
+            // may be waiting even if the X server is otherwise idle
+            let event = match self.next_wakeup() {
+                Some(timeout) => self.conn.wait_for_event_timeout(timeout),
+                None => self.conn.wait_for_event().map(Some),
+            };
+
+            match event {
+                Ok(Some(event)) => {
                     let span = span!(target: "penrose", Level::DEBUG, "XEvent", %event);
                     let _enter = span.enter();
                     trace!(details = ?event, "event details");
@@ -325,7 +547,29 @@ impl<X: XConn> WindowManager<X> {
                         }
                     }
 
+                    if let Err(e) = self.kill_unresponsive_clients() {
+                        (self.error_handler)(e);
+                    }
+
+                    #[cfg(feature = "ipc")]
+                    self.handle_ipc_requests();
+
                     self.run_hook(HookName::EventHandled);
+                    self.run_state_changed_hook();
+                    self.conn.flush();
+                }
+
+                Ok(None) => {
+                    if let Err(e) = self.kill_unresponsive_clients() {
+                        (self.error_handler)(e);
+                    }
+
+                    #[cfg(feature = "ipc")]
+                    if self.handle_ipc_requests() {
+                        self.run_hook(HookName::EventHandled);
+                    }
+
+                    self.run_state_changed_hook();
                     self.conn.flush();
                 }
 
@@ -336,13 +580,107 @@ impl<X: XConn> WindowManager<X> {
         Ok(())
     }
 
+    fn run_state_changed_hook(&mut self) {
+        if self.state_changed {
+            self.state_changed = false;
+            self.run_hook(HookName::StateChanged);
+        }
+    }
+
+    // The longest the event loop can wait for an X event before it has work of its own to do
+    fn next_wakeup(&self) -> Option<Duration> {
+        let deadline = self.next_close_deadline();
+
+        #[cfg(feature = "ipc")]
+        if self.ipc.is_some() {
+            return Some(deadline.map_or(IPC_POLL_INTERVAL, |d| d.min(IPC_POLL_INTERVAL)));
+        }
+
+        deadline
+    }
+
+    // Run any queued IPC commands, returning whether or not there were any
+    #[cfg(feature = "ipc")]
+    fn handle_ipc_requests(&mut self) -> bool {
+        let requests = match self.ipc {
+            Some(ref server) => server.pending(),
+            None => return false,
+        };
+        let handled = !requests.is_empty();
+
+        for request in requests {
+            debug!(command = ?request.command, "running IPC command");
+            let response = match request.command.run(self) {
+                Ok(response) => response,
+                Err(e) => format!("error: {}", e),
+            };
+            request.respond(response);
+        }
+
+        handled
+    }
+
     /*
      * Top Level EventAction handlers
      */
 
+    // Move the cursor to the center of the given client (or the focused screen if there is no
+    // client) after a change in focus. Skipped while a mouse drag is in progress or when the
+    // cursor is already inside of the target.
+    fn warp_cursor_to(&self, id: Option<Xid>) -> Result<()> {
+        if !self.config.focus_warps_cursor || !self.drag_origins.is_empty() {
+            return Ok(());
+        }
+
+        let screen = self.screens.focused();
+        let target = match id {
+            Some(id) => self.conn.client_geometry(id).ok(),
+            None => Some(screen.region(false)),
+        };
+        if let (Some(r), Ok(p)) = (target, self.conn.cursor_position()) {
+            if r.contains_point(&p) {
+                return Ok(());
+            }
+        }
+
+        Ok(self.conn.warp_cursor(id, screen)?)
+    }
+
+    // Drop our focused state and revert focus back to the root window. Used when there are no
+    // clients on the active workspace to focus.
+    fn focus_root(&mut self) -> Result<()> {
+        self.focus_root_replacing(None)
+    }
+
+    // As focus_root, but reporting 'removed' as the previously focused client to hooks when
+    // focus is moving because the focused client has just been removed.
+    fn focus_root_replacing(&mut self, removed: Option<Xid>) -> Result<()> {
+        let root = self.conn.root();
+        if let Err(e) = self.conn.focus_client(root) {
+            warn!("unable to focus root window: {}", e);
+        }
+        let prev = removed.or_else(|| self.clients.focused_client_id());
+        self.clients.clear_focused();
+        let active_window = Atom::NetActiveWindow.as_ref();
+        self.conn
+            .change_prop(root, active_window, Prop::Window(vec![0]))?;
+        self.run_hook(HookName::FocusChange(root));
+        if prev.is_some() {
+            self.run_hook(HookName::ClientFocusChanged(prev, None));
+        }
+
+        Ok(())
+    }
+
     // Set the current focus point based on client focus hints
     #[tracing::instrument(level = "trace", err, skip(self))]
     fn update_focus(&mut self, id: Xid) -> Result<()> {
+        self.update_focus_replacing(id, None)
+    }
+
+    // As update_focus, but reporting 'removed' as the previously focused client to hooks when
+    // focus is moving because the focused client has just been removed.
+    fn update_focus_replacing(&mut self, id: Xid, removed: Option<Xid>) -> Result<()> {
         let target = if self.clients.is_known(id) {
             id
         } else {
@@ -351,22 +689,13 @@ impl<X: XConn> WindowManager<X> {
             //        of this client and add it to the client_map. Not if we ever hit this case or
             //        not, and if we do, why we do...
             warn!(id, "An unknown client has gained focus");
-            match self.active_workspace().focused_client() {
+            match self.focusable_client(self.screens.active_ws_index()) {
                 Some(id) => id,
 
                 // The requested id wasn't something we know about and we don't have any clients on the
                 // active workspace so all we can do is drop our focused state and revert focus back to
                 // the root window.
-                None => {
-                    let root = self.conn.root();
-                    if let Err(e) = self.conn.focus_client(root) {
-                        warn!("unable to focus root window: {}", e);
-                    }
-                    let active_window = Atom::NetActiveWindow.as_ref();
-                    self.conn.delete_prop(root, active_window)?;
-                    self.run_hook(HookName::FocusChange(root));
-                    return Ok(());
-                }
+                None => return self.focus_root_replacing(removed),
             }
         };
 
@@ -381,41 +710,184 @@ impl<X: XConn> WindowManager<X> {
         self.focus_screen(&Selector::Condition(&|s| s.wix == wix));
         self.clients
             .set_x_focus(target, accepts_focus, &self.conn)?;
+        self.update_focus_opacity(prev, target)?;
+
+        // Focused floating clients are raised above the other floating clients on their workspace
+        if prev != Some(target) && matches!(self.clients.get(target), Some(c) if c.floating) {
+            self.clients.restack(target, StackHint::Top);
+            self.restack_floating(wix)?;
+        }
 
         if let Some(ws) = self.workspaces.get_mut(wix) {
             ws.focus_client(target);
+            // Only layouts that depend on the focused client need to be re-run here: for
+            // everything else updating borders and _NET_ACTIVE_WINDOW is enough.
             let in_ws = prev.map_or(false, |prev_id| ws.client_ids().contains(&prev_id));
-            if ws.layout_conf().follow_focus && in_ws {
+            if ws.layout_conf().follow_focus && in_ws && prev != Some(target) {
                 if let Err(e) = self.apply_layout(wix) {
                     error!("unable to apply layout on ws {}: {}", wix, e);
                 }
             }
         }
 
+        // Clients that are yet to be mapped are warped to once they have been positioned
+        let mapped = matches!(self.clients.get(target), Some(c) if c.mapped);
+        if prev != Some(target) && mapped {
+            self.warp_cursor_to(Some(target))?;
+        }
+
         self.run_hook(HookName::FocusChange(target));
+        let hook_prev = removed.or(prev);
+        if hook_prev != Some(target) {
+            self.run_hook(HookName::ClientFocusChanged(hook_prev, Some(target)));
+        }
+
         Ok(())
     }
 
+    // Dim the client that has just lost focus and make the newly focused client fully opaque if
+    // unfocused_opacity is set.
+    fn update_focus_opacity(&mut self, prev: Option<Xid>, target: Xid) -> Result<()> {
+        let opacity = match self.config.unfocused_opacity {
+            Some(opacity) => opacity,
+            None => return Ok(()),
+        };
+
+        match prev {
+            Some(id) if id != target && self.should_dim(id) => {
+                self.set_client_opacity(id, opacity)?
+            }
+            _ => (),
+        }
+
+        self.set_client_opacity(target, 1.0)
+    }
+
+    // Opacity is otherwise only updated when focus moves, so clients that are managed without
+    // being given focus need to be dimmed directly.
+    fn dim_if_unfocused(&mut self, id: Xid) -> Result<()> {
+        match self.config.unfocused_opacity {
+            Some(opacity)
+                if self.clients.focused_client_id() != Some(id) && self.should_dim(id) =>
+            {
+                self.set_client_opacity(id, opacity)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn should_dim(&self, id: Xid) -> bool {
+        matches!(self.clients.get(id), Some(c) if !c.dim_exempt && !c.fullscreen)
+    }
+
     // The given window ID has been destroyed so remove our internal state referencing it.
     #[tracing::instrument(level = "trace", err, skip(self))]
     fn remove_client(&mut self, id: Xid) -> Result<()> {
+        self.ignored_clients.remove(&id);
+        self.geometry_cache.borrow_mut().remove(&id);
+        self.release_strut(id)?;
+        let was_focused = self.clients.focused_client_id() == Some(id);
         if let Some(client) = self.clients.remove(id) {
             let wix = client.workspace();
+            let removed_ix = self
+                .workspaces
+                .client_ids(wix)
+                .ok()
+                .and_then(|ids| ids.iter().position(|&i| i == id));
             self.workspaces.remove_client(wix, id);
+            self.apply_auto_layout(wix);
+            self.scratchpads.retain(|_, &mut sid| sid != id);
+            self.pending_close.remove(&id);
+            self.handle_orphaned_transients(id)?;
+
+            let refocus = was_focused && wix == self.screens.active_ws_index();
+            if refocus {
+                self.focus_after_close(wix, removed_ix);
+            }
 
             if self.screens.visible_workspaces().contains(&wix) {
                 self.apply_layout(wix)?;
             }
 
+            if refocus {
+                match self.focusable_client(wix) {
+                    Some(focused) => self.update_focus_replacing(focused, Some(id))?,
+                    None => self.focus_root_replacing(Some(id))?,
+                };
+            }
+
             self.update_known_x_clients()?;
             self.run_hook(HookName::RemoveClient(id));
         } else {
+            self.pending_iconify.remove(&id);
             debug!(id, "attempt to remove unknown client");
         }
 
         Ok(())
     }
 
+    // Move focus within a workspace after its focused client (previously at 'removed_ix') has
+    // been closed, following the focus_after_close strategy from the user's config.
+    fn focus_after_close(&mut self, wix: usize, removed_ix: Option<usize>) {
+        let ids = match self.workspaces.client_ids(wix) {
+            Ok(ids) => ids,
+            Err(_) => return,
+        };
+
+        let target = match self.config.focus_after_close {
+            FocusAfterClose::Clamped => return,
+            FocusAfterClose::NextInStack => removed_ix
+                .and_then(|ix| ids.get(ix))
+                .or_else(|| ids.last())
+                .copied(),
+            FocusAfterClose::Master => ids.first().copied(),
+            FocusAfterClose::PreviousFocused => self.clients.previously_focused(&ids),
+        };
+
+        if let (Some(id), Some(ws)) = (target, self.workspaces.get_mut(wix)) {
+            ws.focus_client(id);
+        }
+    }
+
+    // Transient windows that are still open when their parent is removed are either closed or
+    // floated in the center of their screen depending on the user's config.
+    fn handle_orphaned_transients(&mut self, parent: Xid) -> Result<()> {
+        let orphans: Vec<(Xid, usize)> = self
+            .clients
+            .matching_clients(&Selector::Condition(&|c| c.transient_for == Some(parent)))
+            .iter()
+            .map(|c| (c.id(), c.workspace()))
+            .collect();
+
+        for (id, wix) in orphans {
+            match self.config.orphaned_transients {
+                TransientPolicy::Close => self.close_client(id)?,
+
+                TransientPolicy::Float => {
+                    self.clients.modify(id, |c| {
+                        c.floating = true;
+                        c.transient_for = None;
+                    });
+
+                    if let Some((_, s)) = self.screens.indexed_screen_for_workspace(wix) {
+                        let r = s.region(true);
+                        let (_, _, w, h) = self.conn.client_geometry(id)?.values();
+                        let reg = center_on_screen(&r, w, h);
+                        self.conn
+                            .position_client(id, reg, self.client_border_px(id), true)?;
+                    }
+
+                    if self.screens.visible_workspaces().contains(&wix) {
+                        self.apply_layout(wix)?;
+                    }
+                }
+            }
+        }
+
+        self.conn.flush();
+        Ok(())
+    }
+
     #[tracing::instrument(level = "trace", err, skip(self))]
     fn move_client_to_workspace(&mut self, id: Xid, wix: usize) -> Result<()> {
         let current_wix = match self.clients.workspace_index_for_client(id) {
@@ -425,12 +897,12 @@ impl<X: XConn> WindowManager<X> {
 
         if current_wix != wix {
             self.workspaces.remove_client(current_wix, id);
+            self.apply_auto_layout(current_wix);
             self.add_client_to_workspace(wix, id)?;
             self.clients.set_client_workspace(id, wix);
 
             if self.screens.visible_workspaces().contains(&wix) {
-                let s = self.screens.focused();
-                self.conn.warp_cursor(Some(id), s)?;
+                self.warp_cursor_to(Some(id))?;
             } else {
                 self.clients.unmap_if_needed(id, &self.conn)?;
             }
@@ -445,19 +917,68 @@ impl<X: XConn> WindowManager<X> {
     /// [Workspace] instances if needed.
     #[tracing::instrument(level = "trace", err, skip(self))]
     pub fn detect_screens(&mut self) -> Result<()> {
+        let visible = self.screens.visible_workspaces();
+        let active = self.screens.active_ws_index_checked();
         let actions = self
             .screens
             .update_known_screens(&self.conn, self.workspaces.len())?;
 
-        self.handle_event_actions(actions)
+        // Workspaces can lose or regain a screen when monitors are added or removed
+        let now_visible = self.screens.visible_workspaces();
+        for (i, &wix) in now_visible.iter().enumerate() {
+            self.apply_screen_layout(wix, i);
+        }
+        for &wix in visible.iter().filter(|wix| !now_visible.contains(wix)) {
+            debug!(wix, "workspace is no longer visible");
+            for id in self.workspaces.client_ids(wix)? {
+                self.clients.unmap_if_needed(id, &self.conn)?;
+            }
+        }
+        for &wix in now_visible.iter().filter(|wix| !visible.contains(wix)) {
+            for id in self.workspaces.client_ids(wix)? {
+                let minimized = self.clients.get(id).map(|c| c.minimized).unwrap_or(false);
+                if !minimized {
+                    self.clients.map_if_needed(id, &self.conn)?;
+                }
+            }
+        }
+
+        self.handle_event_actions(actions)?;
+
+        let now_active = self.screens.active_ws_index();
+        if active.is_some() && active != Some(now_active) {
+            self.conn.set_current_workspace(now_active)?;
+            match self.focusable_client(now_active) {
+                Some(id) => self.update_focus(id)?,
+                None => self.focus_root()?,
+            }
+        }
+
+        Ok(())
     }
 
     // Map a new client window.
     #[tracing::instrument(level = "trace", err, skip(self))]
     fn handle_map_request(&mut self, id: Xid) -> Result<()> {
         trace!(id, "handling map request");
-        let classes = str_slice!(self.config.floating_classes);
-        let client = Client::new(&self.conn, id, self.screens.active_ws_index(), classes);
+        if self.ignored_clients.contains(&id) {
+            return Ok(self.conn.map_client(id)?);
+        }
+
+        let mut client = Client::new(&self.conn, id, self.screens.active_ws_index(), &[]);
+        if !self.hooks_allow_managing(&client) {
+            debug!(id, "a hook vetoed managing this client");
+            self.ignored_clients.insert(id);
+            return Ok(self.conn.map_client(id)?);
+        }
+
+        let rule_region =
+            util::apply_window_rules(&self.config, &mut client, self.workspaces.len());
+        let scratchpad = self.unclaimed_scratchpad(&client);
+        if scratchpad.is_some() {
+            client.floating = true;
+            client.set_workspace(self.screens.active_ws_index());
+        }
         let is_managed_type = self.conn.is_managed_client(&client);
         trace!(id, ?client.wm_name, ?client.wm_class, ?client.wm_type, "client details");
 
@@ -476,6 +997,10 @@ impl<X: XConn> WindowManager<X> {
         }
 
         let (wix, wm_hints, wm_managed, floating) = details.unwrap();
+        if let Some(name) = scratchpad {
+            debug!(id, %name, "claiming client as scratchpad");
+            self.scratchpads.insert(name, id);
+        }
 
         if let Some(ref wmh) = wm_hints {
             if wmh.initial_state == WindowState::Withdrawn {
@@ -484,34 +1009,75 @@ impl<X: XConn> WindowManager<X> {
             }
         }
 
+        // Clients are able to request iconic state before we have seen their map request
+        let iconify = self.pending_iconify.remove(&id);
+
         if !is_managed_type {
-            return Ok(self.conn.map_client(id)?);
+            self.conn.map_client(id)?;
+            return self.reserve_strut(id);
         }
 
         if wm_managed {
             self.add_client_to_workspace(wix, id)?;
         }
 
-        if floating {
+        if self.scratchpads.values().any(|&sid| sid == id) {
+            self.center_floating_client(id, wix)?;
+        } else if let (true, Some(r)) = (floating, rule_region) {
+            self.conn
+                .position_client(id, r, self.client_border_px(id), true)?;
+        } else if let (true, Some(r)) = (floating, self.transient_region(id, wix)) {
+            self.conn
+                .position_client(id, r, self.client_border_px(id), true)?;
+        } else if floating && matches!(self.clients.get(id), Some(c) if c.transient_for.is_some()) {
+            // Dialogs whose parent we don't know about are centered on the screen instead
+            self.center_floating_client(id, wix)?;
+        } else if floating {
             if let Some((_, s)) = self.screens.indexed_screen_for_workspace(wix) {
                 util::position_floating_client(
                     &self.conn,
                     id,
-                    s.region(self.config.show_bar),
-                    self.config.border_px,
+                    s.region(true),
+                    self.client_border_px(id),
                 )?
             }
         }
 
         self.conn.mark_new_client(id)?;
+        if iconify {
+            self.set_minimized(id, true)?;
+            self.dim_if_unfocused(id)?;
+            return self.update_known_x_clients();
+        }
+
         self.update_focus(id)?;
+        self.dim_if_unfocused(id)?;
         self.update_known_x_clients()?;
 
         if wix == self.screens.active_ws_index() {
             self.apply_layout(wix)?;
             self.clients.map_if_needed(id, &self.conn)?;
-            let s = self.screens.focused();
-            self.conn.warp_cursor(Some(id), s)?;
+            self.warp_cursor_to(Some(id))?;
+        }
+
+        Ok(())
+    }
+
+    // A client asking to be iconified may not have been mapped yet, in which case we hold on to
+    // the request until we see its map request.
+    fn handle_iconify_request(&mut self, id: Xid) -> Result<()> {
+        let wix = match self.clients.get(id) {
+            Some(c) => c.workspace(),
+            None => {
+                trace!(id, "deferring iconify request for unknown client");
+                self.pending_iconify.insert(id);
+                return Ok(());
+            }
+        };
+
+        self.set_minimized(id, true)?;
+        if self.screens.visible_workspaces().contains(&wix) {
+            self.apply_layout(wix)?;
         }
 
         Ok(())
@@ -521,7 +1087,7 @@ impl<X: XConn> WindowManager<X> {
         if let Some(client) = self.clients.get(id) {
             if client.floating {
                 debug!(id, region = ?r, "repositioning floating window");
-                let bpx = self.config.border_px;
+                let bpx = self.client_border_px(id);
                 self.conn.position_client(id, r, bpx, true)?;
             }
         }
@@ -534,9 +1100,53 @@ impl<X: XConn> WindowManager<X> {
     }
 
     fn handle_unmap_notify(&mut self, id: Xid) -> Result<()> {
+        self.release_strut(id)?;
+        // Minimized clients are unmapped by us and need to keep their Iconic state
+        if matches!(self.clients.get(id), Some(c) if c.minimized) {
+            return Ok(());
+        }
+
         Ok(self.conn.set_client_state(id, WindowState::Withdrawn)?)
     }
 
+    // The space reserved by a dock, preferring _NET_WM_STRUT_PARTIAL over _NET_WM_STRUT
+    fn read_strut(&self, id: Xid) -> Option<Strut> {
+        [Atom::NetWmStrutPartial, Atom::NetWmStrut]
+            .iter()
+            .find_map(|atom| match self.conn.get_prop(id, atom.as_ref()) {
+                Ok(Prop::Bytes(vals)) => Strut::from_cardinals(&vals),
+                _ => None,
+            })
+    }
+
+    // Docks and panels reserve space at the edges of the screen while they are mapped
+    fn reserve_strut(&mut self, id: Xid) -> Result<()> {
+        match self.read_strut(id) {
+            Some(strut) => {
+                debug!(id, ?strut, "reserving space for dock");
+                self.struts.insert(id, strut);
+                self.update_struts()
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn release_strut(&mut self, id: Xid) -> Result<()> {
+        if self.struts.remove(&id).is_some() {
+            debug!(id, "releasing space reserved by dock");
+            self.update_struts()?;
+        }
+
+        Ok(())
+    }
+
+    fn update_struts(&mut self) -> Result<()> {
+        let actions = self
+            .screens
+            .set_struts(self.struts.values().copied().collect());
+        self.handle_event_actions(actions)
+    }
+
     // NOTE: This defers control of the [WindowManager] to the user's key-binding action
     //       which can lead to arbitrary calls to public methods on the [WindowManager]
     //       including mutable methods.
@@ -550,11 +1160,64 @@ impl<X: XConn> WindowManager<X> {
         }
     }
 
+    // A tap is only pending while the most recent key press was a bare modifier with a tap
+    // binding: any other key press in between cancels it.
+    fn track_modifier_tap(&mut self, k: KeyCode) {
+        let is_tap = match self.modifier_tap_bindings.as_ref() {
+            Some(taps) => taps.contains_key(&k),
+            None => false,
+        };
+        self.pending_modifier_tap = if is_tap { Some(k) } else { None };
+    }
+
+    // NOTE: This defers control of the [WindowManager] to the user's key-binding action
+    //       which can lead to arbitrary calls to public methods on the [WindowManager]
+    //       including mutable methods.
+    #[tracing::instrument(level = "debug", skip(self, k), fields(k.code, k.mask))]
+    fn run_modifier_tap_binding(&mut self, k: KeyCode) {
+        let pending = match self.pending_modifier_tap.take() {
+            Some(pending) if pending.code == k.code => pending,
+            _ => return,
+        };
+
+        let mut taps = match self.modifier_tap_bindings.take() {
+            Some(taps) => taps,
+            None => return,
+        };
+
+        if let Some(action) = taps.get_mut(&pending) {
+            if let Err(e) = action(self) {
+                (self.error_handler)(e);
+            }
+        }
+
+        self.modifier_tap_bindings = Some(taps);
+    }
+
     // NOTE: This defers control of the [WindowManager] to the user's mouse-binding action
     //       which can lead to arbitrary calls to public methods on the [WindowManager]
     //       including mutable methods.
     #[tracing::instrument(level = "debug", skip(self, e, bindings), fields(?e.state, ?e.kind))]
-    fn run_mouse_binding(&mut self, e: MouseEvent, bindings: &mut MouseBindings<X>) {
+    fn run_mouse_binding(&mut self, mut e: MouseEvent, bindings: &mut MouseBindings<X>) {
+        if e.kind == MouseEventKind::Press && self.is_double_click(&e, bindings) {
+            e.kind = MouseEventKind::DoubleClick;
+        }
+
+        match e.kind {
+            MouseEventKind::Press | MouseEventKind::DoubleClick => {
+                self.drag_origins.insert(e.state.button, (e.rpt, false));
+            }
+            MouseEventKind::Release => {
+                self.drag_origins.remove(&e.state.button);
+            }
+            MouseEventKind::Motion => {
+                if !self.drag_threshold_crossed(&e) {
+                    return;
+                }
+                self.focus_from_motion(e.id);
+            }
+        }
+
         if let Some(action) = bindings.get_mut(&(e.kind, e.state.clone())) {
             // ignoring Child handlers and SIGCHILD
             if let Err(e) = action(self, &e) {
@@ -563,6 +1226,106 @@ impl<X: XConn> WindowManager<X> {
         }
     }
 
+    // A press is treated as a double click if it follows a press of the same button within the
+    // configured interval and close to the same point. Presses are only converted if there is a
+    // DoubleClick binding for them, otherwise they are run as a normal Press.
+    fn is_double_click(&mut self, e: &MouseEvent, bindings: &MouseBindings<X>) -> bool {
+        let now = Instant::now();
+        let interval = Duration::from_millis(self.config.double_click_ms);
+        let close = |p: Point| points_within(p, e.rpt, DOUBLE_CLICK_DISTANCE_PX);
+
+        let is_double = match self.last_mouse_press.take() {
+            Some((button, p, t)) => {
+                button == e.state.button && close(p) && now.duration_since(t) < interval
+            }
+            None => false,
+        };
+
+        if is_double && bindings.contains_key(&(MouseEventKind::DoubleClick, e.state.clone())) {
+            return true;
+        }
+
+        self.last_mouse_press = Some((e.state.button, e.rpt, now));
+        false
+    }
+
+    // Motion over a client that is not currently focused moves focus to it when focus follows
+    // the mouse, unless a button is held as part of a drag.
+    fn focus_from_motion(&mut self, id: Xid) {
+        let should_focus = self.config.focus_follows_mouse
+            && self.drag_origins.is_empty()
+            && self.clients.is_known(id)
+            && self.clients.focused_client_id() != Some(id);
+
+        if should_focus {
+            if let Err(e) = self.update_focus(id) {
+                (self.error_handler)(e);
+            }
+        }
+    }
+
+    // Motion is only dispatched once the cursor has moved further than the configured threshold
+    // from the point where the button was pressed. Motion for buttons that we have not seen a
+    // press for is always dispatched.
+    fn drag_threshold_crossed(&mut self, e: &MouseEvent) -> bool {
+        let threshold = self.config.drag_threshold_px;
+        match self.drag_origins.get_mut(&e.state.button) {
+            Some((p, crossed)) => {
+                if !*crossed && !points_within(*p, e.rpt, threshold) {
+                    *crossed = true;
+                }
+                *crossed
+            }
+            None => true,
+        }
+    }
+
+    // The border to use when positioning the given client: clients that have asked to not be
+    // decorated are positioned without one.
+    pub(crate) fn client_border_px(&self, id: Xid) -> u32 {
+        match self.clients.get(id) {
+            Some(c) if c.undecorated => 0,
+            _ => self.config.border_px,
+        }
+    }
+
+    fn client_decorations_changed(&mut self, id: Xid) -> Result<()> {
+        let (wix, undecorated) = match self.clients.client_decorations_changed(id, &self.conn) {
+            Some(res) => res,
+            None => return Ok(()),
+        };
+
+        debug!(id, undecorated, "client decoration hints changed");
+        self.geometry_cache.borrow_mut().remove(&id);
+        let floating = self.clients.get(id).map(|c| c.floating).unwrap_or(false);
+
+        if floating {
+            let r = self.conn.client_geometry(id)?;
+            self.conn
+                .position_client(id, r, self.client_border_px(id), true)?;
+        } else if self.screens.visible_workspaces().contains(&wix) {
+            self.apply_layout(wix)?;
+        }
+
+        Ok(())
+    }
+
+    // Tiled clients need to be laid out again if they are being sized according to their hints
+    fn client_size_hints_changed(&mut self, id: Xid) -> Result<()> {
+        let wix = match self.clients.client_size_hints_changed(id, &self.conn) {
+            Some(wix) => wix,
+            None => return Ok(()),
+        };
+
+        self.geometry_cache.borrow_mut().remove(&id);
+        let hinted = self.config.respect_size_hints || self.config.respect_resize_increments;
+        if hinted && self.screens.visible_workspaces().contains(&wix) {
+            self.apply_layout(wix)?;
+        }
+
+        Ok(())
+    }
+
     fn set_active_client(&mut self, id: Xid) -> Result<()> {
         self.focus_client(&Selector::WinId(id))
             .map_err(|_| PenroseError::UnknownClient(id))
@@ -577,6 +1340,11 @@ impl<X: XConn> WindowManager<X> {
             None => self.conn.cursor_position()?,
         };
 
+        // The pointer has moved since we last switched workspace
+        if matches!(self.focus_suppressed_at, Some(p) if p != point) {
+            self.focus_suppressed_at = None;
+        }
+
         self.focus_screen(&Selector::Condition(&|s: &Screen| s.contains(point)));
         Ok(())
     }
@@ -585,29 +1353,105 @@ impl<X: XConn> WindowManager<X> {
     // be triggered by user key bindings as well as applications requesting full screen as well.
     // TODO: should something going fullscreen also hide unmaged windows?
     fn set_fullscreen(&mut self, id: Xid, should_fullscreen: bool) -> Result<()> {
-        let (currently_fullscreen, wix) = self
+        let (currently_fullscreen, wix, floating) = self
             .clients
             .get(id)
-            .map(|c| (c.fullscreen, c.workspace()))
+            .map(|c| (c.fullscreen, c.workspace(), c.floating))
             .ok_or(PenroseError::UnknownClient(id))?;
 
         if currently_fullscreen == should_fullscreen {
             return Ok(()); // Client is already in the correct state, we shouldn't have been called
         }
+        self.geometry_cache.borrow_mut().remove(&id);
+        self.state_changed = true;
 
         let r = match self.screen(&Selector::Condition(&|s| s.wix == wix)) {
             Some(s) => s.region(false),
             None => return Ok(()),
         };
 
+        // Tiled clients are restored by the layout but floating clients need to be returned to
+        // where they were before they were made fullscreen
+        if should_fullscreen && floating {
+            let r = self.conn.client_geometry(id).ok();
+            self.clients.modify(id, |c| c.floating_region = r);
+        }
+
         let client_ids = self.workspaces.client_ids(wix)?;
         let actions = self
             .clients
             .toggle_fullscreen(id, wix, &client_ids, r, &self.conn)?;
 
+        if should_fullscreen {
+            self.restack_client(id, StackHint::Top)?;
+        } else if floating {
+            if let Some(r) = self.clients.get(id).and_then(|c| c.floating_region) {
+                self.conn
+                    .position_client(id, r, self.client_border_px(id), true)?;
+            }
+        }
+
         self.handle_event_actions(actions)
     }
 
+    fn set_minimized(&mut self, id: Xid, should_minimize: bool) -> Result<()> {
+        let currently_minimized = self
+            .clients
+            .get(id)
+            .map(|c| c.minimized)
+            .ok_or(PenroseError::UnknownClient(id))?;
+
+        if currently_minimized == should_minimize {
+            return Ok(());
+        }
+
+        self.clients.modify(id, |c| c.minimized = should_minimize);
+        self.state_changed = true;
+        if should_minimize {
+            self.clients.unmap_if_needed(id, &self.conn)?;
+            self.conn.set_client_state(id, WindowState::Iconic)?;
+
+            // Minimized clients can't hold focus as they are no longer visible
+            if self.clients.focused_client_id() == Some(id) {
+                let wix = self.clients.workspace_index_for_client(id);
+                match wix.and_then(|wix| self.focusable_client(wix)) {
+                    Some(next) => self.update_focus(next)?,
+                    None => self.focus_root()?,
+                }
+            }
+        } else {
+            self.conn.set_client_state(id, WindowState::Normal)?;
+            // Floating clients are not mapped again by apply_layout
+            let wix = self.clients.workspace_index_for_client(id);
+            if matches!(wix, Some(wix) if self.screens.visible_workspaces().contains(&wix)) {
+                self.clients.map_if_needed(id, &self.conn)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // The client that should be given focus on a workspace: its focused client unless that has
+    // been minimized, in which case the next client in the stack that is still visible.
+    fn focusable_client(&self, wix: usize) -> Option<Xid> {
+        let ws = self.workspaces.get(wix)?;
+        let ids = ws.client_ids();
+        let ix = ids
+            .iter()
+            .position(|&id| Some(id) == self.workspaces.focused_client(wix))?;
+
+        ids.iter()
+            .cycle()
+            .skip(ix)
+            .take(ids.len())
+            .find(|&&id| !self.is_minimized(id))
+            .copied()
+    }
+
+    fn is_minimized(&self, id: Xid) -> bool {
+        matches!(self.clients.get(id), Some(c) if c.minimized)
+    }
+
     /*
      * Common mid level actions that make up larger event response handlers.
      */
@@ -629,6 +1473,9 @@ impl<X: XConn> WindowManager<X> {
     fn update_x_workspace_details(&mut self) -> Result<()> {
         let names = self.workspaces.workspace_names();
         self.conn.update_desktops(&names)?;
+        // adding or removing workspaces can change the index of the active workspace
+        self.conn
+            .set_current_workspace(self.screens.active_ws_index())?;
         self.run_hook(HookName::WorkspacesUpdated(
             names,
             self.screens.active_ws_index(),
@@ -638,8 +1485,71 @@ impl<X: XConn> WindowManager<X> {
     }
 
     fn update_known_x_clients(&self) -> Result<()> {
-        let ids = self.clients.all_known_ids();
-        Ok(self.conn.update_known_clients(&ids)?)
+        let ids = self.managed_ids(self.clients.ids_in_mapping_order());
+        self.conn.update_known_clients(&ids)?;
+        self.update_client_stacking()
+    }
+
+    fn update_client_stacking(&self) -> Result<()> {
+        let ids = self.managed_ids(self.clients.ids_in_stacking_order());
+        Ok(self.conn.update_client_stacking(&ids)?)
+    }
+
+    // Docks and other unmanaged windows are known to us but are not reported to pagers
+    fn managed_ids(&self, ids: &[Xid]) -> Vec<Xid> {
+        ids.iter()
+            .copied()
+            .filter(|&id| matches!(self.clients.get(id), Some(c) if self.conn.is_managed_client(c)))
+            .collect()
+    }
+
+    // Raise or lower a client, keeping _NET_CLIENT_LIST_STACKING up to date
+    fn restack_client(&mut self, id: Xid, hint: StackHint) -> Result<()> {
+        match hint {
+            StackHint::Top => self.conn.raise_client(id)?,
+            StackHint::Bottom => self.conn.lower_client(id)?,
+        }
+        self.clients.restack(id, hint);
+        self.update_client_stacking()
+    }
+
+    // Floating clients in the order they should be raised: preserving their current relative
+    // stacking order, but with sticky clients and scratchpads kept above other floating clients
+    fn floating_raise_order(&self, ids: &[Xid]) -> Vec<Xid> {
+        let stacking = self.clients.ids_in_stacking_order();
+        let mut ids = ids.to_vec();
+        ids.sort_by_key(|&id| {
+            let pinned = matches!(self.clients.get(id), Some(c) if c.sticky)
+                || self.scratchpads.values().any(|&sid| sid == id);
+            (pinned, stacking.iter().position(|&i| i == id))
+        });
+
+        ids
+    }
+
+    // Raise the floating clients on a workspace back above its tiled clients
+    fn restack_floating(&mut self, wix: usize) -> Result<()> {
+        let clients = self
+            .clients
+            .clients_for_ids(&self.workspaces.get_workspace(wix)?.client_ids());
+
+        // A fullscreen client needs to stay above everything else on the workspace
+        if clients.iter().any(|c| c.fullscreen) {
+            return Ok(());
+        }
+
+        let floating: Vec<Xid> = clients
+            .iter()
+            .filter(|c| c.floating && !c.minimized)
+            .map(|c| c.id())
+            .collect();
+
+        for id in self.floating_raise_order(&floating) {
+            self.conn.raise_client(id)?;
+            self.clients.restack(id, StackHint::Top);
+        }
+
+        self.update_client_stacking()
     }
 
     fn focus_screen(&mut self, sel: &Selector<'_, Screen>) -> &Screen {
@@ -663,8 +1573,10 @@ impl<X: XConn> WindowManager<X> {
 
     #[tracing::instrument(level = "trace", err, skip(self))]
     fn add_client_to_workspace(&mut self, wix: usize, id: Xid) -> Result<()> {
+        self.geometry_cache.borrow_mut().remove(&id);
         self.clients.modify(id, |c| c.set_workspace(wix));
         if let Some(action) = self.workspaces.add_client(wix, id)? {
+            self.apply_auto_layout(wix);
             self.conn.set_client_workspace(id, wix)?;
             self.handle_event_action(action, None, None)?;
         }
@@ -672,6 +1584,180 @@ impl<X: XConn> WindowManager<X> {
         Ok(())
     }
 
+    // Sticky clients follow the focused screen as it changes workspace, so they are moved to the
+    // new workspace rather than being unmapped along with the rest of the old workspace.
+    fn move_sticky_clients(&mut self, from: usize, to: usize) -> Result<()> {
+        let sticky = self.sticky_client_ids(from)?;
+        self.move_clients_between_workspaces(&sticky, from, to)
+    }
+
+    fn sticky_client_ids(&self, wix: usize) -> Result<Vec<Xid>> {
+        Ok(self
+            .workspaces
+            .client_ids(wix)?
+            .into_iter()
+            .filter(|&id| matches!(self.clients.get(id), Some(c) if c.sticky))
+            .collect())
+    }
+
+    fn move_clients_between_workspaces(
+        &mut self,
+        ids: &[Xid],
+        from: usize,
+        to: usize,
+    ) -> Result<()> {
+        for &id in ids {
+            self.workspaces.remove_client(from, id);
+            self.add_client_to_workspace(to, id)?;
+        }
+
+        Ok(())
+    }
+
+    // The name of the first scratchpad matching this client that does not yet have a client
+    fn unclaimed_scratchpad(&self, client: &Client) -> Option<String> {
+        self.config
+            .scratchpads
+            .iter()
+            .find(|s| !self.scratchpads.contains_key(&s.name) && s.matches.matches(client))
+            .map(|s| s.name.clone())
+    }
+
+    // The region centered over the parent of a transient client, kept within the screen holding
+    // the given workspace. None if the client is not transient for a known client.
+    fn transient_region(&self, id: Xid, wix: usize) -> Option<Region> {
+        let parent = self.clients.get(id)?.transient_for?;
+        if !self.clients.is_known(parent) {
+            return None;
+        }
+
+        let (_, s) = self.screens.indexed_screen_for_workspace(wix)?;
+        let center = self.conn.client_geometry(parent).ok()?.center();
+        let (_, _, w, h) = self.conn.client_geometry(id).ok()?.values();
+        let r = Region::new(
+            (center.x - (w / 2) as i32).max(0) as u32,
+            (center.y - (h / 2) as i32).max(0) as u32,
+            w,
+            h,
+        );
+
+        Some(r.clamped_within(&s.region(true)))
+    }
+
+    // Place a client in the center of the screen holding the given workspace, shrinking it if it
+    // is larger than the screen.
+    fn center_floating_client(&self, id: Xid, wix: usize) -> Result<()> {
+        if let Some((_, s)) = self.screens.indexed_screen_for_workspace(wix) {
+            let r = s.region(true);
+            let (_, _, w, h) = self.conn.client_geometry(id)?.values();
+            let reg = center_on_screen(&r, w, h);
+            self.conn
+                .position_client(id, reg, self.client_border_px(id), true)?;
+        }
+
+        Ok(())
+    }
+
+    // Move a scratchpad client to the given workspace (from wherever it currently is) as a
+    // floating client and focus it.
+    fn show_scratchpad(&mut self, id: Xid, from: usize, to: usize) -> Result<()> {
+        self.workspaces.remove_client(from, id);
+        if from != to {
+            self.apply_auto_layout(from);
+            if self.screens.visible_workspaces().contains(&from) {
+                self.apply_layout(from)?;
+            }
+        }
+
+        self.clients.modify(id, |c| c.floating = true);
+        self.set_minimized(id, false)?;
+        self.add_client_to_workspace(to, id)?;
+        self.center_floating_client(id, to)?;
+        self.clients.map_if_needed(id, &self.conn)?;
+        self.update_focus(id)?;
+        self.apply_layout(to)?;
+        self.warp_cursor_to(Some(id))
+    }
+
+    // Hidden scratchpads are unmapped and held outside of any workspace until they are next shown
+    fn hide_scratchpad(&mut self, id: Xid, wix: usize) -> Result<()> {
+        self.workspaces.remove_client(wix, id);
+        self.apply_auto_layout(wix);
+        self.clients.unmap_if_needed(id, &self.conn)?;
+
+        match self.focusable_client(wix) {
+            Some(focused) => self.update_focus(focused)?,
+            None => self.focus_root()?,
+        };
+
+        self.apply_layout(wix)
+    }
+
+    // Called whenever a workspace is placed on a screen: the first time each workspace is shown it
+    // switches to the default layout for that screen, if there is one.
+    fn apply_screen_layout(&mut self, wix: usize, screen_index: usize) {
+        if !self.shown_workspaces.insert(wix) {
+            return;
+        }
+
+        let symbol = match self
+            .config
+            .screen_layouts
+            .iter()
+            .find(|rule| rule.screen == screen_index)
+        {
+            Some(rule) => rule.symbol.clone(),
+            None => return,
+        };
+
+        let ws = match self.workspaces.get_mut(wix) {
+            Some(ws) => ws,
+            None => return,
+        };
+
+        if ws.layout_symbol() != symbol {
+            if ws.try_set_layout(&symbol).is_some() {
+                debug!(
+                    wix,
+                    screen_index,
+                    ?symbol,
+                    "applying default layout for screen"
+                );
+                self.run_hook(HookName::LayoutChange(wix));
+            } else {
+                warn!(?symbol, "screen layout references an unknown layout");
+            }
+        }
+    }
+
+    // Called whenever the number of clients on a workspace changes: switch to the layout from the
+    // first matching auto layout rule, if any. The workspace is not laid out again here.
+    fn apply_auto_layout(&mut self, wix: usize) {
+        let n_clients = match self.workspaces.get(wix) {
+            Some(ws) => ws.len(),
+            None => return,
+        };
+
+        let symbol = match self
+            .config
+            .auto_layout_rules
+            .iter()
+            .find(|r| r.matches(n_clients))
+        {
+            Some(rule) => rule.symbol.clone(),
+            None => return,
+        };
+
+        let ws = &mut self.workspaces[wix];
+        if ws.layout_symbol() != symbol {
+            if ws.try_set_layout(&symbol).is_some() {
+                self.run_hook(HookName::LayoutChange(wix));
+            } else {
+                warn!(?symbol, "auto layout rule references an unknown layout");
+            }
+        }
+    }
+
     /*
      * Public methods that can be triggered by user bindings or directly in the
      * user's main.rs
@@ -706,6 +1792,11 @@ impl<X: XConn> WindowManager<X> {
         self.clients.focused_client_id()
     }
 
+    /// The currently focused [Client] if there is one
+    pub fn focused_client(&self) -> Option<&Client> {
+        self.clients.focused_client()
+    }
+
     /// Cycle between known [screens][Screen]. Does not wrap from first to last
     pub fn cycle_screen(&mut self, direction: Direction) -> Result<()> {
         let old_wix = self.screens.focused().wix;
@@ -722,15 +1813,38 @@ impl<X: XConn> WindowManager<X> {
     /// Cycle between [workspaces][1] on the current [screen][2].
     ///
     /// This method will pull workspaces to the active screen if they are currently displayed on
-    /// another screen.
+    /// another screen. If [workspace_switch_wraps][3] is not set then cycling past the first or
+    /// last workspace is a no-op.
     ///
     /// [1]: Workspace
     /// [2]: Screen
+    /// [3]: crate::core::config::Config::workspace_switch_wraps
     pub fn cycle_workspace(&mut self, direction: Direction) -> Result<()> {
+        if !self.config.workspace_switch_wraps && self.workspaces.would_wrap(direction) {
+            return Ok(());
+        }
+
         let i = self.workspaces.cycle_workspace(direction);
         self.focus_workspace(&Selector::Index(i))
     }
 
+    /// Cycle between [workspaces][1] on the current [screen][2], skipping those that have no
+    /// clients.
+    ///
+    /// Unlike [cycle_workspace][3] this always wraps at the first and last workspace. If no other
+    /// workspace has any clients then this is a no-op.
+    ///
+    /// [1]: Workspace
+    /// [2]: Screen
+    /// [3]: WindowManager::cycle_workspace
+    pub fn cycle_occupied_workspace(&mut self, direction: Direction) -> Result<()> {
+        let active = self.screens.active_ws_index();
+        match self.workspaces.next_occupied(active, direction) {
+            Some(i) => self.focus_workspace(&Selector::Index(i)),
+            None => Ok(()),
+        }
+    }
+
     /// Move the currently focused [Workspace] to the next [Screen] in 'direction'
     pub fn drag_workspace(&mut self, direction: Direction) -> Result<()> {
         let wix = self.screens.active_ws_index();
@@ -738,17 +1852,63 @@ impl<X: XConn> WindowManager<X> {
         self.focus_workspace(&Selector::Index(wix)) // focus_workspace will pull it to the new screen
     }
 
+    /// Swap the [Workspace] shown on the focused [Screen] with the one shown on the next screen.
+    ///
+    /// Clients remain on their current workspaces (other than sticky clients, which stay on
+    /// their screen) and focus follows the focused workspace to its new screen. This is a no-op
+    /// if there is only a single screen attached.
+    pub fn swap_screens(&mut self) -> Result<()> {
+        let n_screens = self.screens.n_screens();
+        if n_screens < 2 {
+            return Ok(());
+        }
+
+        let (a, b) = (
+            self.screens.focused_index(),
+            (self.screens.focused_index() + 1) % n_screens,
+        );
+        let (wix_a, wix_b) = (self.screens.inner[a].wix, self.screens.inner[b].wix);
+        self.screens.inner[a].wix = wix_b;
+        self.screens.inner[b].wix = wix_a;
+        self.previous_workspaces.insert(a, wix_a);
+        self.previous_workspaces.insert(b, wix_b);
+
+        // sticky clients stay on the screen they were on rather than following their workspace
+        let (sticky_a, sticky_b) = (
+            self.sticky_client_ids(wix_a)?,
+            self.sticky_client_ids(wix_b)?,
+        );
+        self.move_clients_between_workspaces(&sticky_a, wix_a, wix_b)?;
+        self.move_clients_between_workspaces(&sticky_b, wix_b, wix_a)?;
+
+        // re-apply layouts as screen dimensions may differ
+        self.apply_layout(wix_a)?;
+        self.apply_layout(wix_b)?;
+
+        let actions = self.screens.focus_screen(&Selector::Index(b));
+        self.warp_cursor_to(self.workspaces.get_workspace(wix_a)?.focused_client())?;
+        self.run_hook(HookName::ScreenWorkspaceChange(wix_a, wix_b, a));
+        self.run_hook(HookName::ScreenWorkspaceChange(wix_b, wix_a, b));
+
+        self.handle_event_actions(actions)
+    }
+
     /// Cycle focus between [clients][1] for the active [Workspace]
     ///
     /// [1]: Client
     pub fn cycle_client(&mut self, direction: Direction) -> Result<()> {
         let wix = self.screens.active_ws_index();
-        let res = self.workspaces.cycle_client(wix, direction);
-        // 1st parameter is not needed because self.state.clients.focused_client_id has not been updated
-        if let Some((_, new)) = res {
-            self.update_focus(new)?;
-            let screen = self.screens.focused();
-            self.conn.warp_cursor(Some(new), screen)?;
+        let n_clients = self.workspaces.get(wix).map(|ws| ws.len()).unwrap_or(0);
+
+        // Minimized clients are skipped: cycling through every client returns focus in the ring
+        // to where it started if they are all minimized.
+        for _ in 0..n_clients {
+            // 1st parameter is not needed because self.state.clients.focused_client_id has not been updated
+            match self.workspaces.cycle_client(wix, direction) {
+                Some((_, new)) if !self.is_minimized(new) => return self.update_focus(new),
+                Some(_) => continue,
+                None => break,
+            }
         }
 
         Ok(())
@@ -761,6 +1921,11 @@ impl<X: XConn> WindowManager<X> {
             None => return Err(PenroseError::NoMatchingElement),
         };
 
+        // Minimized clients are restored rather than focused while they are hidden
+        if self.is_minimized(id) {
+            self.restore_client(&Selector::WinId(id))?;
+        }
+
         if let Some(wid) = self.active_workspace().focused_client() {
             if wid == id {
                 return Ok(id);
@@ -769,12 +1934,32 @@ impl<X: XConn> WindowManager<X> {
 
         // update focused client if there is a new client that is in focus
         self.update_focus(id)?;
-        let screen = self.screens.focused();
-        self.conn.warp_cursor(Some(id), screen)?;
 
         Ok(id)
     }
 
+    /// Focus the [Client] that most recently became urgent, switching to its [Workspace] if
+    /// needed.
+    ///
+    /// Focusing the client clears its urgent state. If there are no urgent clients then this is a
+    /// no-op.
+    pub fn focus_urgent(&mut self) -> Result<()> {
+        let (id, wix) = match self
+            .clients
+            .most_recently_urgent()
+            .and_then(|id| self.clients.get(id))
+        {
+            Some(c) => (c.id(), c.workspace()),
+            None => return Ok(()),
+        };
+
+        if wix != self.screens.active_ws_index() {
+            self.focus_workspace(&Selector::Index(wix))?;
+        }
+
+        self.focus_client(&Selector::WinId(id)).map(|_| ())
+    }
+
     /// Rotate the [Client] stack on the active [Workspace].
     ///
     /// This maintains the current window layout but permutes the positions of each window within
@@ -782,6 +1967,7 @@ impl<X: XConn> WindowManager<X> {
     pub fn rotate_clients(&mut self, direction: Direction) -> Result<()> {
         let wix = self.screens.active_ws_index();
         self.workspaces.rotate_clients(wix, direction);
+        self.state_changed = true;
         self.apply_layout(wix)
     }
 
@@ -790,9 +1976,98 @@ impl<X: XConn> WindowManager<X> {
         if let Some(id) = self.clients.focused_client_id() {
             let wix = self.screens.active_ws_index();
             self.workspaces.drag_client(wix, direction);
+            self.state_changed = true;
+            self.apply_layout(wix)?;
+            self.update_focus(id)?;
+            self.warp_cursor_to(Some(id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Move focus to the nearest visible [Client] in the given direction from the focused client.
+    ///
+    /// Focus is left unchanged if there is no client in that direction or if more than one client
+    /// is an equally good match.
+    pub fn focus_direction(&mut self, direction: RelativePosition) -> Result<()> {
+        if let Some(id) = self.neighbour_in_direction(direction) {
+            self.focus_client(&Selector::WinId(id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Swap the focused [Client] with the nearest visible client in the given direction, using the
+    /// same rules as [focus_direction][WindowManager::focus_direction] to find the client to swap
+    /// with. Does nothing if there is no such client.
+    pub fn swap_direction(&mut self, direction: RelativePosition) -> Result<()> {
+        let (id, other) = match (
+            self.clients.focused_client_id(),
+            self.neighbour_in_direction(direction),
+        ) {
+            (Some(id), Some(other)) => (id, other),
+            _ => return Ok(()),
+        };
+
+        let wix = self.screens.active_ws_index();
+        if self.workspaces.swap_clients(wix, id, other) {
+            self.state_changed = true;
+            self.apply_layout(wix)?;
+            self.update_focus(id)?;
+            self.warp_cursor_to(Some(id))?;
+        }
+
+        Ok(())
+    }
+
+    // The visible client on the active workspace in the given direction from the focused client
+    fn neighbour_in_direction(&self, direction: RelativePosition) -> Option<Xid> {
+        let focused = self.clients.focused_client_id()?;
+        let regions: Vec<(Xid, Region)> = self
+            .active_workspace()
+            .iter()
+            .filter(|&&id| matches!(self.clients.get(id), Some(c) if c.mapped))
+            .flat_map(|&id| self.conn.client_geometry(id).map(|r| (id, r)))
+            .collect();
+
+        let (_, from) = regions.iter().find(|(id, _)| *id == focused)?;
+        let candidates: Vec<(Xid, Region)> = regions
+            .iter()
+            .filter(|(id, _)| *id != focused)
+            .copied()
+            .collect();
+
+        util::neighbour_in_direction(*from, &candidates, direction)
+    }
+
+    /// Promote the focused [Client] to the head of the stack on the active [Workspace].
+    ///
+    /// Calling this again while the promoted client is still focused at the head of the stack
+    /// will return it to its original position, so a single binding can both promote and demote.
+    pub fn toggle_master(&mut self) -> Result<()> {
+        if let Some(id) = self.clients.focused_client_id() {
+            let wix = self.screens.active_ws_index();
+            self.workspaces.toggle_master(wix);
+            self.state_changed = true;
+            self.apply_layout(wix)?;
+            self.update_focus(id)?;
+            self.warp_cursor_to(Some(id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the focused [Client] into the master position on the active [Workspace].
+    ///
+    /// If the focused client is already the master then it is swapped with the first client in
+    /// the rest of the stack instead. Focus moves to the new master client.
+    pub fn promote_focused(&mut self) -> Result<()> {
+        let wix = self.screens.active_ws_index();
+        if let Some(id) = self.workspaces.promote_focused(wix) {
+            self.state_changed = true;
             self.apply_layout(wix)?;
             self.update_focus(id)?;
-            self.conn.warp_cursor(Some(id), self.screens.focused())?;
+            self.warp_cursor_to(Some(id))?;
         }
 
         Ok(())
@@ -808,6 +2083,82 @@ impl<X: XConn> WindowManager<X> {
         self.apply_layout(wix)
     }
 
+    /// Cycle forward through only the [layouts][1] on the active [Workspace] whose symbols are in
+    /// `symbols`.
+    ///
+    /// If the current layout is not one of `symbols` then the first of them that is available is
+    /// used instead. This is a no-op if none of `symbols` match an available layout.
+    ///
+    /// [1]: crate::core::layout::Layout
+    pub fn cycle_layout_filtered(&mut self, symbols: &[&str]) -> Result<()> {
+        let wix = self.screens.active_ws_index();
+        let prev = self.workspaces.current_layout_symbol(wix).to_string();
+        self.workspaces.cycle_layout_filtered(wix, symbols);
+
+        if self.workspaces.current_layout_symbol(wix) == prev {
+            return Ok(());
+        }
+
+        self.run_hook(HookName::LayoutChange(wix));
+        self.apply_layout(wix)
+    }
+
+    /// The regions that clients on the active [Workspace] would be positioned in if the
+    /// [layout][1] matching `symbol` was applied. The active layout is not modified.
+    ///
+    /// [1]: crate::core::layout::Layout
+    pub fn layout_preview_regions(&self, symbol: &str) -> Result<Vec<Region>> {
+        let wix = self.screens.active_ws_index();
+        let Config { border_px, .. } = self.config;
+        let (gap_px, outer_gap_px) = (self.gap_px, self.outer_gap_px);
+
+        let mut clients = self
+            .clients
+            .clients_for_ids(&self.workspaces.client_ids(wix)?);
+        clients.retain(|c| !c.minimized);
+
+        let s = self.screens.focused();
+        let r = s.region(true);
+        let (lc, aa) = self
+            .workspaces
+            .get_workspace(wix)?
+            .preview_arrange(symbol, r, &clients)
+            .ok_or_else(|| perror!("unknown layout symbol: {}", symbol))?;
+
+        if lc.floating {
+            return Ok(vec![]);
+        }
+
+        Ok(aa
+            .actions
+            .into_iter()
+            .flat_map(|action| action.region)
+            .map(|region| pad_region(&region, &r, lc.gapless, gap_px, outer_gap_px, border_px))
+            .collect())
+    }
+
+    /// Draw an outline of where clients on the active [Workspace] would be positioned by the
+    /// [layout][1] matching `symbol` and wait for the user to confirm (Return) or cancel
+    /// (Escape) the layout change. Returns whether or not the layout was applied.
+    ///
+    /// [1]: crate::core::layout::Layout
+    pub fn preview_layout<D: KeyPressDraw>(&mut self, symbol: &str, drw: &mut D) -> Result<bool> {
+        let regions = self.layout_preview_regions(symbol)?;
+        let color = self.config.focused_border.primary_color();
+        let confirmed = preview_regions(drw, &regions, color)?;
+
+        if confirmed {
+            let wix = self.screens.active_ws_index();
+            if let Some(ws) = self.workspaces.workspace_mut(&Selector::Index(wix)) {
+                ws.try_set_layout(symbol);
+            }
+            self.run_hook(HookName::LayoutChange(wix));
+            self.apply_layout(wix)?;
+        }
+
+        Ok(confirmed)
+    }
+
     /// Increase or decrease the number of clients in the main area by 1.
     ///
     /// The change is applied to the active [layout][1] on the [Workspace] that currently holds
@@ -817,6 +2168,7 @@ impl<X: XConn> WindowManager<X> {
     pub fn update_max_main(&mut self, change: Change) -> Result<()> {
         let wix = self.screens.active_ws_index();
         self.workspaces.update_max_main(wix, change);
+        self.state_changed = true;
         self.apply_layout(wix)
     }
 
@@ -829,9 +2181,58 @@ impl<X: XConn> WindowManager<X> {
     pub fn update_main_ratio(&mut self, change: Change) -> Result<()> {
         let wix = self.screens.active_ws_index();
         self.workspaces.update_main_ratio(wix, change);
+        self.state_changed = true;
         self.apply_layout(wix)
     }
 
+    /// Toggle the gaps between tiled clients off and back on again.
+    ///
+    /// Toggling gaps off removes both the inner and outer gaps, remembering their previous values
+    /// so that they can be restored when gaps are toggled back on.
+    pub fn toggle_gaps(&mut self) -> Result<()> {
+        match self.hidden_gaps.take() {
+            Some((gap_px, outer_gap_px)) => {
+                self.gap_px = gap_px;
+                self.outer_gap_px = outer_gap_px;
+            }
+            None => {
+                self.hidden_gaps = Some((self.gap_px, self.outer_gap_px));
+                self.gap_px = 0;
+                self.outer_gap_px = 0;
+            }
+        }
+
+        self.layout_visible()
+    }
+
+    /// Toggle the space reserved for a status bar off on every screen and back on again.
+    ///
+    /// This only changes the region that clients are laid out in (the bar itself is left alone)
+    /// and toggling back on again restores the configured settings for each screen. Space reserved
+    /// by docks and panels is kept either way.
+    pub fn toggle_bar(&mut self) -> Result<()> {
+        let actions = self.screens.toggle_bars();
+        self.handle_event_actions(actions)
+    }
+
+    /// Increase or decrease the gap between tiled clients by `step` pixels.
+    ///
+    /// The gap is clamped between 0 and 100 pixels. If gaps are currently toggled off they are
+    /// restored before being adjusted.
+    pub fn adjust_gaps(&mut self, change: Change, step: u32) -> Result<()> {
+        if let Some((gap_px, outer_gap_px)) = self.hidden_gaps.take() {
+            self.gap_px = gap_px;
+            self.outer_gap_px = outer_gap_px;
+        }
+
+        self.gap_px = match change {
+            Change::More => self.gap_px.saturating_add(step).min(MAX_GAP_PX),
+            Change::Less => self.gap_px.saturating_sub(step),
+        };
+
+        self.layout_visible()
+    }
+
     /// Shut down the WindowManager, running any required cleanup and exiting penrose
     ///
     /// **NOTE**: any registered hooks on the `WindowManager` will still run following calling this
@@ -853,35 +2254,197 @@ impl<X: XConn> WindowManager<X> {
         self.workspaces.current_layout_symbol(wix)
     }
 
-    /// Set the root X window name. Useful for exposing information to external programs
-    pub fn set_root_window_name(&self, s: impl AsRef<str>) -> Result<()> {
-        Ok(self.conn.set_root_window_name(s.as_ref())?)
+    /// A snapshot of the current workspace, layout and focus state for use by status bars.
+    ///
+    /// See [WmState::to_json] for rendering the snapshot for external programs.
+    pub fn state(&self) -> WmState {
+        let active = self.screens.active_ws_index();
+        let workspaces = self
+            .workspaces
+            .matching_workspaces(&Selector::Any)
+            .into_iter()
+            .enumerate()
+            .map(|(ix, ws)| WorkspaceState {
+                name: ws.name().to_string(),
+                occupied: !ws.is_empty(),
+                focused: ix == active,
+            })
+            .collect();
+
+        WmState {
+            workspaces,
+            layout: self.current_layout_symbol().to_string(),
+            focused_title: self.focused_client().map(|c| c.wm_name().to_string()),
+        }
     }
 
-    /// Set the insert point for new clients. Default is to insert at index 0.
-    pub fn set_client_insert_point(&mut self, cip: InsertPoint) -> Result<()> {
-        self.workspaces.set_client_insert_point(cip);
+    /// A read-only snapshot of every managed client in the order they were first mapped.
+    ///
+    /// This uses the state cached by the [WindowManager] rather than querying the X server.
+    pub fn client_snapshot(&self) -> Vec<ClientInfo> {
+        let focused = self.clients.focused_client_id();
 
-        Ok(())
+        self.clients
+            .ids_in_mapping_order()
+            .iter()
+            .flat_map(|&id| self.clients.get(id))
+            .map(|c| ClientInfo {
+                id: c.id(),
+                workspace: c.workspace(),
+                focused: focused == Some(c.id()),
+                floating: c.is_floating(),
+                class: c.class().to_string(),
+                title: c.wm_name().to_string(),
+            })
+            .collect()
     }
 
-    /// Set the displayed workspace for the focused screen to be `index` in the list of
-    /// workspaces passed at `init`.
+    /// The current layout settings of every [Workspace]: the active [layout][1] along with the
+    /// `max_main` and `ratio` of each available layout.
     ///
-    /// A common way to use this method is in a `refMap` section when generating your keybindings
-    /// and using the [index_selectors][1] helper method to make the required [selectors][2].
+    /// This can be restored using [restore_layout_state][WindowManager::restore_layout_state]
+    /// in order to preserve your arrangement when restarting penrose in place.
     ///
-    /// [1]: crate::core::helpers::index_selectors
-    /// [2]: crate::core::ring::Selector
-    pub fn focus_workspace(&mut self, selector: &Selector<'_, Workspace>) -> Result<()> {
-        let ix = self.screens.focused().wix;
-        if self.workspaces.would_focus(ix, selector) {
-            return Ok(());
+    /// [1]: crate::core::layout::Layout
+    pub fn layout_state(&self) -> Vec<WorkspaceLayoutState> {
+        self.workspaces
+            .matching_workspaces(&Selector::Any)
+            .iter()
+            .map(|ws| ws.layout_state())
+            .collect()
+    }
+
+    /// Restore layout settings previously generated by
+    /// [layout_state][WindowManager::layout_state] and re-layout all visible workspaces.
+    ///
+    /// Saved state is matched to workspaces by name and to layouts by symbol: anything that no
+    /// longer exists in the current config is ignored.
+    pub fn restore_layout_state(&mut self, state: &[WorkspaceLayoutState]) -> Result<()> {
+        let visible = self.screens.visible_workspaces();
+        let mut changed = vec![];
+
+        for ws_state in state.iter() {
+            let sel = Selector::Condition(&|ws: &Workspace| ws.name() == ws_state.name);
+            if let Some(wix) = self.workspaces.index(&sel) {
+                let ws = &mut self.workspaces[wix];
+                let prev = ws.layout_symbol().to_string();
+                ws.restore_layout_state(ws_state);
+                if visible.contains(&wix) && ws.layout_symbol() != prev {
+                    changed.push(wix);
+                }
+            }
+        }
+
+        for wix in changed {
+            self.run_hook(HookName::LayoutChange(wix));
+        }
+
+        self.layout_visible()
+    }
+
+    /// Write the current [layout_state][WindowManager::layout_state] to `path` as TOML.
+    #[cfg(feature = "serde")]
+    pub fn save_layout_state(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let saved = SavedLayoutState {
+            workspaces: self.layout_state(),
+        };
+        let s = toml::to_string(&saved)
+            .map_err(|e| perror!("unable to serialize layout state: {}", e))?;
+
+        Ok(std::fs::write(path, s)?)
+    }
+
+    /// Restore layout state previously written to `path` using
+    /// [save_layout_state][WindowManager::save_layout_state].
+    ///
+    /// This should be called after [init][WindowManager::init]. If `path` does not exist then
+    /// this is a no-op so that it can be called unconditionally on startup.
+    #[cfg(feature = "serde")]
+    pub fn load_layout_state(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let s = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let saved: SavedLayoutState = toml::from_str(&s)?;
+
+        self.restore_layout_state(&saved.workspaces)
+    }
+
+    /// The [LayoutConf] for the [layout][1] currently being used on the active workspace
+    ///
+    /// [1]: crate::core::layout::Layout
+    pub fn current_layout_conf(&self) -> Option<LayoutConf> {
+        let wix = self.screens.active_ws_index();
+        self.workspaces.get(wix).map(|ws| ws.layout_conf())
+    }
+
+    /// Set the root X window name. Useful for exposing information to external programs
+    pub fn set_root_window_name(&self, s: impl AsRef<str>) -> Result<()> {
+        Ok(self.conn.set_root_window_name(s.as_ref())?)
+    }
+
+    /// Set the insert point for new clients. Default is to insert at index 0.
+    pub fn set_client_insert_point(&mut self, cip: InsertPoint) -> Result<()> {
+        self.workspaces.set_client_insert_point(cip);
+
+        Ok(())
+    }
+
+    /// Allow [startup][crate::core::hooks::Hook::startup] hooks to run again the next time that
+    /// [WindowManager::grab_keys_and_run] is called.
+    ///
+    /// Startup hooks are otherwise only run the first time that the event loop is started.
+    pub fn reset_startup_hooks(&mut self) {
+        self.startup_hooks_run = false;
+    }
+
+    /// Set the [ModifierTapBindings] that should be run when a bare modifier key is tapped.
+    ///
+    /// This must be called before [WindowManager::grab_keys_and_run] in order for the modifier
+    /// keys to be grabbed.
+    pub fn set_modifier_tap_bindings(&mut self, bindings: ModifierTapBindings<X>) {
+        self.modifier_tap_bindings = Some(bindings);
+    }
+
+    /// Accept commands from external scripts using the given [IpcServer].
+    ///
+    /// Pending commands are run by the main event loop in between handling X events. While a
+    /// server is set the event loop also wakes up periodically to check for commands so that
+    /// they are run promptly even when there are no X events to process.
+    #[cfg(feature = "ipc")]
+    pub fn set_ipc_server(&mut self, server: IpcServer) {
+        self.ipc = Some(server);
+    }
+
+    /// Set the displayed workspace for the focused screen to be `index` in the list of
+    /// workspaces passed at `init`.
+    ///
+    /// A common way to use this method is in a `refMap` section when generating your keybindings
+    /// and using the [index_selectors][1] helper method to make the required [selectors][2].
+    ///
+    /// [1]: crate::core::helpers::index_selectors
+    /// [2]: crate::core::ring::Selector
+    pub fn focus_workspace(&mut self, selector: &Selector<'_, Workspace>) -> Result<()> {
+        let ix = self.screens.focused().wix;
+        if self.workspaces.would_focus(ix, selector) {
+            return Ok(());
         }
 
         if let Some(index) = self.workspaces.index(selector) {
             let active = self.screens.active_ws_index();
-            self.previous_workspace = active;
+            self.previous_workspaces
+                .insert(self.screens.focused_index(), active);
+
+            if self.config.focus_follows_mouse {
+                self.focus_suppressed_at = self.conn.cursor_position().ok();
+            }
+
+            if self.config.restore_minimized {
+                for id in self.workspaces.client_ids(index)? {
+                    self.set_minimized(id, false)?;
+                }
+            }
 
             for i in 0..self.screens.n_screens() {
                 if self.screens.inner[i].wix == index {
@@ -890,6 +2453,8 @@ impl<X: XConn> WindowManager<X> {
                     // workspace we had on the screen where the target was
                     self.screens.inner[i].wix = self.screens.focused().wix;
                     self.screens.focused_mut().wix = index;
+                    self.previous_workspaces.insert(i, index);
+                    self.move_sticky_clients(active, index)?;
 
                     // re-apply layouts as screen dimensions may differ
                     self.apply_layout(active)?;
@@ -898,48 +2463,68 @@ impl<X: XConn> WindowManager<X> {
                     // update xproperty _NET_CURRENT_DESKTOP
                     self.conn.set_current_workspace(index)?;
 
-                    let ws = self.workspaces.get_workspace(index)?;
-                    if let Some(id) = ws.focused_client() {
-                        self.update_focus(id)?;
+                    match self.focusable_client(index) {
+                        Some(id) => self.update_focus(id)?,
+                        None => self.focus_root()?,
                     };
 
                     self.workspaces.focus(&Selector::Index(index));
                     self.run_hook(HookName::WorkspaceChange(active, index));
+                    let focused = self.screens.focused_index();
+                    self.run_hook(HookName::ScreenWorkspaceChange(active, index, focused));
+                    self.run_hook(HookName::ScreenWorkspaceChange(index, active, i));
                     return Ok(());
                 }
             }
 
             // target not currently displayed so unmap what we currently have
             // displayed and replace it with the target workspace
+            self.move_sticky_clients(active, index)?;
             let ws = self.workspaces.get_workspace(active)?;
             for id in ws.client_ids().iter() {
                 self.clients.unmap_if_needed(*id, &self.conn)?;
             }
 
             let ws = self.workspaces.get_workspace(index)?;
-            for id in ws.client_ids().iter() {
-                self.clients.map_if_needed(*id, &self.conn)?;
+            for id in ws.client_ids() {
+                let minimized = self.clients.get(id).map(|c| c.minimized).unwrap_or(false);
+                if !minimized {
+                    self.clients.map_if_needed(id, &self.conn)?;
+                }
             }
 
             self.screens.focused_mut().wix = index;
+            self.apply_screen_layout(index, self.screens.focused_index());
             self.apply_layout(index)?;
             self.conn.set_current_workspace(index)?;
 
-            let ws = self.workspaces.get_workspace(index)?;
-            if let Some(id) = ws.focused_client() {
-                self.update_focus(id)?;
+            match self.focusable_client(index) {
+                Some(id) => self.update_focus(id)?,
+                None => self.focus_root()?,
             };
 
             self.workspaces.focus(&Selector::Index(index));
             self.run_hook(HookName::WorkspaceChange(active, index));
+            let focused = self.screens.focused_index();
+            self.run_hook(HookName::ScreenWorkspaceChange(active, index, focused));
         }
 
         Ok(())
     }
 
-    /// Switch focus back to the last workspace that had focus.
+    /// Switch the active screen back to the workspace it was showing before its current one.
+    ///
+    /// Each screen tracks its own previous workspace. If the active screen has not yet changed
+    /// workspace then this is a no-op.
     pub fn toggle_workspace(&mut self) -> Result<()> {
-        self.focus_workspace(&Selector::Index(self.previous_workspace))
+        match self
+            .previous_workspaces
+            .get(&self.screens.focused_index())
+            .copied()
+        {
+            Some(wix) => self.focus_workspace(&Selector::Index(wix)),
+            None => Ok(()),
+        }
     }
 
     /// Move the focused client to the workspace matching 'selector'.
@@ -947,7 +2532,7 @@ impl<X: XConn> WindowManager<X> {
         if let Some(id) = self.clients.focused_client_id() {
             if let Some(wix) = self.workspaces.index(selector) {
                 self.move_client_to_workspace(id, wix)?;
-                if let Some(now_focused) = self.active_workspace().focused_client() {
+                if let Some(now_focused) = self.focusable_client(self.screens.active_ws_index()) {
                     self.clients.set_focused(now_focused, &self.conn);
                 }
             }
@@ -956,7 +2541,74 @@ impl<X: XConn> WindowManager<X> {
         Ok(())
     }
 
+    /// Move the focused client to the workspace matching 'selector' and then follow it there.
+    ///
+    /// The target workspace is shown on the active screen (pulling it from another screen if it
+    /// is currently visible there) and the moved client keeps focus. If there is no focused client
+    /// or no matching workspace then this is a no-op.
+    pub fn client_to_workspace_and_follow(
+        &mut self,
+        selector: &Selector<'_, Workspace>,
+    ) -> Result<()> {
+        let (id, wix) = match (
+            self.clients.focused_client_id(),
+            self.workspaces.index(selector),
+        ) {
+            (Some(id), Some(wix)) => (id, wix),
+            _ => return Ok(()),
+        };
+
+        self.move_client_to_workspace(id, wix)?;
+        self.focus_workspace(&Selector::Index(wix))?;
+        self.focus_client(&Selector::WinId(id)).map(|_| ())
+    }
+
+    /// Move all clients on the workspace at index 'from' to the workspace at index 'into',
+    /// leaving 'from' empty.
+    ///
+    /// The moved clients are placed after any clients already on 'into', keeping their relative
+    /// order. Sticky clients are not moved as they follow the screen they are on rather than
+    /// any particular workspace. Returns an error if either index is out of bounds.
+    pub fn merge_workspaces(&mut self, from: usize, into: usize) -> Result<()> {
+        self.workspaces.get_workspace(from)?;
+        self.workspaces.get_workspace(into)?;
+        if from == into {
+            return Ok(());
+        }
+
+        let ids: Vec<Xid> = self
+            .workspaces
+            .client_ids(from)?
+            .into_iter()
+            .filter(|&id| !matches!(self.clients.get(id), Some(c) if c.sticky))
+            .collect();
+        let actions = self.workspaces.merge(from, into, &ids)?;
+        self.apply_auto_layout(from);
+        self.apply_auto_layout(into);
+        let into_visible = self.screens.visible_workspaces().contains(&into);
+
+        for id in ids {
+            self.geometry_cache.borrow_mut().remove(&id);
+            self.clients.set_client_workspace(id, into);
+            self.conn.set_client_workspace(id, into)?;
+            if !into_visible {
+                self.clients.unmap_if_needed(id, &self.conn)?;
+            }
+        }
+
+        self.handle_event_actions(actions)?;
+        self.layout_visible()?;
+        if let Some(now_focused) = self.focusable_client(self.screens.active_ws_index()) {
+            self.clients.set_focused(now_focused, &self.conn);
+        }
+
+        Ok(())
+    }
+
     /// Move the focused client to the active workspace on the screen matching 'selector'.
+    ///
+    /// This is a no-op if no screen matches 'selector' or if the client is already on the
+    /// workspace shown by the selected screen.
     pub fn client_to_screen(&mut self, selector: &Selector<'_, Screen>) -> Result<()> {
         let i = match self.screen(selector) {
             Some(s) => s.wix,
@@ -974,6 +2626,232 @@ impl<X: XConn> WindowManager<X> {
         self.set_fullscreen(id, !client_is_fullscreen)
     }
 
+    /// Toggle the floating state of the [Client] matching the given [Selector]
+    ///
+    /// Clients that are made floating are returned to the position they last had when floating
+    /// or placed at their current position if they have not floated before. The workspace holding
+    /// the client is then laid out again.
+    pub fn toggle_client_floating(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        let (id, wix, floating, floating_region) = match self.client(selector) {
+            None => return Ok(()), // unknown client
+            Some(c) => (c.id(), c.workspace(), c.floating, c.floating_region),
+        };
+
+        self.geometry_cache.borrow_mut().remove(&id);
+        self.state_changed = true;
+        if floating {
+            let r = self.conn.client_geometry(id).ok();
+            self.clients.modify(id, |c| {
+                c.floating = false;
+                c.floating_region = r;
+            });
+        } else {
+            self.clients.modify(id, |c| c.floating = true);
+            if let Some(r) = floating_region {
+                self.conn
+                    .position_client(id, r, self.client_border_px(id), true)?;
+            } else if let Some((_, s)) = self.screens.indexed_screen_for_workspace(wix) {
+                util::position_floating_client(
+                    &self.conn,
+                    id,
+                    s.region(true),
+                    self.client_border_px(id),
+                )?;
+            }
+        }
+
+        self.apply_layout(wix)
+    }
+
+    /// Move the focused [Client] to fill a half or quarter of the screen it is on.
+    ///
+    /// Gaps and borders are applied to the target region in the same way as they are for tiled
+    /// clients. This is a no-op if the focused client is not floating or is fullscreen.
+    pub fn snap_floating(&mut self, region_spec: SnapRegion) -> Result<()> {
+        let (id, wix) = match self.clients.focused_client() {
+            Some(c) if c.floating && !c.fullscreen => (c.id(), c.workspace()),
+            _ => return Ok(()),
+        };
+
+        let r = match self.screens.indexed_screen_for_workspace(wix) {
+            Some((_, s)) => s.region(true),
+            None => return Ok(()),
+        };
+
+        let bpx = self.client_border_px(id);
+        let reg = pad_region(
+            &region_spec.region_within(&r),
+            &r,
+            false,
+            self.gap_px,
+            self.outer_gap_px,
+            bpx,
+        );
+
+        Ok(self.conn.position_client(id, reg, bpx, true)?)
+    }
+
+    /// Move the focused [Client] by the given number of pixels if it is floating.
+    ///
+    /// The client is kept within the bounds of the screen it is on.
+    pub fn move_floating(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.adjust_floating((dx, dy, 0, 0))
+    }
+
+    /// Grow or shrink the focused [Client] by the given number of pixels if it is floating.
+    ///
+    /// The top left corner of the client stays in place unless the new size would take it off
+    /// of the screen, and the client is never made smaller than a minimum size.
+    pub fn resize_floating(&mut self, dw: i32, dh: i32) -> Result<()> {
+        self.adjust_floating((0, 0, dw, dh))
+    }
+
+    fn adjust_floating(&mut self, delta: (i32, i32, i32, i32)) -> Result<()> {
+        let (id, wix) = match self.clients.focused_client() {
+            Some(c) if c.floating && !c.fullscreen => (c.id(), c.workspace()),
+            _ => return Ok(()),
+        };
+
+        let r = match self.screens.indexed_screen_for_workspace(wix) {
+            Some((_, s)) => s.region(true),
+            None => return Ok(()),
+        };
+
+        let bpx = self.client_border_px(id);
+        let current = self.conn.client_geometry(id)?;
+        let reg = adjust_floating_region(current, delta, MIN_FLOATING_PX, r, bpx);
+
+        Ok(self.conn.position_client(id, reg, bpx, true)?)
+    }
+
+    /// Toggle whether or not the [Client] matching the given [Selector] is sticky.
+    ///
+    /// Sticky clients float above tiled clients and remain visible when the screen they are on
+    /// changes workspace. They can still be closed and destroyed as normal.
+    pub fn toggle_client_sticky(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        let (id, wix, sticky) = match self.client(selector) {
+            None => return Ok(()), // unknown client
+            Some(c) => (c.id(), c.workspace(), c.sticky),
+        };
+
+        if !sticky && !self.clients.get(id).map(|c| c.floating).unwrap_or(false) {
+            self.toggle_client_floating(&Selector::WinId(id))?;
+        }
+        self.clients.modify(id, |c| c.sticky = !sticky);
+        self.state_changed = true;
+
+        self.apply_layout(wix)
+    }
+
+    /// Raise the [Client] matching the given [Selector] to the top of its stacking layer.
+    ///
+    /// Floating clients are always kept above tiled clients, and sticky clients and scratchpads
+    /// are always kept above other floating clients.
+    pub fn raise_client(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        self.restack_selected_client(selector, StackHint::Top)
+    }
+
+    /// Lower the [Client] matching the given [Selector] to the bottom of its stacking layer.
+    ///
+    /// Lowering a floating client places it below the other floating clients on its [Workspace]
+    /// but it will still be above any tiled clients.
+    pub fn lower_client(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        self.restack_selected_client(selector, StackHint::Bottom)
+    }
+
+    fn restack_selected_client(
+        &mut self,
+        selector: &Selector<'_, Client>,
+        hint: StackHint,
+    ) -> Result<()> {
+        let (id, wix) = match self.client(selector) {
+            None => return Ok(()), // unknown client
+            Some(c) => (c.id(), c.workspace()),
+        };
+
+        self.restack_client(id, hint)?;
+        self.restack_floating(wix)
+    }
+
+    /// Show or hide the [Scratchpad][1] with the given name.
+    ///
+    /// Showing a scratchpad moves its client to the active workspace, floating in the center of
+    /// the screen, and focuses it. Hiding it unmaps the client without closing it. If the
+    /// scratchpad does not have a client yet then its spawn command is run (if there is one) and
+    /// the resulting client is shown once it is mapped.
+    ///
+    /// [1]: crate::core::config::Scratchpad
+    pub fn toggle_scratchpad(&mut self, name: &str) -> Result<()> {
+        let scratchpad = match self.config.scratchpads.iter().find(|s| s.name == name) {
+            Some(s) => s.clone(),
+            None => {
+                warn!(name, "unknown scratchpad");
+                return Ok(());
+            }
+        };
+
+        let id = match self.scratchpads.get(name) {
+            Some(&id) => id,
+            None => {
+                let claimed: Vec<Xid> = self.scratchpads.values().copied().collect();
+                let existing = self
+                    .clients
+                    .matching_clients(&Selector::Condition(&|c| {
+                        !claimed.contains(&c.id()) && scratchpad.matches.matches(c)
+                    }))
+                    .first()
+                    .map(|c| c.id());
+
+                match (existing, scratchpad.spawn) {
+                    (Some(id), _) => {
+                        self.scratchpads.insert(scratchpad.name, id);
+                        id
+                    }
+                    (None, Some(cmd)) => return spawn(cmd),
+                    (None, None) => return Ok(()),
+                }
+            }
+        };
+
+        let wix = self.screens.active_ws_index();
+        let (current, minimized) = match self.clients.get(id) {
+            Some(c) => (c.workspace(), c.minimized),
+            None => return Ok(()),
+        };
+
+        if current == wix && !minimized && self.workspaces.client_ids(wix)?.contains(&id) {
+            self.hide_scratchpad(id, wix)
+        } else {
+            self.show_scratchpad(id, current, wix)
+        }
+    }
+
+    /// Minimize the [Client] matching the given [Selector].
+    ///
+    /// Minimized clients remain on their workspace but are not shown or laid out until they are
+    /// restored. If [restore_minimized][1] is set then switching to the workspace holding a
+    /// minimized client will restore it.
+    ///
+    /// [1]: crate::core::config::Config::restore_minimized
+    pub fn minimize_client(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        let (id, wix) = match self.client(selector) {
+            None => return Ok(()), // unknown client
+            Some(c) => (c.id(), c.workspace()),
+        };
+        self.set_minimized(id, true)?;
+        self.apply_layout(wix)
+    }
+
+    /// Restore the minimized [Client] matching the given [Selector].
+    pub fn restore_client(&mut self, selector: &Selector<'_, Client>) -> Result<()> {
+        let (id, wix) = match self.client(selector) {
+            None => return Ok(()), // unknown client
+            Some(c) => (c.id(), c.workspace()),
+        };
+        self.set_minimized(id, false)?;
+        self.apply_layout(wix)
+    }
+
     /// Kill the focused client window.
     #[tracing::instrument(level = "debug", err, skip(self))]
     pub fn kill_client(&mut self) -> Result<()> {
@@ -986,6 +2864,75 @@ impl<X: XConn> WindowManager<X> {
         Ok(())
     }
 
+    /// Close the focused client window, forcibly killing it if it does not close by itself.
+    ///
+    /// Clients that support the WM_DELETE_WINDOW protocol are asked to close, giving them the
+    /// chance to save any unsaved work. If the client is still open [close_timeout_ms][1] after
+    /// being asked then it is killed, as are clients that do not support the protocol.
+    ///
+    /// [1]: crate::core::config::Config::close_timeout_ms
+    #[tracing::instrument(level = "debug", err, skip(self))]
+    pub fn close_focused(&mut self) -> Result<()> {
+        let id = match self.clients.focused_client_id() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        self.close_client(id)?;
+        self.conn.flush();
+        Ok(())
+    }
+
+    // Politely ask a client to close if it supports WM_DELETE_WINDOW, otherwise (or if it has
+    // already been asked and failed to close in time) kill it.
+    fn close_client(&mut self, id: Xid) -> Result<()> {
+        let timeout = Duration::from_millis(self.config.close_timeout_ms);
+        let timed_out = matches!(self.pending_close.get(&id), Some(t) if t.elapsed() >= timeout);
+        let delete = Atom::WmDeleteWindow.as_ref();
+
+        if !timed_out && self.conn.client_supports_protocol(id, delete)? {
+            self.pending_close.entry(id).or_insert_with(Instant::now);
+            let msg = ClientMessageKind::DeleteWindow(id).as_message(&self.conn)?;
+            self.conn.send_client_event(msg)?;
+        } else {
+            self.pending_close.remove(&id);
+            self.conn.kill_client(id)?;
+        }
+
+        Ok(())
+    }
+
+    // The time remaining until the first client in pending_close is due to be killed
+    fn next_close_deadline(&self) -> Option<Duration> {
+        let timeout = Duration::from_millis(self.config.close_timeout_ms);
+        self.pending_close
+            .values()
+            .map(|t| timeout.saturating_sub(t.elapsed()))
+            .min()
+    }
+
+    // Kill any clients that were asked to close by close_focused but are still open after
+    // close_timeout_ms. Clients are removed from pending_close once they are destroyed.
+    fn kill_unresponsive_clients(&mut self) -> Result<()> {
+        let timeout = Duration::from_millis(self.config.close_timeout_ms);
+        let expired: Vec<Xid> = self
+            .pending_close
+            .iter()
+            .filter(|(_, t)| t.elapsed() >= timeout)
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in expired {
+            self.pending_close.remove(&id);
+            if self.clients.is_known(id) {
+                warn!(id, "client did not close in time: killing it");
+                self.conn.kill_client(id)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get a reference to the first Screen satisfying 'selector'. Xid selectors will return
     /// the screen containing that Client if the client is known.
     /// NOTE: It is not possible to get a mutable reference to a Screen.
@@ -1029,8 +2976,22 @@ impl<X: XConn> WindowManager<X> {
         self.update_x_workspace_details()
     }
 
+    /// Create a new workspace called `name` using the layouts from the current [Config] and add it
+    /// to the end of the current workspace list, returning its index.
+    pub fn create_workspace(&mut self, name: impl Into<String>) -> Result<usize> {
+        let ws = Workspace::new(name, self.config.bounded_layouts());
+        self.push_workspace(ws)?;
+
+        Ok(self.workspaces.len() - 1)
+    }
+
     /// Remove a Workspace from the WindowManager. All clients that were present on the removed
-    /// workspace will be destroyed. Xid selectors will be ignored.
+    /// workspace are moved to the workspace before it (or the one after it when removing the
+    /// first workspace). Xid selectors will be ignored.
+    ///
+    /// Any screen that was showing the removed workspace will be given a workspace that is not
+    /// currently visible on another screen, preferring the closest workspace before the one that
+    /// was removed.
     pub fn remove_workspace(
         &mut self,
         selector: &Selector<'_, Workspace>,
@@ -1039,18 +3000,107 @@ impl<X: XConn> WindowManager<X> {
             return Err(perror!("must have at least one workspace per screen"));
         }
 
-        let ws = self.workspaces.remove_workspace(selector)?;
-        ws.iter().try_for_each(|c| self.remove_client(*c))?;
+        let ix = match self.workspaces.index(selector) {
+            Some(ix) => ix,
+            None => return Err(perror!("unknown workspace")),
+        };
+
+        let target = if ix > 0 { ix - 1 } else { 1 };
+        let target_visible = self.screens.visible_workspaces().contains(&target);
+        for id in self.workspaces.client_ids(ix)? {
+            self.workspaces.remove_client(ix, id);
+            self.add_client_to_workspace(target, id)?;
+            if !target_visible {
+                self.clients.unmap_if_needed(id, &self.conn)?;
+            }
+        }
+        let ws = self.workspaces.remove_workspace(&Selector::Index(ix))?;
+
+        for id in self.clients.all_known_ids() {
+            if let Some(wix) = self
+                .clients
+                .get(id)
+                .map(|c| c.workspace)
+                .filter(|&w| w > ix)
+            {
+                self.clients.modify(id, |c| c.workspace = wix - 1);
+                self.conn.set_client_workspace(id, wix - 1)?;
+            }
+        }
 
-        // Focus the workspace before the one we just removed. There is always at least one
-        // workspace before this one due to the guard above.
-        let ix = self.screens.focused().wix.saturating_sub(1);
-        self.focus_workspace(&Selector::Index(ix))?;
+        self.reassign_screen_workspaces(ix)?;
+        if target_visible {
+            self.layout_visible()?; // relocated clients may now be on screen
+        }
         self.update_x_workspace_details()?;
 
         Ok(Some(ws))
     }
 
+    // Shift the workspace indices held by each screen to account for the workspace at 'removed'
+    // no longer existing, and give any screen that was showing it a workspace that is not
+    // visible elsewhere so that every screen is always showing a valid workspace.
+    fn reassign_screen_workspaces(&mut self, removed: usize) -> Result<()> {
+        let mut orphaned = vec![];
+        let mut taken = vec![];
+        for (i, s) in self.screens.inner.iter_mut().enumerate() {
+            if s.wix == removed {
+                orphaned.push(i);
+                continue;
+            } else if s.wix > removed {
+                s.wix -= 1;
+            }
+            taken.push(s.wix);
+        }
+
+        self.previous_workspaces.retain(|_, wix| *wix != removed);
+        for wix in self.previous_workspaces.values_mut() {
+            if *wix > removed {
+                *wix -= 1;
+            }
+        }
+        self.shown_workspaces = self
+            .shown_workspaces
+            .iter()
+            .filter(|&&wix| wix != removed)
+            .map(|&wix| if wix > removed { wix - 1 } else { wix })
+            .collect();
+        self.screens.remove_detached_workspace(removed);
+
+        for i in orphaned {
+            let wix = match (0..removed)
+                .rev()
+                .chain(removed..self.workspaces.len())
+                .find(|ix| !taken.contains(ix))
+            {
+                Some(wix) => wix,
+                None => return Err(perror!("no workspace available for screen {}", i)),
+            };
+            taken.push(wix);
+            self.screens.inner[i].wix = wix;
+
+            for id in self.workspaces.client_ids(wix)? {
+                let minimized = self.clients.get(id).map(|c| c.minimized).unwrap_or(false);
+                if !minimized {
+                    self.clients.map_if_needed(id, &self.conn)?;
+                }
+            }
+            self.apply_layout(wix)?;
+
+            if i == self.screens.focused_index() {
+                self.conn.set_current_workspace(wix)?;
+                if let Some(id) = self.focusable_client(wix) {
+                    self.update_focus(id)?;
+                }
+            }
+        }
+
+        self.workspaces
+            .focus(&Selector::Index(self.screens.active_ws_index()));
+
+        Ok(())
+    }
+
     /// Get a reference to the first Workspace satisfying 'selector'. Xid selectors will return
     /// the workspace containing that Client if the client is known.
     pub fn workspace(&self, selector: &Selector<'_, Workspace>) -> Option<&Workspace> {
@@ -1158,21 +3208,64 @@ impl<X: XConn> WindowManager<X> {
         self.screens.n_screens()
     }
 
+    /// The total number of clients on all workspaces.
+    pub fn clients_len(&self) -> usize {
+        self.workspaces.iter().map(|ws| ws.len()).sum()
+    }
+
+    /// The number of clients on the workspace at index `wix`: 0 if there is no such workspace.
+    pub fn workspace_client_count(&self, wix: usize) -> usize {
+        self.workspaces.get(wix).map(|ws| ws.len()).unwrap_or(0)
+    }
+
     /// The current effective screen size of the target screen. Effective screen size is the
     /// physical screen size minus any space reserved for a status bar.
     pub fn screen_size(&self, index: usize) -> Option<Region> {
-        self.screens.screen_size(index, self.config.show_bar)
+        self.screens.screen_size(index)
     }
 
     /// Position an individual client on the display. (x,y) coordinates are absolute (i.e. relative
     /// to the root window not any individual screen).
     pub fn position_client(&self, id: Xid, region: Region, stack_above: bool) -> Result<()> {
-        let bpx = self.config.border_px;
+        self.geometry_cache.borrow_mut().remove(&id);
+        let bpx = self.client_border_px(id);
         self.conn
             .position_client(id, region, bpx, stack_above)
             .map_err(|e| e.into())
     }
 
+    /// Set the _NET_WM_WINDOW_OPACITY of a client, where 0.0 is fully transparent and 1.0 is
+    /// fully opaque. Values outside of this range are clamped.
+    ///
+    /// This requires a running compositor to have any visible effect.
+    pub fn set_client_opacity(&mut self, id: Xid, opacity: f64) -> Result<()> {
+        let val = (opacity.clamp(0.0, 1.0) * u32::MAX as f64).round() as u32;
+        Ok(self
+            .conn
+            .change_prop(id, Atom::NetWmWindowOpacity.as_ref(), Prop::Cardinal(val))?)
+    }
+
+    /// Set whether or not a client is excluded from being dimmed when it loses focus.
+    ///
+    /// Exempt clients are made fully opaque. See
+    /// [unfocused_opacity][crate::core::config::Config::unfocused_opacity] for details.
+    pub fn set_client_dim_exempt(&mut self, id: Xid, exempt: bool) -> Result<()> {
+        if !self.clients.is_known(id) {
+            return Err(PenroseError::UnknownClient(id));
+        }
+        self.clients.modify(id, |c| c.dim_exempt = exempt);
+
+        match self.config.unfocused_opacity {
+            Some(opacity)
+                if self.clients.focused_client_id() != Some(id) && self.should_dim(id) =>
+            {
+                self.set_client_opacity(id, opacity)
+            }
+            Some(_) => self.set_client_opacity(id, 1.0),
+            None => Ok(()),
+        }
+    }
+
     /// Make the Client with ID 'id' visible at its last known position.
     pub fn show_client(&mut self, id: Xid) -> Result<()> {
         self.clients.map_if_needed(id, &self.conn)?;
@@ -1207,21 +3300,37 @@ mod tests {
     use super::*;
     use crate::{
         __test_helpers::{
-            n_clients, test_key_bindings, test_mouse_bindings, test_windowmanager, RecordedCall,
-            RecordingXConn,
+            n_clients, test_key_bindings, test_mouse_bindings, test_windowmanager, GeometryXConn,
+            RecordedCall, RecordingXConn, TestKeyHandler, TestMouseBindings, TestWM, TestXConn,
         },
         core::{
+            bindings::{ModifierKey, MouseEventHandler, MouseState},
             data_types::*,
             helpers::logging_error_handler,
             ring::Direction::*,
             screen::*,
-            xconnection::{MockXConn, Prop, XEvent},
+            xconnection::{MockXConn, PointerChange, Prop, XEvent, XState},
             Layout, LayoutConf,
         },
         draw::Color,
     };
 
-    use std::{cell::Cell, collections::HashMap, convert::TryFrom};
+    use crate::core::{
+        config::{
+            AutoLayoutRule, FocusAfterClose, Scratchpad, ScreenBar, ScreenLayout, WindowMatch,
+            WindowRule,
+        },
+        hooks::Hook,
+        layouts::mock_layout,
+        xconnection::{ClientEventMask, ClientMessage, WmNormalHints, WmNormalHintsFlags, XError},
+    };
+
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        convert::TryFrom,
+        rc::Rc,
+    };
 
     fn wm_with_mock_conn(events: Vec<XEvent>, unmanaged_ids: Vec<Xid>) -> WindowManager<MockXConn> {
         let conn = MockXConn::new(test_screens(), events, unmanaged_ids);
@@ -1262,6 +3371,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn state_reports_workspace_occupancy_and_focus() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0);
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+
+        let state = wm.state();
+        let flags: Vec<(bool, bool)> = state
+            .workspaces
+            .iter()
+            .take(3)
+            .map(|ws| (ws.occupied, ws.focused))
+            .collect();
+
+        assert_eq!(state.workspaces.len(), wm.workspaces.len());
+        assert_eq!(flags, vec![(true, false), (false, true), (false, false)]);
+        assert_eq!(state.layout, wm.current_layout_symbol());
+        assert_eq!(state.focused_title, None);
+    }
+
+    type FocusChanges = Rc<RefCell<Vec<(Option<Xid>, Option<Xid>)>>>;
+
+    struct FocusRecorder(FocusChanges);
+
+    impl<X: XConn> Hook<X> for FocusRecorder {
+        fn client_focus_changed(
+            &mut self,
+            _: &mut WindowManager<X>,
+            prev: Option<Xid>,
+            new: Option<Xid>,
+        ) -> Result<()> {
+            self.0.borrow_mut().push((prev, new));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn client_focus_changed_fires_once_per_focus_move() {
+        let changes = Rc::new(RefCell::new(vec![]));
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let hooks: Vec<Box<dyn Hook<MockXConn>>> = vec![Box::new(FocusRecorder(changes.clone()))];
+        let mut wm = WindowManager::new(Config::default(), conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+
+        add_n_clients(&mut wm, 2, 0); // 10 then 20
+        wm.cycle_client(Forward).unwrap(); // 20 -> 10
+        wm.focus_client(&Selector::WinId(10)).unwrap(); // already focused
+        wm.focus_workspace(&Selector::Index(1)).unwrap(); // empty workspace
+        wm.focus_workspace(&Selector::Index(0)).unwrap(); // back to 10
+
+        assert_eq!(
+            changes.take(),
+            vec![
+                (None, Some(10)),
+                (Some(10), Some(20)),
+                (Some(20), Some(10)),
+                (Some(10), None),
+                (None, Some(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn client_focus_changed_reports_removed_clients_as_the_previous_focus() {
+        let changes = Rc::new(RefCell::new(vec![]));
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let hooks: Vec<Box<dyn Hook<MockXConn>>> = vec![Box::new(FocusRecorder(changes.clone()))];
+        let mut wm = WindowManager::new(Config::default(), conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        changes.take();
+        wm.remove_client(20).unwrap();
+        wm.remove_client(10).unwrap();
+
+        assert_eq!(changes.take(), vec![(Some(20), Some(10)), (Some(10), None)]);
+    }
+
+    type WorkspaceChanges = Rc<RefCell<Vec<(usize, usize, usize)>>>;
+
+    struct WorkspaceRecorder(WorkspaceChanges);
+
+    impl<X: XConn> Hook<X> for WorkspaceRecorder {
+        fn screen_workspace_change(
+            &mut self,
+            _: &mut WindowManager<X>,
+            prev: usize,
+            new: usize,
+            screen_index: usize,
+        ) -> Result<()> {
+            self.0.borrow_mut().push((prev, new, screen_index));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn screen_workspace_change_reports_each_screen_that_changed() {
+        let changes = Rc::new(RefCell::new(vec![]));
+        let screens = vec![
+            Screen::new(Region::new(0, 0, 1000, 800), 0),
+            Screen::new(Region::new(1000, 0, 1000, 800), 0),
+        ];
+        let conn = MockXConn::new(screens, vec![], vec![]);
+        let hooks: Vec<Box<dyn Hook<MockXConn>>> =
+            vec![Box::new(WorkspaceRecorder(changes.clone()))];
+        let mut wm = WindowManager::new(Config::default(), conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+        assert!(
+            changes.take().is_empty(),
+            "startup should not fire the hook"
+        );
+
+        wm.focus_workspace(&Selector::Index(3)).unwrap(); // screen 0: 0 -> 3
+        wm.focus_workspace(&Selector::Index(3)).unwrap(); // already visible
+        wm.focus_workspace(&Selector::Index(1)).unwrap(); // swap with screen 1
+        wm.cycle_screen(Forward).unwrap();
+        wm.focus_workspace(&Selector::Index(5)).unwrap(); // screen 1: 3 -> 5
+
+        assert_eq!(
+            changes.take(),
+            vec![(0, 3, 0), (3, 1, 0), (1, 3, 1), (3, 5, 1)]
+        );
+    }
+
     #[test]
     fn workspace_switching_with_active_clients() {
         let mut wm = wm_with_mock_conn(vec![], vec![]);
@@ -1294,6 +3527,263 @@ mod tests {
         assert_eq!(wm.workspaces[0].len(), 1);
     }
 
+    // The last value written to the given property, formatted as in a RecordedCall
+    fn last_set_prop(calls: &[RecordedCall], atom: Atom) -> Option<String> {
+        calls
+            .iter()
+            .rev()
+            .find(|(m, args)| m == "change_prop" && args[1] == strings!(atom.as_ref())[0])
+            .map(|(_, args)| args[2].clone())
+    }
+
+    #[test]
+    fn ewmh_support_is_advertised_on_startup() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        let (root, check_win) = (wm.conn.root(), wm.conn.check_window());
+        let calls = wm.conn.calls();
+        let set_on = |id: Xid, atom: Atom| {
+            calls
+                .iter()
+                .rev()
+                .find(|(m, args)| m == "change_prop" && args[..2] == strings!(id, atom.as_ref()))
+                .map(|(_, args)| args[2].clone())
+        };
+
+        let check = format!("{:?}", Prop::Window(vec![check_win]));
+        let name = format!("{:?}", Prop::UTF8String(vec!["penrose".into()]));
+        for &id in &[root, check_win] {
+            assert_eq!(set_on(id, Atom::NetSupportingWmCheck), Some(check.clone()));
+            assert_eq!(set_on(id, Atom::NetWmName), Some(name.clone()));
+        }
+
+        let supported = crate::core::xconnection::EWMH_SUPPORTED_ATOMS
+            .iter()
+            .map(|a| a.as_ref().to_string())
+            .collect();
+        assert_eq!(
+            set_on(root, Atom::NetSupported),
+            Some(format!("{:?}", Prop::Atom(supported)))
+        );
+    }
+
+    #[test]
+    fn client_lists_are_published_to_the_root_window() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+        wm.remove_client(20).unwrap();
+        wm.toggle_client_fullscreen(&Selector::WinId(10)).unwrap();
+
+        // mapping order for the client list and bottom to top for the stacking order
+        let calls = wm.conn.calls();
+        let client_list = format!("{:?}", Prop::Window(vec![10, 30]));
+        let stacking = format!("{:?}", Prop::Window(vec![30, 10]));
+        assert_eq!(
+            last_set_prop(&calls, Atom::NetClientList),
+            Some(client_list)
+        );
+        assert_eq!(
+            last_set_prop(&calls, Atom::NetClientListStacking),
+            Some(stacking)
+        );
+    }
+
+    #[test]
+    fn desktop_properties_are_published_to_the_root_window() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        let names: Vec<String> = Config::default().workspaces;
+        let current = |wix: u32| Some(format!("{:?}", Prop::Cardinal(wix)));
+
+        let calls = wm.conn.calls();
+        let n_desktops = format!("{:?}", Prop::Cardinal(names.len() as u32));
+        let desktop_names = format!("{:?}", Prop::UTF8String(names.clone()));
+        assert_eq!(
+            last_set_prop(&calls, Atom::NetNumberOfDesktops),
+            Some(n_desktops)
+        );
+        assert_eq!(
+            last_set_prop(&calls, Atom::NetDesktopNames),
+            Some(desktop_names)
+        );
+        assert_eq!(last_set_prop(&calls, Atom::NetCurrentDesktop), current(0));
+
+        wm.focus_workspace(&Selector::Index(3)).unwrap();
+        assert_eq!(
+            last_set_prop(&wm.conn.calls(), Atom::NetCurrentDesktop),
+            current(3)
+        );
+
+        // the second screen is showing workspace 1
+        wm.cycle_screen(Direction::Forward).unwrap();
+        assert_eq!(
+            last_set_prop(&wm.conn.calls(), Atom::NetCurrentDesktop),
+            current(1)
+        );
+
+        wm.set_workspace_name("renamed", &Selector::Index(0))
+            .unwrap();
+        let mut renamed = names;
+        renamed[0] = "renamed".into();
+        let desktop_names = format!("{:?}", Prop::UTF8String(renamed));
+        assert_eq!(
+            last_set_prop(&wm.conn.calls(), Atom::NetDesktopNames),
+            Some(desktop_names)
+        );
+    }
+
+    fn active_window(conn: &RecordingXConn) -> Option<String> {
+        last_set_prop(&conn.calls(), Atom::NetActiveWindow)
+    }
+
+    #[test]
+    fn active_window_tracks_focus() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        let active = |id: Xid| Some(format!("{:?}", Prop::Window(vec![id])));
+
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        assert_eq!(active_window(&wm.conn), active(20));
+
+        wm.focus_client(&Selector::WinId(10)).unwrap();
+        assert_eq!(active_window(&wm.conn), active(10));
+
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        assert_eq!(active_window(&wm.conn), active(0));
+
+        add_n_clients(&mut wm, 1, 2); // [30]
+        assert_eq!(active_window(&wm.conn), active(30));
+
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(active_window(&wm.conn), active(10));
+
+        wm.remove_client(10).unwrap();
+        assert_eq!(active_window(&wm.conn), active(20));
+    }
+
+    #[test]
+    fn minimized_clients_stay_hidden_on_workspace_switch() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.minimize_client(&Selector::WinId(10)).unwrap();
+
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+
+        let c = wm.client(&Selector::WinId(10)).unwrap();
+        assert!(c.is_minimized());
+        assert!(!c.mapped);
+        assert!(wm.client(&Selector::WinId(20)).unwrap().mapped);
+        assert_eq!(wm.workspaces[0].client_ids(), vec![20, 10]);
+    }
+
+    #[test]
+    fn minimized_clients_can_be_restored_on_workspace_switch() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        wm.config.restore_minimized = true;
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.minimize_client(&Selector::WinId(10)).unwrap();
+        assert!(!wm.client(&Selector::WinId(10)).unwrap().mapped);
+
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+
+        let c = wm.client(&Selector::WinId(10)).unwrap();
+        assert!(!c.is_minimized());
+        assert!(c.mapped);
+    }
+
+    #[test]
+    fn restored_floating_clients_are_mapped() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.toggle_client_floating(&Selector::WinId(10)).unwrap();
+        wm.minimize_client(&Selector::WinId(10)).unwrap();
+        assert!(!wm.client(&Selector::WinId(10)).unwrap().mapped);
+
+        wm.restore_client(&Selector::WinId(10)).unwrap();
+
+        let c = wm.client(&Selector::WinId(10)).unwrap();
+        assert!(!c.is_minimized());
+        assert!(c.mapped);
+    }
+
+    #[test]
+    fn minimized_clients_keep_their_iconic_state_when_unmapped() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.minimize_client(&Selector::WinId(10)).unwrap();
+        wm.conn.clear();
+
+        // The X server notifies us of the unmap that minimizing the client triggered
+        wm.handle_unmap_notify(10).unwrap();
+        wm.handle_unmap_notify(20).unwrap();
+
+        let states: Vec<Vec<String>> = wm
+            .conn
+            .calls()
+            .into_iter()
+            .filter(|(m, _)| m == "set_client_state")
+            .map(|(_, args)| args)
+            .collect();
+        assert_eq!(states, vec![strings!(20, WindowState::Withdrawn)]);
+    }
+
+    #[test]
+    fn minimizing_the_focused_client_moves_focus_to_the_next_visible_client() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+        assert_eq!(wm.focused_client_id(), Some(30));
+
+        wm.minimize_client(&Selector::WinId(30)).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(20));
+
+        // minimized clients are skipped when cycling
+        wm.cycle_client(Forward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(10));
+        wm.cycle_client(Forward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(20));
+
+        wm.minimize_client(&Selector::WinId(20)).unwrap();
+        wm.minimize_client(&Selector::WinId(10)).unwrap();
+        assert_eq!(wm.focused_client_id(), None);
+        wm.cycle_client(Forward).unwrap();
+        assert_eq!(wm.focused_client_id(), None);
+    }
+
+    #[test]
+    fn focusing_a_minimized_client_restores_it() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.minimize_client(&Selector::WinId(20)).unwrap();
+
+        wm.focus_client(&Selector::WinId(20)).unwrap();
+
+        let c = wm.client(&Selector::WinId(20)).unwrap();
+        assert!(!c.is_minimized());
+        assert!(c.mapped);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
+
+    #[test]
+    fn minimized_clients_are_not_focused_on_workspace_switch() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        wm.minimize_client(&Selector::WinId(20)).unwrap();
+
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(10));
+    }
+
     #[test]
     fn client_to_workspace_inserts_at_head() {
         let mut wm = wm_with_mock_conn(vec![], vec![]);
@@ -1308,6 +3798,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn client_to_workspace_updates_net_wm_desktop() {
+        let conn = RecordingXConn::init();
+        let conf = Config {
+            layouts: focus_test_layouts(false),
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.conn.clear();
+
+        wm.client_to_workspace(&Selector::Index(3)).unwrap();
+
+        let expected: RecordedCall = (
+            "change_prop".into(),
+            strings!(20, Atom::NetWmDesktop.as_ref(), Prop::Cardinal(3)),
+        );
+        assert!(wm.conn.calls().contains(&expected));
+    }
+
     #[test]
     fn client_to_workspace_sets_focus() {
         let mut wm = wm_with_mock_conn(vec![], vec![]);
@@ -1348,6 +3859,89 @@ mod tests {
         assert_eq!(wm.clients.workspace_index_for_client(10), Some(0));
     }
 
+    #[test]
+    fn client_counts() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        assert_eq!(wm.clients_len(), 0);
+
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+        wm.client_to_workspace(&Selector::Index(2)).unwrap();
+
+        assert_eq!(wm.clients_len(), 3);
+        assert_eq!(wm.workspace_client_count(0), 2);
+        assert_eq!(wm.workspace_client_count(1), 0);
+        assert_eq!(wm.workspace_client_count(2), 1);
+        assert_eq!(wm.workspace_client_count(42), 0);
+    }
+
+    #[test]
+    fn sticky_clients_stay_mapped_across_workspace_switches() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.toggle_client_sticky(&Selector::WinId(10)).unwrap();
+
+        let c = wm.clients.get(10).unwrap();
+        assert!(c.is_sticky() && c.is_floating());
+
+        // target workspace not currently displayed
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        assert!(wm.clients.get(10).unwrap().mapped);
+        assert!(!wm.clients.get(20).unwrap().mapped);
+        assert_eq!(wm.workspaces[2].client_ids(), vec![10]);
+        assert_eq!(wm.clients.get(10).unwrap().workspace(), 2);
+
+        // target workspace displayed on the other screen
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert!(wm.clients.get(10).unwrap().mapped);
+        assert_eq!(wm.workspaces[1].client_ids(), vec![10]);
+        assert!(wm.workspaces[2].is_empty());
+
+        wm.toggle_client_sticky(&Selector::WinId(10)).unwrap();
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert!(!wm.clients.get(10).unwrap().mapped);
+        assert_eq!(wm.clients.get(10).unwrap().workspace(), 1);
+    }
+
+    #[test]
+    fn sticky_clients_can_be_destroyed() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.toggle_client_sticky(&Selector::WinId(10)).unwrap();
+        process_events(&mut wm, vec![XEvent::Destroy(10)]);
+
+        assert!(wm.clients.get(10).is_none());
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        assert!(wm.workspaces[2].is_empty());
+        assert_eq!(wm.clients_len(), 1);
+    }
+
+    #[test]
+    fn client_to_current_screen_is_noop() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+
+        wm.client_to_screen(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.workspaces[0].client_ids(), vec![20, 10]);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
+
+    #[test]
+    fn focused_client_follows_the_active_workspace() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.focus_client(&Selector::WinId(10)).unwrap();
+
+        assert_eq!(wm.focused_client_id(), Some(10));
+        assert_eq!(wm.focused_client().map(|c| c.id()), Some(10));
+
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        assert_eq!(wm.focused_client_id(), None);
+        assert!(wm.focused_client().is_none());
+
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(10));
+    }
+
     #[test]
     fn x_focus_events_set_workspace_focus() {
         let mut wm = wm_with_mock_conn(vec![], vec![]);
@@ -1465,52 +4059,349 @@ mod tests {
         assert!(wm.workspaces[0].len() == 1);
     }
 
-    struct ScreenChangingXConn {
-        num_screens: Cell<usize>,
-    }
+    #[test]
+    fn client_snapshot_reports_cached_client_state() {
+        let conn = GeometryXConn::default()
+            .with_class(10, "st")
+            .with_class(20, "firefox");
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        wm.handle_map_request(20).unwrap();
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+
+        let info = |id, workspace, focused, floating, class: &str| ClientInfo {
+            id,
+            workspace,
+            focused,
+            floating,
+            class: class.to_string(),
+            title: "unknown".to_string(),
+        };
 
-    impl ScreenChangingXConn {
-        fn set_num_screens(&mut self, n: usize) {
-            self.num_screens.set(n);
-        }
+        assert_eq!(
+            wm.client_snapshot(),
+            vec![
+                info(10, 0, false, false, "st"),
+                info(20, 2, true, true, "firefox"),
+            ]
+        );
     }
 
-    __impl_stub_xcon! {
-        for ScreenChangingXConn;
+    struct IgnoreClass(&'static str);
 
-        atom_queries: {}
-        client_properties: {}
-        client_handler: {}
-        client_config: {}
-        event_handler: {}
-        state: {
-            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
-                let num_screens = self.num_screens.get();
-                Ok((0..(num_screens))
-                    .map(|n| Screen::new(Region::new(800 * n as u32, 600 * n as u32, 800, 600), n))
-                    .collect())
-            }
+    impl<X: XConn> Hook<X> for IgnoreClass {
+        fn should_manage(&mut self, _: &mut WindowManager<X>, client: &Client) -> Result<bool> {
+            Ok(client.wm_class() != self.0)
         }
-        conn: {}
     }
 
-    impl WindowManager<ScreenChangingXConn> {
-        fn set_num_screens(&mut self, n: usize) {
-            self.conn_mut().set_num_screens(n);
-        }
+    #[test]
+    fn hooks_can_veto_managing_a_client() {
+        let conn = GeometryXConn::default()
+            .with_class(10, "overlay")
+            .with_class(20, "st");
+        let hooks: Vec<Box<dyn Hook<GeometryXConn>>> = vec![Box::new(IgnoreClass("overlay"))];
+        let mut wm = WindowManager::new(Config::default(), conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+
+        wm.handle_map_request(10).unwrap();
+        assert_eq!(wm.clients_len(), 0);
+        assert!(wm.active_workspace().is_empty());
+
+        // the vetoed window being mapped again should not get picked up
+        process_events(&mut wm, vec![XEvent::MapRequest(10, false)]);
+        assert_eq!(wm.clients_len(), 0);
+
+        wm.handle_map_request(20).unwrap();
+        assert_eq!(wm.clients_len(), 1);
+        assert_eq!(wm.active_workspace().client_ids(), vec![20]);
     }
 
-    // TODO: rewrite and move out to screens.rs
     #[test]
-    fn updating_screens_retains_focused_workspaces() {
-        let conn = ScreenChangingXConn {
-            num_screens: Cell::new(1),
+    fn window_rules_are_applied_to_new_clients() {
+        let conn = GeometryXConn::default()
+            .with_class(10, "Firefox")
+            .with_class(20, "Gimp")
+            .with_class(30, "st");
+        let conf = Config {
+            window_rules: vec![
+                WindowRule::new(WindowMatch::Class("Firefox".into())).on_workspace(1),
+                WindowRule::new(WindowMatch::ClassContains("Gim".into())).float(),
+            ],
+            ..Default::default()
         };
-        let conf = Config::default();
         let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
         wm.init().unwrap();
 
-        // detect_screens is called on init so should have one screen
+        for id in [10, 20, 30] {
+            wm.handle_map_request(id).unwrap();
+        }
+
+        assert_eq!(wm.clients.get(10).unwrap().workspace(), 1);
+        assert!(!wm.clients.get(10).unwrap().floating);
+        assert_eq!(wm.workspaces[1].client_ids(), vec![10]);
+        assert!(wm.clients.get(20).unwrap().floating);
+        assert_eq!(wm.clients.get(20).unwrap().workspace(), 0);
+        assert!(!wm.clients.get(30).unwrap().floating);
+        assert_eq!(wm.workspaces[0].client_ids(), vec![30, 20]);
+    }
+
+    test_cases! {
+        window_types_can_float;
+        args: (ty: Atom, configured: bool, expected: bool);
+
+        case: dialog => (Atom::NetWindowTypeDialog, true, true);
+        case: splash => (Atom::NetWindowTypeSplash, true, true);
+        case: normal => (Atom::NetWindowTypeNormal, true, false);
+        case: dialog_not_configured => (Atom::NetWindowTypeDialog, false, false);
+
+        body: {
+            let conn = GeometryXConn::default()
+                .with_prop(10, Atom::NetWmWindowType, Prop::Atom(vec![ty.as_ref().into()]));
+            let mut conf = Config::default();
+            if !configured {
+                conf.floating_window_types.clear();
+            }
+            let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.handle_map_request(10).unwrap();
+
+            assert_eq!(wm.clients.get(10).unwrap().floating, expected);
+        }
+    }
+
+    #[test]
+    fn transient_clients_float_over_their_parent() {
+        let conn =
+            GeometryXConn::default().with_prop(20, Atom::WmTransientFor, Prop::Window(vec![10]));
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+
+        assert!(!wm.clients.get(10).unwrap().floating);
+        assert!(wm.clients.get(20).unwrap().floating);
+
+        let positions = wm.conn.positions.borrow();
+        let center = positions[&10].center();
+        let expected = Region::new(center.x as u32 - 50, center.y as u32 - 50, 100, 100);
+        assert_eq!(positions.get(&20), Some(&expected));
+    }
+
+    #[test]
+    fn transient_clients_with_an_unknown_parent_are_centered_on_the_screen() {
+        let conn =
+            GeometryXConn::default().with_prop(20, Atom::WmTransientFor, Prop::Window(vec![99]));
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(20).unwrap();
+
+        assert!(wm.clients.get(20).unwrap().floating);
+        // the default config reserves 18px at the top of the screen for a bar
+        let expected = Region::new(350, 259, 100, 100);
+        assert_eq!(wm.conn.positions.borrow().get(&20), Some(&expected));
+    }
+
+    fn hinted_client_position(respect_size_hints: bool) -> Region {
+        let base = Region::new(0, 0, 4, 2);
+        let hints = WmNormalHints::new(WmNormalHintsFlags::empty(), Some(base), None, None, None)
+            .with_resize_increments(7, 13);
+        let conn =
+            GeometryXConn::default().with_prop(10, Atom::WmNormalHints, Prop::WmNormalHints(hints));
+        let conf = Config {
+            respect_size_hints,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+
+        let r = wm.conn.positions.borrow()[&10];
+        r
+    }
+
+    #[test]
+    fn tiled_clients_can_respect_size_hints() {
+        let strict = hinted_client_position(false);
+        let hinted = hinted_client_position(true);
+
+        assert_ne!(strict, hinted);
+        assert_eq!((hinted.w - 4) % 7, 0);
+        assert_eq!((hinted.h - 2) % 13, 0);
+        assert!(strict.w - hinted.w < 7 && strict.h - hinted.h < 13);
+
+        // the snapped region is centered within the region given by the layout
+        assert_eq!(hinted.x - strict.x, (strict.w - hinted.w) / 2);
+        assert_eq!(hinted.y - strict.y, (strict.h - hinted.h) / 2);
+    }
+
+    #[test]
+    fn tiled_clients_can_snap_to_resize_increments() {
+        let strict = hinted_client_position(false);
+        let hints = WmNormalHints::new(WmNormalHintsFlags::empty(), None, None, None, None)
+            .with_resize_increments(10, 10);
+        let conn =
+            GeometryXConn::default().with_prop(10, Atom::WmNormalHints, Prop::WmNormalHints(hints));
+        let conf = Config {
+            respect_resize_increments: true,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+
+        let snapped = wm.conn.positions.borrow()[&10];
+        let (dw, dh) = (strict.w % 10, strict.h % 10);
+
+        assert!(
+            dw > 0 && dh > 0,
+            "cell should not be divisible by 10: {:?}",
+            strict
+        );
+        assert_eq!(
+            snapped,
+            Region::new(
+                strict.x + dw / 2,
+                strict.y + dh / 2,
+                strict.w - dw,
+                strict.h - dh
+            )
+        );
+    }
+
+    fn wm_with_scratchpad() -> WindowManager<GeometryXConn> {
+        let conn = GeometryXConn::default()
+            .with_class(10, "st")
+            .with_class(20, "scratch");
+        let conf = Config {
+            scratchpads: vec![Scratchpad::new(
+                "term",
+                WindowMatch::Class("scratch".into()),
+            )],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+
+        wm
+    }
+
+    #[test]
+    fn scratchpads_claim_matching_clients() {
+        let wm = wm_with_scratchpad();
+
+        assert_eq!(wm.scratchpads.get("term"), Some(&20));
+        assert!(wm.clients.get(20).unwrap().floating);
+        assert!(!wm.clients.get(10).unwrap().floating);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
+
+    #[test]
+    fn toggle_scratchpad_hides_and_shows() {
+        let mut wm = wm_with_scratchpad();
+
+        wm.toggle_scratchpad("term").unwrap();
+        assert_eq!(wm.workspaces[0].client_ids(), vec![10]);
+        assert!(!wm.clients.get(20).unwrap().mapped);
+        assert_eq!(wm.focused_client_id(), Some(10));
+
+        wm.toggle_scratchpad("term").unwrap();
+        assert!(wm.workspaces[0].client_ids().contains(&20));
+        assert!(wm.clients.get(20).unwrap().mapped);
+        assert!(wm.clients.get(20).unwrap().floating);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
+
+    test_cases! {
+        toggle_scratchpad_summons_to_active_workspace;
+        args: (hide_first: bool);
+
+        case: while_shown => (false);
+        case: while_hidden => (true);
+
+        body: {
+            let mut wm = wm_with_scratchpad();
+            if hide_first {
+                wm.toggle_scratchpad("term").unwrap();
+            }
+
+            wm.focus_workspace(&Selector::Index(2)).unwrap();
+            wm.toggle_scratchpad("term").unwrap();
+
+            assert_eq!(wm.workspaces[0].client_ids(), vec![10]);
+            assert_eq!(wm.workspaces[2].client_ids(), vec![20]);
+            assert_eq!(wm.clients.get(20).unwrap().workspace(), 2);
+            assert!(wm.clients.get(20).unwrap().mapped);
+            assert_eq!(wm.focused_client_id(), Some(20));
+        }
+    }
+
+    #[test]
+    fn unknown_scratchpads_are_ignored() {
+        let mut wm = wm_with_scratchpad();
+        wm.toggle_scratchpad("missing").unwrap();
+
+        assert_eq!(wm.workspaces[0].client_ids(), vec![20, 10]);
+    }
+
+    #[test]
+    fn removed_scratchpad_clients_are_released() {
+        let mut wm = wm_with_scratchpad();
+        wm.remove_client(20).unwrap();
+
+        assert!(wm.scratchpads.is_empty());
+    }
+
+    struct ScreenChangingXConn {
+        num_screens: Cell<usize>,
+    }
+
+    impl ScreenChangingXConn {
+        fn set_num_screens(&mut self, n: usize) {
+            self.num_screens.set(n);
+        }
+    }
+
+    __impl_stub_xcon! {
+        for ScreenChangingXConn;
+
+        atom_queries: {}
+        client_properties: {}
+        client_handler: {}
+        client_config: {}
+        event_handler: {}
+        state: {
+            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
+                let num_screens = self.num_screens.get();
+                Ok((0..(num_screens))
+                    .map(|n| Screen::new(Region::new(800 * n as u32, 600 * n as u32, 800, 600), n))
+                    .collect())
+            }
+        }
+        conn: {}
+    }
+
+    impl WindowManager<ScreenChangingXConn> {
+        fn set_num_screens(&mut self, n: usize) {
+            self.conn_mut().set_num_screens(n);
+        }
+    }
+
+    // TODO: rewrite and move out to screens.rs
+    #[test]
+    fn updating_screens_retains_focused_workspaces() {
+        let conn = ScreenChangingXConn {
+            num_screens: Cell::new(1),
+        };
+        let conf = Config::default();
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        // detect_screens is called on init so should have one screen
         assert_eq!(wm.screens.n_screens(), 1);
         assert_eq!(wm.screens.focused().wix, 0);
 
@@ -1539,6 +4430,102 @@ mod tests {
         assert_eq!(wm.screens.get(0).unwrap().wix, 3);
     }
 
+    #[test]
+    fn workspaces_can_be_created_renamed_and_removed_at_runtime() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        let n_ws = wm.workspaces.len();
+        add_n_clients(&mut wm, 2, 0);
+
+        let ix = wm.create_workspace("scratch").unwrap();
+        assert_eq!(ix, n_ws);
+        assert_eq!(wm.workspaces[ix].name(), "scratch");
+
+        wm.client_to_workspace(&Selector::Index(ix)).unwrap();
+        assert_eq!(wm.workspaces[ix].client_ids(), vec![20]);
+
+        wm.set_workspace_name("renamed", &Selector::Index(ix))
+            .unwrap();
+        assert_eq!(wm.workspaces[ix].name(), "renamed");
+
+        // clients on a removed workspace move to the one before it
+        wm.remove_workspace(&Selector::Index(ix)).unwrap();
+        assert_eq!(wm.workspaces.len(), n_ws);
+        assert_eq!(wm.client(&Selector::WinId(20)).unwrap().workspace(), ix - 1);
+        assert_eq!(wm.workspaces[ix - 1].client_ids(), vec![20]);
+
+        // ...or the one after it when removing the first workspace
+        wm.remove_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.client(&Selector::WinId(10)).unwrap().workspace(), 0);
+        assert_eq!(wm.workspaces[0].name(), "2");
+        assert_eq!(wm.workspaces[0].client_ids(), vec![10]);
+    }
+
+    #[test]
+    fn the_last_workspace_for_a_screen_can_not_be_removed() {
+        let mut wm = test_windowmanager(1, vec![]);
+        while wm.workspaces.len() > 1 {
+            wm.remove_workspace(&Selector::Index(0)).unwrap();
+        }
+
+        assert!(wm.remove_workspace(&Selector::Index(0)).is_err());
+        assert_eq!(wm.workspaces.len(), 1);
+    }
+
+    #[test]
+    fn removing_a_visible_workspace_reassigns_its_screen() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 1, 0);
+        wm.client_to_workspace(&Selector::Index(3)).unwrap();
+        assert_eq!(wm.screens.get(1).unwrap().wix, 1);
+
+        wm.remove_workspace(&Selector::Index(1)).unwrap();
+
+        let n_ws = wm.workspaces.len();
+        let visible = wm.screens.visible_workspaces();
+        assert!(visible.iter().all(|&wix| wix < n_ws));
+        assert_eq!(visible, vec![0, 1]);
+        assert_eq!(wm.workspaces[1].name(), "3");
+        assert_eq!(wm.client(&Selector::WinId(10)).unwrap().workspace(), 2);
+        assert_eq!(wm.workspaces[2].client_ids(), vec![10]);
+    }
+
+    #[test]
+    fn removing_a_workspace_shifts_the_workspaces_marked_as_shown() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        wm.focus_workspace(&Selector::Index(5)).unwrap();
+        assert!(wm.shown_workspaces.contains(&5));
+        assert!(!wm.shown_workspaces.contains(&4));
+
+        wm.remove_workspace(&Selector::Index(2)).unwrap();
+
+        assert!(wm.shown_workspaces.contains(&4));
+        assert!(!wm.shown_workspaces.contains(&5));
+    }
+
+    test_cases! {
+        client_changes_flag_state_changed;
+        args: (change: fn(&mut WindowManager<MockXConn>) -> Result<()>);
+
+        case: toggle_floating => (|wm| wm.toggle_client_floating(&Selector::Focused));
+        case: toggle_sticky => (|wm| wm.toggle_client_sticky(&Selector::Focused));
+        case: toggle_fullscreen => (|wm| wm.toggle_client_fullscreen(&Selector::Focused));
+        case: minimize => (|wm| wm.minimize_client(&Selector::Focused));
+        case: rotate => (|wm| wm.rotate_clients(Forward));
+        case: drag => (|wm| wm.drag_client(Forward));
+        case: toggle_master => (|wm| wm.toggle_master());
+        case: main_ratio => (|wm| wm.update_main_ratio(Change::More));
+
+        body: {
+            let mut wm = wm_with_mock_conn(vec![], vec![]);
+            add_n_clients(&mut wm, 3, 0);
+            wm.state_changed = false;
+
+            change(&mut wm).unwrap();
+
+            assert!(wm.state_changed);
+        }
+    }
+
     // Check that workspace layout is triggered correctly from public methods
 
     macro_rules! layout_trigger_test {
@@ -1560,14 +4547,16 @@ mod tests {
                     wm.conn.clear();
                     wm.$method($($arg),*).unwrap();
 
-                    // Defining "we applied layout" as "position_client" was called
-                    // at least once. Tests around layout application itself being
-                    // correct are handled separately
-                    if $should_layout {
-                        assert!(wm.conn.was_called("position_client"));
-                    } else {
-                        assert!(wm.conn.was_not_called("position_client"));
-                    }
+                    // Defining "we applied layout" as "grab_server" or "position_client" being
+                    // called at least once (clients that have not moved are not repositioned
+                    // by a layout). Tests around layout application itself being correct are
+                    // handled separately
+                    let laid_out = wm
+                        .conn
+                        .calls()
+                        .iter()
+                        .any(|(m, _)| m == "grab_server" || m == "position_client");
+                    assert_eq!(laid_out, $should_layout);
                 }
             }
         }
@@ -1575,6 +4564,7 @@ mod tests {
 
     layout_trigger_test!(cycle_workspace; true; Forward);
     layout_trigger_test!(drag_workspace; true; Forward);
+    layout_trigger_test!(swap_screens; true;);
     layout_trigger_test!(cycle_client; false; Forward);
     layout_trigger_test!(focus_client; false; &Selector::Any);
     layout_trigger_test!(rotate_clients; true; Forward);
@@ -1595,6 +4585,84 @@ mod tests {
     layout_trigger_test!(position_client; true; 10, Region::default(), true);
     layout_trigger_test!(layout_screen; true; 0);
 
+    #[test]
+    fn unchanged_clients_are_not_repositioned() {
+        let mut wm = WindowManager::new(
+            Config::default(),
+            RecordingXConn::init(),
+            vec![],
+            logging_error_handler(),
+        );
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 3, 0);
+        wm.conn.clear();
+
+        wm.apply_layout(0).unwrap();
+        assert!(wm.conn.was_not_called("position_client"));
+
+        wm.update_main_ratio(Change::More).unwrap();
+        assert!(wm.conn.was_called("position_client"));
+
+        // toggling a client's floating state drops its cached position so it is retiled
+        wm.toggle_client_floating(&Selector::WinId(10)).unwrap();
+        wm.conn.clear();
+        wm.toggle_client_floating(&Selector::WinId(10)).unwrap();
+        let repositioned: Vec<String> = wm
+            .conn
+            .calls()
+            .into_iter()
+            .filter(|(m, _)| m == "position_client")
+            .map(|(_, args)| args[0].clone())
+            .collect();
+        assert!(repositioned.contains(&"10".to_string()));
+    }
+
+    thread_local! {
+        static LAYOUT_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn counting_layout(
+        clients: &[&Client],
+        focused: Option<Xid>,
+        r: &Region,
+        max_main: u32,
+        ratio: f32,
+    ) -> Vec<ResizeAction> {
+        LAYOUT_CALLS.with(|n| n.set(n.get() + 1));
+        crate::core::layouts::mock_layout(clients, focused, r, max_main, ratio)
+    }
+
+    test_cases! {
+        focus_changes_only_relayout_follow_focus_layouts;
+        args: (follow_focus: bool, expected: usize);
+
+        case: follow_focus => (true, 1);
+        case: no_follow_focus => (false, 0);
+
+        body: {
+            let conf = LayoutConf {
+                follow_focus,
+                ..Default::default()
+            };
+            let conf = Config {
+                layouts: vec![Layout::new("t", conf, counting_layout, 1, 0.6)],
+                ..Default::default()
+            };
+            let conn = MockXConn::new(test_screens(), vec![], vec![]);
+            let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            LAYOUT_CALLS.with(|n| n.set(0));
+
+            wm.focus_client(&Selector::WinId(10)).unwrap();
+            assert_eq!(LAYOUT_CALLS.with(|n| n.get()), expected);
+
+            // re-focusing the same client never needs a relayout
+            wm.update_focus(10).unwrap();
+            assert_eq!(LAYOUT_CALLS.with(|n| n.get()), expected);
+        }
+    }
+
     #[test]
     fn layout_trigger_test_cycle_client_follow_focus() {
         let conn = RecordingXConn::init();
@@ -1611,7 +4679,7 @@ mod tests {
         wm.conn.clear();
         wm.cycle_client(Direction::Forward).unwrap();
 
-        assert!(wm.conn.was_called("position_client"));
+        assert!(wm.conn.was_called("grab_server"));
     }
 
     #[test]
@@ -1630,13 +4698,13 @@ mod tests {
 
         // shouldn't trigger layout when re-focusing same client
         wm.focus_client(&Selector::WinId(0)).unwrap();
-        assert!(wm.conn.was_not_called("position_client"));
+        assert!(wm.conn.was_not_called("grab_server"));
 
         wm.conn.clear();
 
         // focusing any other client should trigger layout stuff
         wm.focus_client(&Selector::WinId(1)).unwrap();
-        assert!(wm.conn.was_called("position_client"));
+        assert!(wm.conn.was_called("grab_server"));
     }
 
     /*
@@ -1672,7 +4740,7 @@ mod tests {
     }
 
     fn _remove_active() -> RecordedCall {
-        ("delete_prop".into(), strings!(42, "_NET_ACTIVE_WINDOW"))
+        _active(0)
     }
 
     test_cases! {
@@ -1718,7 +4786,7 @@ mod tests {
         case: client_does_not_accept_focus_different => (
             20, false, Some(10), 3, false,
             Some(20), vec![
-                _border(10, false), _id(Atom::WmTakeFocus), _take_focus(20)
+                _border(10, false), _id(Atom::WmTakeFocus), _take_focus(20), _active(20)
             ]
         );
 
@@ -1726,7 +4794,7 @@ mod tests {
         // set the border and send the TakeFocus event
         case: client_does_not_accept_focus_same => (
             20, false, Some(20), 3, false,
-            Some(20), vec![_id(Atom::WmTakeFocus), _take_focus(20)]
+            Some(20), vec![_id(Atom::WmTakeFocus), _take_focus(20), _active(20)]
         );
 
         // TODO: add test cases for follow_focus layout triggering
@@ -1735,8 +4803,8 @@ mod tests {
             let conn = RecordingXConn::init();
             let conf = Config {
                 layouts: focus_test_layouts(follow_focus),
-                focused_border: Color::try_from("#00ff00").unwrap(),
-                unfocused_border: Color::try_from("#ff0000").unwrap(),
+                focused_border: Color::try_from("#00ff00").unwrap().into(),
+                unfocused_border: Color::try_from("#ff0000").unwrap().into(),
                 ..Default::default()
             };
             let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
@@ -1852,209 +4920,2071 @@ mod tests {
     }
 
     #[test]
-    fn drag_workspace_move_focused_workspaces_between_screens() {
-        let mut wm = test_windowmanager(2, vec![]);
-
-        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
-        wm.drag_workspace(Forward).unwrap();
-        assert_eq!(wm.focused_workspaces(), vec![1, 0]);
-    }
-
-    #[test]
-    fn cycle_client_updates_focus() {
-        let mut wm = test_windowmanager(1, n_clients(3));
+    fn initial_workspaces_are_shown_on_startup() {
+        let conn = TestXConn::new(2, vec![], vec![]);
+        let conf = Config {
+            layouts: focus_test_layouts(false),
+            initial_workspaces: vec![3, 1],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
         wm.init().unwrap();
         wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
             .unwrap();
-        wm.focus_client(&Selector::WinId(0)).unwrap();
 
-        assert_eq!(wm.focused_client_id(), Some(0));
-        wm.cycle_client(Backward).unwrap();
-        assert_eq!(wm.focused_client_id(), Some(1));
-        wm.cycle_client(Backward).unwrap();
-        assert_eq!(wm.focused_client_id(), Some(2));
-        wm.cycle_client(Backward).unwrap();
-        assert_eq!(wm.focused_client_id(), Some(0));
+        assert_eq!(wm.screens.visible_workspaces(), vec![3, 1]);
+        assert_eq!(wm.screens.active_ws_index(), 3);
+        assert_eq!(wm.workspaces.focused_index(), 3);
+        assert_eq!(wm.workspace(&Selector::Focused).unwrap().name(), "4");
     }
 
     #[test]
-    fn focus_client() {
-        let mut wm = test_windowmanager(1, n_clients(3));
-        wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
+    fn layout_preview_regions_do_not_change_layout() {
+        let mut wm = test_windowmanager(1, n_clients(2));
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
             .unwrap();
 
-        let focused = wm.focus_client(&Selector::WinId(0));
-        assert_eq!(focused.unwrap(), 0);
-        let focused = wm.focus_client(&Selector::WinId(42));
-        assert!(focused.is_err());
-        let focused = wm.focus_client(&Selector::WinId(1));
-        assert_eq!(focused.unwrap(), 1);
-        let focused = wm.focus_client(&Selector::WinId(42));
-        assert!(focused.is_err());
-    }
+        let regions = wm.layout_preview_regions("second").unwrap();
 
-    #[test]
-    fn focus_client_no_clients() {
-        let mut wm = test_windowmanager(1, vec![]);
-        let focused = wm.focus_client(&Selector::WinId(0));
-        assert!(focused.is_err());
+        assert_eq!(
+            regions,
+            vec![Region::new(5, 23, 786, 277), Region::new(5, 314, 786, 277)]
+        );
+        assert_eq!(wm.current_layout_symbol(), "first");
     }
 
     #[test]
-    fn rotate_clients() {
-        let mut wm = test_windowmanager(1, n_clients(3));
-        wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
-            .unwrap();
+    fn layout_preview_regions_unknown_symbol_is_an_error() {
+        let wm = test_windowmanager(1, vec![]);
 
-        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
-        wm.rotate_clients(Forward).unwrap();
-        assert_eq!(wm.active_workspace().client_ids(), vec![0, 2, 1]);
+        assert!(wm.layout_preview_regions("unknown").is_err());
     }
 
     #[test]
-    fn drag_client() {
-        let mut wm = test_windowmanager(1, n_clients(4));
-        wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
-            .unwrap();
+    fn cycle_workspace_wraps_at_boundaries() {
+        let mut wm = test_windowmanager(1, vec![]);
 
-        assert_eq!(wm.active_workspace().client_ids(), vec![3, 2, 1, 0]);
-        wm.drag_client(Forward).unwrap();
-        assert_eq!(wm.active_workspace().client_ids(), vec![2, 3, 1, 0]);
-        wm.drag_client(Forward).unwrap();
-        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 3, 0]);
+        wm.cycle_workspace(Backward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![8]);
+        wm.cycle_workspace(Forward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![0]);
     }
 
     #[test]
-    fn cycle_layout() {
+    fn cycle_workspace_clamps_at_boundaries_without_wrapping() {
         let mut wm = test_windowmanager(1, vec![]);
+        wm.config.workspace_switch_wraps = false;
 
-        assert_eq!(wm.current_layout_symbol(), "first");
-        wm.cycle_layout(Forward).unwrap();
-        assert_eq!(wm.current_layout_symbol(), "second");
-        wm.cycle_layout(Forward).unwrap();
-        assert_eq!(wm.current_layout_symbol(), "first");
+        wm.cycle_workspace(Backward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![0]);
+
+        wm.focus_workspace(&Selector::Index(8)).unwrap();
+        wm.cycle_workspace(Forward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![8]);
+        wm.cycle_workspace(Backward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![7]);
     }
 
     #[test]
-    fn focus_workspace() {
+    fn workspaces_are_remapped_when_screens_are_hotplugged() {
+        let mut wm = test_windowmanager(2, vec![]);
+        wm.cycle_screen(Forward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
+        assert_eq!(wm.active_workspace().name(), "2");
+
+        wm.conn_mut().set_screen_count(1);
+        wm.detect_screens().unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![1]);
+        assert_eq!(wm.active_workspace().name(), "2");
+
+        wm.conn_mut().set_screen_count(3);
+        wm.detect_screens().unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![1, 0, 2]);
+        assert_eq!(wm.active_workspace().name(), "2");
+    }
+
+    #[test]
+    fn drag_workspace_move_focused_workspaces_between_screens() {
+        let mut wm = test_windowmanager(2, vec![]);
+
+        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
+        wm.drag_workspace(Forward).unwrap();
+        assert_eq!(wm.focused_workspaces(), vec![1, 0]);
+    }
+
+    #[test]
+    fn swap_screens_swaps_workspaces_without_moving_clients() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0);
+        wm.focus_screen(&Selector::Index(1));
+        add_n_clients(&mut wm, 1, 2);
+        wm.focus_screen(&Selector::Index(0));
+
+        wm.swap_screens().unwrap();
+
+        assert_eq!(wm.screens.visible_workspaces(), vec![1, 0]);
+        assert_eq!(wm.screens.focused_index(), 1);
+        assert_eq!(wm.screens.active_ws_index(), 0);
+        assert_eq!(wm.workspaces.client_ids(0).unwrap(), vec![20, 10]);
+        assert_eq!(wm.workspaces.client_ids(1).unwrap(), vec![30]);
+    }
+
+    #[test]
+    fn swap_screens_leaves_sticky_clients_on_their_screen() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.toggle_client_sticky(&Selector::WinId(10)).unwrap();
+        wm.focus_screen(&Selector::Index(1));
+        add_n_clients(&mut wm, 2, 2); // [40, 30]
+        wm.toggle_client_sticky(&Selector::WinId(30)).unwrap();
+        wm.focus_screen(&Selector::Index(0));
+
+        wm.swap_screens().unwrap();
+
+        assert_eq!(wm.screens.visible_workspaces(), vec![1, 0]);
+        assert_eq!(wm.workspaces.client_ids(0).unwrap(), vec![30, 20]);
+        assert_eq!(wm.workspaces.client_ids(1).unwrap(), vec![10, 40]);
+        assert_eq!(wm.clients.get(10).unwrap().workspace(), 1);
+        assert_eq!(wm.clients.get(30).unwrap().workspace(), 0);
+        assert!(wm.clients.get(10).unwrap().mapped);
+        assert!(wm.clients.get(30).unwrap().mapped);
+    }
+
+    #[test]
+    fn swap_screens_is_a_noop_with_a_single_screen() {
         let mut wm = test_windowmanager(1, vec![]);
+        wm.swap_screens().unwrap();
 
-        assert_eq!(wm.active_workspace().name(), "1");
-        wm.focus_workspace(&Selector::Index(3)).unwrap();
-        assert_eq!(wm.active_workspace().name(), "4");
-        wm.focus_workspace(&Selector::Condition(&|ws| ws.name() == "9"))
+        assert_eq!(wm.focused_workspaces(), vec![0]);
+    }
+
+    struct StartupCounter(Rc<Cell<usize>>);
+
+    impl<X: XConn> Hook<X> for StartupCounter {
+        fn startup(&mut self, _: &mut WindowManager<X>) -> Result<()> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn startup_hooks_run_once_unless_reset() {
+        let count = Rc::new(Cell::new(0));
+        let conn = TestXConn::new(1, n_clients(2), vec![]);
+        let hooks: Vec<Box<dyn Hook<TestXConn>>> = vec![Box::new(StartupCounter(count.clone()))];
+        let mut wm = WindowManager::new(Config::default(), conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+
+        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
             .unwrap();
-        assert_eq!(wm.active_workspace().name(), "9");
+        assert_eq!(count.get(), 1);
+
+        for _ in 0..3 {
+            wm.layout_screen(0).unwrap();
+            wm.cycle_layout(Forward).unwrap();
+        }
+        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
+            .unwrap();
+        assert_eq!(count.get(), 1);
+
+        wm.reset_startup_hooks();
+        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
+            .unwrap();
+        assert_eq!(count.get(), 2);
     }
 
     #[test]
-    fn toggle_workspace() {
-        let mut wm = test_windowmanager(1, vec![]);
+    fn cycle_client_updates_focus() {
+        let mut wm = test_windowmanager(1, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
+            .unwrap();
+        wm.focus_client(&Selector::WinId(0)).unwrap();
 
-        wm.focus_workspace(&Selector::Index(1)).unwrap();
-        assert_eq!(wm.active_workspace().name(), "2");
-        wm.focus_workspace(&Selector::Index(0)).unwrap();
-        assert_eq!(wm.active_workspace().name(), "1");
-        wm.toggle_workspace().unwrap();
-        assert_eq!(wm.active_workspace().name(), "2");
+        assert_eq!(wm.focused_client_id(), Some(0));
+        wm.cycle_client(Backward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(1));
+        wm.cycle_client(Backward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(2));
+        wm.cycle_client(Backward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(0));
     }
 
     #[test]
-    fn client_to_workspace() {
+    fn focus_client() {
         let mut wm = test_windowmanager(1, n_clients(3));
         wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+        wm.grab_keys_and_run(test_key_bindings(), HashMap::new())
             .unwrap();
 
-        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
-        (0..3).for_each(|_| wm.client_to_workspace(&Selector::Index(1)).unwrap());
-        wm.focus_workspace(&Selector::Index(1)).unwrap();
-        assert_eq!(wm.active_workspace().client_ids(), vec![0, 1, 2]);
+        let focused = wm.focus_client(&Selector::WinId(0));
+        assert_eq!(focused.unwrap(), 0);
+        let focused = wm.focus_client(&Selector::WinId(42));
+        assert!(focused.is_err());
+        let focused = wm.focus_client(&Selector::WinId(1));
+        assert_eq!(focused.unwrap(), 1);
+        let focused = wm.focus_client(&Selector::WinId(42));
+        assert!(focused.is_err());
     }
 
     #[test]
-    fn client_to_screen() {
-        let mut wm = test_windowmanager(2, n_clients(3));
+    fn focus_client_no_clients() {
+        let mut wm = test_windowmanager(1, vec![]);
+        let focused = wm.focus_client(&Selector::WinId(0));
+        assert!(focused.is_err());
+    }
+
+    #[test]
+    fn rotate_clients() {
+        let mut wm = test_windowmanager(1, n_clients(3));
         wm.init().unwrap();
         wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
             .unwrap();
 
-        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
-        assert_eq!(wm.active_screen_index(), 0);
         assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
-        wm.client_to_screen(&Selector::Index(1)).unwrap();
-        assert_eq!(wm.active_workspace().client_ids(), vec![1, 0]);
-        wm.cycle_screen(Forward).unwrap();
-        assert_eq!(wm.active_screen_index(), 1);
-        assert_eq!(wm.active_workspace().client_ids(), vec![2]);
+        wm.rotate_clients(Forward).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![0, 2, 1]);
     }
 
     #[test]
-    fn toggle_client_fullscreen() {
-        let mut wm = test_windowmanager(1, n_clients(1));
+    fn drag_client() {
+        let mut wm = test_windowmanager(1, n_clients(4));
         wm.init().unwrap();
         wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
             .unwrap();
 
-        assert!(!wm.client(&Selector::Focused).unwrap().is_fullscreen(),);
-        wm.toggle_client_fullscreen(&Selector::Focused).unwrap();
-        assert!(wm.client(&Selector::Focused).unwrap().is_fullscreen());
-        wm.toggle_client_fullscreen(&Selector::Focused).unwrap();
-        assert!(!wm.client(&Selector::Focused).unwrap().is_fullscreen(),);
+        assert_eq!(wm.active_workspace().client_ids(), vec![3, 2, 1, 0]);
+        wm.drag_client(Forward).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![2, 3, 1, 0]);
+        wm.drag_client(Forward).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 3, 0]);
     }
 
     #[test]
-    fn screen() {
-        let mut wm = test_windowmanager(2, n_clients(3));
+    fn toggle_master() {
+        let mut wm = test_windowmanager(1, n_clients(4));
         wm.init().unwrap();
         wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
             .unwrap();
+        wm.focus_client(&Selector::WinId(1)).unwrap();
 
-        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
-        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
+        assert_eq!(wm.active_workspace().client_ids(), vec![3, 2, 1, 0]);
+        wm.toggle_master().unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![1, 3, 2, 0]);
+        assert_eq!(wm.focused_client_id(), Some(1));
+        wm.toggle_master().unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![3, 2, 1, 0]);
+        assert_eq!(wm.focused_client_id(), Some(1));
+    }
 
-        assert_eq!(
-            wm.screen(&Selector::Index(0)),
-            wm.screen(&Selector::WinId(0)),
-        );
+    #[test]
+    fn promote_focused_moves_the_focused_client_to_master() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 4, 0); // [40, 30, 20, 10]
+        wm.focus_client(&Selector::WinId(20)).unwrap();
 
-        wm.client_to_screen(&Selector::Index(1)).unwrap();
+        wm.promote_focused().unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![20, 40, 30, 10]);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
 
-        assert_eq!(
-            wm.screen(&Selector::WinId(2)),
-            wm.screen(&Selector::Index(1)),
-        );
+    #[test]
+    fn promote_focused_swaps_an_already_master_client_into_the_stack() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 4, 0); // [40, 30, 20, 10]
+        wm.focus_client(&Selector::WinId(40)).unwrap();
+
+        wm.promote_focused().unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![30, 40, 20, 10]);
+        assert_eq!(wm.focused_client_id(), Some(30));
+
+        wm.promote_focused().unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![40, 30, 20, 10]);
+        assert_eq!(wm.focused_client_id(), Some(40));
     }
 
     #[test]
-    fn client() {
-        let mut wm = test_windowmanager(1, n_clients(3));
-        wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
-            .unwrap();
+    fn merge_workspaces() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0);
+        wm.client_to_workspace(&Selector::Index(2)).unwrap();
+        add_n_clients(&mut wm, 2, 2);
 
-        assert_eq!(wm.client(&Selector::Focused).unwrap().id(), 2);
-        assert_eq!(wm.client(&Selector::Index(2)).unwrap().id(), 0);
+        assert_eq!(wm.workspaces[0].client_ids(), vec![40, 30, 10]);
+        assert_eq!(wm.workspaces[2].client_ids(), vec![20]);
+
+        wm.merge_workspaces(0, 2).unwrap();
+
+        assert!(wm.workspaces[0].is_empty());
+        assert_eq!(wm.workspaces[2].client_ids(), vec![20, 40, 30, 10]);
+        for id in [10, 30, 40] {
+            assert_eq!(wm.clients.get(id).unwrap().workspace(), 2);
+            assert!(!wm.clients.get(id).unwrap().mapped);
+        }
+        assert!(wm.merge_workspaces(0, 42).is_err());
+        assert!(wm.merge_workspaces(42, 0).is_err());
     }
 
     #[test]
-    fn client_mut() {
-        let mut wm = test_windowmanager(1, n_clients(3));
+    fn merge_workspaces_skips_sticky_clients() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 3, 0);
+        wm.toggle_client_sticky(&Selector::WinId(20)).unwrap();
+
+        wm.merge_workspaces(0, 0).unwrap();
+        assert_eq!(wm.workspaces[0].client_ids(), vec![30, 20, 10]);
+
+        wm.merge_workspaces(0, 2).unwrap();
+        assert_eq!(wm.workspaces[0].client_ids(), vec![20]);
+        assert_eq!(wm.workspaces[2].client_ids(), vec![30, 10]);
+        assert_eq!(wm.clients.get(20).unwrap().workspace(), 0);
+    }
+
+    #[test]
+    fn toggle_client_floating() {
+        let conn = GeometryXConn::default();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
         wm.init().unwrap();
-        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
-            .unwrap();
+        for id in [10, 20, 30] {
+            wm.handle_map_request(id).unwrap();
+        }
+        let tiled = wm.conn.positions.borrow().clone();
 
-        assert_eq!(
-            wm.client(&Selector::Focused).map(|c| c.workspace()),
-            Some(0)
-        );
-        wm.client_mut(&Selector::Focused).unwrap().set_workspace(5);
-        assert_eq!(
-            wm.client(&Selector::Focused).map(|c| c.workspace()),
-            Some(5)
-        );
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+        assert!(wm.clients.get(20).unwrap().is_floating());
+        assert_ne!(wm.conn.positions.borrow()[&10], tiled[&10]);
+
+        // move the floating client so that we can check its position is remembered
+        let floating_region = Region::new(50, 50, 200, 100);
+        wm.handle_move_if_floating(20, floating_region).unwrap();
+
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+        assert!(!wm.clients.get(20).unwrap().is_floating());
+        assert_eq!(*wm.conn.positions.borrow(), tiled);
+
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+        assert_eq!(wm.conn.positions.borrow()[&20], floating_region);
+    }
+
+    #[derive(Default)]
+    struct GrabXConn {
+        grabs: Cell<usize>,
+        ungrabs: Cell<usize>,
+        fail_positioning: Cell<bool>,
+    }
+
+    __impl_stub_xcon! {
+        for GrabXConn;
+
+        atom_queries: {}
+        client_properties: {}
+        client_handler: {}
+        client_config: {
+            fn mock_position_client(
+                &self,
+                _id: Xid,
+                _r: Region,
+                _border: u32,
+                _stack_above: bool,
+            ) -> crate::core::xconnection::Result<()> {
+                if self.fail_positioning.get() {
+                    Err(XError::Raw("unable to position client".into()))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+        event_handler: {}
+        state: {
+            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
+                Ok(vec![Screen::new(Region::new(0, 0, 800, 600), 0)])
+            }
+        }
+        conn: {
+            fn mock_grab_server(&self) -> crate::core::xconnection::Result<()> {
+                self.grabs.set(self.grabs.get() + 1);
+                Ok(())
+            }
+
+            fn mock_ungrab_server(&self) -> crate::core::xconnection::Result<()> {
+                self.ungrabs.set(self.ungrabs.get() + 1);
+                Ok(())
+            }
+        }
+    }
+
+    test_cases! {
+        apply_layout_grabs_the_server_once;
+        args: (fail_positioning: bool);
+
+        case: success => (false);
+        case: positioning_fails => (true);
+
+        body: {
+            let mut wm = WindowManager::new(
+                Config::default(),
+                GrabXConn::default(),
+                vec![],
+                logging_error_handler(),
+            );
+            wm.init().unwrap();
+            for id in [10, 20, 30] {
+                wm.handle_map_request(id).unwrap();
+            }
+            wm.geometry_cache.borrow_mut().clear();
+            wm.conn.grabs.set(0);
+            wm.conn.ungrabs.set(0);
+            wm.conn.fail_positioning.set(fail_positioning);
+
+            assert_eq!(wm.apply_layout(0).is_err(), fail_positioning);
+            assert_eq!(wm.conn.grabs.get(), 1);
+            assert_eq!(wm.conn.ungrabs.get(), 1);
+        }
+    }
+
+    test_cases! {
+        snap_floating;
+        args: (floating: bool, spec: SnapRegion, expected: Option<Region>);
+
+        case: left_half => (true, SnapRegion::LeftHalf, Some(Region::new(10, 10, 380, 580)));
+        case: top_right => (true, SnapRegion::TopRight, Some(Region::new(410, 10, 380, 280)));
+        case: tiled_clients_are_ignored => (false, SnapRegion::LeftHalf, None);
+
+        body: {
+            let conn = GeometryXConn::default();
+            let conf = Config {
+                show_bar: false,
+                gap_px: 10,
+                outer_gap_px: 10,
+                border_px: 0,
+                ..Default::default()
+            };
+            let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.handle_map_request(10).unwrap();
+            if floating {
+                wm.toggle_client_floating(&Selector::WinId(10)).unwrap();
+            }
+            let before = wm.conn.positions.borrow()[&10];
+
+            wm.snap_floating(spec).unwrap();
+
+            let after = wm.conn.positions.borrow()[&10];
+            assert_eq!(after, expected.unwrap_or(before));
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Nudge {
+        Move(i32, i32),
+        Resize(i32, i32),
+    }
+
+    test_cases! {
+        keyboard_move_and_resize_floating;
+        args: (floating: bool, nudge: Nudge, expected: Region);
+
+        case: move_within_screen => (true, Nudge::Move(10, -20), Region::new(110, 80, 200, 100));
+        case: move_past_right_edge => (true, Nudge::Move(1000, 0), Region::new(600, 100, 200, 100));
+        case: move_past_top_edge => (true, Nudge::Move(0, -500), Region::new(100, 0, 200, 100));
+        case: grow => (true, Nudge::Resize(50, 20), Region::new(100, 100, 250, 120));
+        case: shrink_to_min => (true, Nudge::Resize(-500, -500), Region::new(100, 100, 20, 20));
+        case: grow_past_screen => (true, Nudge::Resize(1000, 0), Region::new(0, 100, 800, 100));
+        case: tiled_clients_are_ignored => (false, Nudge::Move(10, 10), Region::new(100, 100, 200, 100));
+
+        body: {
+            let conn = GeometryXConn::default();
+            let conf = Config {
+                show_bar: false,
+                border_px: 0,
+                ..Default::default()
+            };
+            let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.handle_map_request(10).unwrap();
+            if floating {
+                wm.toggle_client_floating(&Selector::WinId(10)).unwrap();
+            }
+            wm.position_client(10, Region::new(100, 100, 200, 100), true).unwrap();
+
+            match nudge {
+                Nudge::Move(dx, dy) => wm.move_floating(dx, dy).unwrap(),
+                Nudge::Resize(dw, dh) => wm.resize_floating(dw, dh).unwrap(),
+            }
+
+            assert_eq!(wm.conn.positions.borrow()[&10], expected);
+        }
+    }
+
+    #[test]
+    fn undecorated_clients_are_positioned_without_a_border() {
+        let conn = GeometryXConn::default().with_prop(
+            20,
+            Atom::MotifWmHints,
+            Prop::Bytes(vec![1 << 1, 0, 0, 0, 0]),
+        );
+        let conf = Config {
+            border_px: 3,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+
+        assert!(wm.clients.get(20).unwrap().is_undecorated());
+        assert_eq!(wm.conn.borders.borrow()[&10], 3);
+        assert_eq!(wm.conn.borders.borrow()[&20], 0);
+
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+        assert_eq!(wm.conn.borders.borrow()[&20], 0);
+        wm.toggle_client_floating(&Selector::WinId(20)).unwrap();
+
+        // dropping the hint gives the client its border back
+        wm.conn.remove_prop(20, Atom::MotifWmHints);
+        wm.handle_event_action(EventAction::ClientDecorationsChanged(20), None, None)
+            .unwrap();
+
+        assert!(!wm.clients.get(20).unwrap().is_undecorated());
+        assert_eq!(wm.conn.borders.borrow()[&20], 3);
+    }
+
+    #[test]
+    fn auto_layout_rules_follow_the_client_count() {
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let layout = |symbol| Layout::new(symbol, LayoutConf::default(), mock_layout, 1, 0.6);
+        let conf = Config {
+            layouts: vec![layout("tile"), layout("mono"), layout("float")],
+            auto_layout_rules: vec![
+                AutoLayoutRule::new(0, Some(0), "float"),
+                AutoLayoutRule::new(1, Some(1), "tile"),
+                AutoLayoutRule::new(2, None, "mono"),
+            ],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        assert_eq!(wm.current_layout_symbol(), "tile");
+
+        add_n_clients(&mut wm, 1, 0);
+        assert_eq!(wm.current_layout_symbol(), "tile");
+        add_n_clients(&mut wm, 1, 1);
+        assert_eq!(wm.current_layout_symbol(), "mono");
+
+        // manual changes stick until the number of clients changes
+        wm.cycle_layout(Forward).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "float");
+        add_n_clients(&mut wm, 1, 2);
+        assert_eq!(wm.current_layout_symbol(), "mono");
+
+        wm.remove_client(30).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "mono");
+        wm.remove_client(20).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "tile");
+        wm.remove_client(10).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "float");
+    }
+
+    test_cases! {
+        cycle_occupied_workspace;
+        args: (occupied: &[usize], direction: Direction, expected: usize);
+
+        case: forward => (&[0, 4, 7], Forward, 4);
+        case: backward_wraps => (&[0, 4, 7], Backward, 7);
+        case: no_other_occupied_workspaces => (&[0], Forward, 0);
+
+        body: {
+            let mut wm = wm_with_mock_conn(vec![], vec![]);
+            for (n, &wix) in occupied.iter().enumerate() {
+                wm.focus_workspace(&Selector::Index(wix)).unwrap();
+                wm.handle_map_request(10 * (n as Xid + 1)).unwrap();
+            }
+            wm.focus_workspace(&Selector::Index(0)).unwrap();
+
+            wm.cycle_occupied_workspace(direction).unwrap();
+
+            assert_eq!(wm.screens.active_ws_index(), expected);
+        }
+    }
+
+    #[test]
+    fn focus_urgent_switches_to_the_most_recently_urgent_client() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        for (wix, id) in [(3, 20), (5, 40), (0, 10)] {
+            wm.focus_workspace(&Selector::Index(wix)).unwrap();
+            wm.handle_map_request(id).unwrap();
+        }
+        wm.clients.set_urgent(20, true);
+        wm.clients.set_urgent(40, true);
+
+        for (wix, id) in [(5, 40), (3, 20)] {
+            wm.focus_urgent().unwrap();
+            assert_eq!(wm.screens.active_ws_index(), wix);
+            assert_eq!(wm.focused_client_id(), Some(id));
+            assert!(!wm.clients.get(id).unwrap().is_urgent());
+        }
+
+        // no more urgent clients
+        wm.focus_urgent().unwrap();
+        assert_eq!(wm.screens.active_ws_index(), 3);
+        assert_eq!(wm.focused_client_id(), Some(20));
+    }
+
+    fn wm_with_focus_after_close(strategy: FocusAfterClose) -> WindowManager<MockXConn> {
+        let conf = Config {
+            layouts: focus_test_layouts(false),
+            focus_after_close: strategy,
+            ..Default::default()
+        };
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+
+        wm
+    }
+
+    test_cases! {
+        focus_after_close;
+        args: (strategy: FocusAfterClose, focus_order: &[Xid], expected: Xid);
+
+        case: clamped => (FocusAfterClose::Clamped, &[30, 20], 30);
+        case: next_in_stack => (FocusAfterClose::NextInStack, &[30, 20], 10);
+        case: master => (FocusAfterClose::Master, &[10, 20], 30);
+        case: previous_focused => (FocusAfterClose::PreviousFocused, &[30, 20], 30);
+
+        body: {
+            let mut wm = wm_with_focus_after_close(strategy);
+            for &id in focus_order {
+                wm.focus_client(&Selector::WinId(id)).unwrap();
+            }
+
+            wm.remove_client(20).unwrap();
+
+            assert_eq!(wm.active_workspace().client_ids(), vec![30, 10]);
+            assert_eq!(wm.focused_client_id(), Some(expected));
+            assert_eq!(wm.active_workspace().focused_client(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn default_focus_after_close_is_unchanged() {
+        let mut wm = wm_with_focus_after_close(Config::default().focus_after_close);
+        wm.focus_client(&Selector::WinId(20)).unwrap();
+
+        wm.remove_client(20).unwrap();
+
+        // The workspace stack clamps its focus up to the head when the closed client was
+        // one of the last two clients
+        assert_eq!(wm.focused_client_id(), Some(30));
+    }
+
+    #[test]
+    fn focus_history_skips_closed_clients() {
+        let mut wm = wm_with_focus_after_close(FocusAfterClose::PreviousFocused);
+        for id in [10, 30, 20] {
+            wm.focus_client(&Selector::WinId(id)).unwrap();
+        }
+
+        wm.remove_client(30).unwrap();
+        wm.remove_client(20).unwrap();
+
+        assert_eq!(wm.focused_client_id(), Some(10));
+    }
+
+    test_cases! {
+        client_to_workspace_and_follow;
+        args: (target: usize, expected_ws: usize);
+
+        case: hidden_workspace => (3, 3);
+        case: visible_on_other_screen => (1, 1);
+        case: current_workspace => (0, 0);
+        case: invalid_index => (42, 0);
+
+        body: {
+            let mut wm = wm_with_mock_conn(vec![], vec![]);
+            add_n_clients(&mut wm, 3, 0);
+            wm.focus_client(&Selector::WinId(20)).unwrap();
+
+            wm.client_to_workspace_and_follow(&Selector::Index(target)).unwrap();
+
+            assert_eq!(wm.screens.active_ws_index(), expected_ws);
+            assert_eq!(wm.screens.focused_index(), 0);
+            assert_eq!(wm.focused_client_id(), Some(20));
+            assert_eq!(wm.clients.get(20).unwrap().workspace(), expected_ws);
+            assert!(wm.workspaces[expected_ws].client_ids().contains(&20));
+        }
+    }
+
+    #[test]
+    fn cycle_layout_filtered_only_visits_matching_layouts() {
+        let layout = |symbol| Layout::new(symbol, LayoutConf::default(), mock_layout, 1, 0.6);
+        let conf = Config {
+            layouts: vec![layout("a"), layout("b"), layout("c"), layout("d")],
+            ..Default::default()
+        };
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        // "a" isn't in the filter so we jump to the first symbol given
+        let mut visited = vec![];
+        for _ in 0..4 {
+            wm.cycle_layout_filtered(&["d", "b"]).unwrap();
+            visited.push(wm.current_layout_symbol().to_string());
+        }
+
+        assert_eq!(visited, vec!["d", "b", "d", "b"]);
+    }
+
+    fn wm_with_two_layouts() -> WindowManager<MockXConn> {
+        wm_with_two_layouts_and_hooks(vec![])
+    }
+
+    fn wm_with_two_layouts_and_hooks(
+        hooks: Vec<Box<dyn Hook<MockXConn>>>,
+    ) -> WindowManager<MockXConn> {
+        let layout = |symbol| Layout::new(symbol, LayoutConf::default(), mock_layout, 1, 0.6);
+        let conf = Config {
+            layouts: vec![layout("a"), layout("b")],
+            ..Default::default()
+        };
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let mut wm = WindowManager::new(conf, conn, hooks, logging_error_handler());
+        wm.init().unwrap();
+
+        wm
+    }
+
+    test_cases! {
+        main_ratio_is_clamped_to_config_bounds;
+        args: (change: Change, expected: f32);
+
+        case: clamped_at_max => (Change::More, 0.8);
+        case: clamped_at_min => (Change::Less, 0.3);
+
+        body: {
+            let conf = Config {
+                layouts: focus_test_layouts(false),
+                min_main_ratio: 0.3,
+                max_main_ratio: 0.8,
+                ..Default::default()
+            };
+            let mut wm = WindowManager::new(conf, MockXConn::new(test_screens(), vec![], vec![]), vec![], logging_error_handler());
+            wm.init().unwrap();
+            let new_ws = wm.create_workspace("new").unwrap();
+
+            for _ in 0..20 {
+                wm.update_main_ratio(change).unwrap();
+            }
+            wm.focus_workspace(&Selector::Index(new_ws)).unwrap();
+            for _ in 0..20 {
+                wm.update_main_ratio(change).unwrap();
+            }
+
+            for ix in &[0, new_ws] {
+                assert_eq!(wm.layout_state()[*ix].layouts[0].ratio, expected);
+            }
+        }
+    }
+
+    fn wm_with_adjusted_layouts() -> WindowManager<MockXConn> {
+        let mut wm = wm_with_two_layouts();
+        wm.update_max_main(Change::More).unwrap();
+        wm.update_main_ratio(Change::Less).unwrap();
+        wm.cycle_layout(Forward).unwrap();
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        wm.update_max_main(Change::More).unwrap();
+        wm.update_max_main(Change::More).unwrap();
+
+        wm
+    }
+
+    #[test]
+    fn layout_state_can_be_restored() {
+        let state = wm_with_adjusted_layouts().layout_state();
+        let mut wm = wm_with_two_layouts();
+        assert_ne!(wm.layout_state(), state);
+
+        wm.restore_layout_state(&state).unwrap();
+        assert_eq!(wm.layout_state(), state);
+    }
+
+    struct LayoutChangeRecorder(Rc<RefCell<Vec<usize>>>);
+
+    impl<X: XConn> Hook<X> for LayoutChangeRecorder {
+        fn layout_change(&mut self, _: &mut WindowManager<X>, wix: usize, _: usize) -> Result<()> {
+            self.0.borrow_mut().push(wix);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restoring_layout_state_runs_layout_change_hooks_for_visible_workspaces() {
+        // only the active layout of workspace 0 differs from the defaults
+        let state = wm_with_adjusted_layouts().layout_state();
+        let changes = Rc::new(RefCell::new(vec![]));
+        let mut wm =
+            wm_with_two_layouts_and_hooks(vec![Box::new(LayoutChangeRecorder(changes.clone()))]);
+
+        wm.restore_layout_state(&state).unwrap();
+        assert_eq!(changes.take(), vec![0]);
+
+        // restoring the same state again does not change any layouts
+        wm.restore_layout_state(&state).unwrap();
+        assert_eq!(changes.take(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn restoring_layout_state_ignores_unknown_workspaces_and_layouts() {
+        let mut state = wm_with_adjusted_layouts().layout_state();
+        state[0].name = "unknown".into();
+        state[2].active = "unknown".into();
+        state[2].layouts[0].symbol = "unknown".into();
+
+        let mut wm = wm_with_two_layouts();
+        let initial = wm.layout_state();
+        wm.restore_layout_state(&state).unwrap();
+        let restored = wm.layout_state();
+
+        assert_eq!(restored[0], initial[0]);
+        assert_eq!(restored[2], initial[2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn layout_state_round_trips_through_a_file() {
+        let path =
+            std::env::temp_dir().join(format!("penrose-layouts-{}.toml", std::process::id()));
+        let state = wm_with_adjusted_layouts().layout_state();
+        wm_with_adjusted_layouts().save_layout_state(&path).unwrap();
+
+        let mut wm = wm_with_two_layouts();
+        wm.load_layout_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(wm.layout_state(), state);
+        assert!(wm.load_layout_state(&path).is_ok()); // missing files are ignored
+    }
+
+    #[test]
+    fn toggle_gaps_removes_and_restores_gaps() {
+        let conf = Config {
+            gap_px: 5,
+            outer_gap_px: 10,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(
+            conf,
+            MockXConn::new(test_screens(), vec![], vec![]),
+            vec![],
+            logging_error_handler(),
+        );
+        wm.init().unwrap();
+
+        wm.toggle_gaps().unwrap();
+        assert_eq!((wm.gap_px, wm.outer_gap_px), (0, 0));
+
+        wm.toggle_gaps().unwrap();
+        assert_eq!((wm.gap_px, wm.outer_gap_px), (5, 10));
+    }
+
+    #[test]
+    fn toggle_bar_releases_and_restores_the_bar_region() {
+        let conn = GeometryXConn::default();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        let bar_height = wm.config.bar_height;
+        let (x, y, w, h) = wm.screen_size(0).unwrap().values();
+        let (_, cy, _, ch) = wm.conn.positions.borrow()[&10].values();
+
+        wm.toggle_bar().unwrap();
+        assert_eq!(
+            wm.screen_size(0),
+            Some(Region::new(x, y - bar_height, w, h + bar_height))
+        );
+        let (_, y_hidden, _, h_hidden) = wm.conn.positions.borrow()[&10].values();
+        assert_eq!((y_hidden, h_hidden), (cy - bar_height, ch + bar_height));
+
+        wm.toggle_bar().unwrap();
+        assert_eq!(wm.screen_size(0), Some(Region::new(x, y, w, h)));
+        assert_eq!(wm.conn.positions.borrow()[&10].values().3, ch);
+    }
+
+    #[test]
+    fn toggle_bar_keeps_space_reserved_by_docks() {
+        let conn = dock_conn(vec![(2, vec![0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, 799])]);
+        let conf = Config {
+            bar_height: 18,
+            gap_px: 0,
+            outer_gap_px: 0,
+            border_px: 0,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        process_events(
+            &mut wm,
+            vec![XEvent::MapRequest(2, false), XEvent::MapRequest(10, false)],
+        );
+        let with_bar = Region::new(0, 18, 800, 562);
+        assert_eq!(wm.screen_size(0), Some(with_bar));
+
+        wm.toggle_bar().unwrap();
+        let without_bar = Region::new(0, 0, 800, 580);
+        assert_eq!(wm.screen_size(0), Some(without_bar));
+        assert_eq!(wm.conn.positions.borrow().get(&10), Some(&without_bar));
+
+        wm.toggle_bar().unwrap();
+        assert_eq!(wm.screen_size(0), Some(with_bar));
+    }
+
+    #[test]
+    fn adjust_gaps_is_clamped() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        let start = wm.gap_px;
+
+        wm.adjust_gaps(Change::More, 4).unwrap();
+        wm.adjust_gaps(Change::More, 4).unwrap();
+        assert_eq!(wm.gap_px, start + 8);
+
+        wm.adjust_gaps(Change::Less, 4).unwrap();
+        assert_eq!(wm.gap_px, start + 4);
+
+        wm.adjust_gaps(Change::Less, start + 20).unwrap();
+        assert_eq!(wm.gap_px, 0);
+
+        wm.adjust_gaps(Change::More, 1000).unwrap();
+        assert_eq!(wm.gap_px, MAX_GAP_PX);
+
+        // adjusting while gaps are toggled off restores them first
+        wm.toggle_gaps().unwrap();
+        wm.adjust_gaps(Change::Less, 10).unwrap();
+        assert_eq!(wm.gap_px, MAX_GAP_PX - 10);
+        assert_eq!(wm.hidden_gaps, None);
+    }
+
+    #[test]
+    fn raise_and_lower_client_maintain_the_floating_stacking_order() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 4, 0); // [40, 30, 20, 10]
+        for id in [10, 20, 30] {
+            wm.toggle_client_floating(&Selector::WinId(id)).unwrap();
+        }
+        wm.focus_client(&Selector::WinId(40)).unwrap();
+        let order = |wm: &WindowManager<_>| wm.clients.ids_in_stacking_order().to_vec();
+        assert_eq!(order(&wm), vec![40, 30, 20, 10]);
+
+        wm.raise_client(&Selector::WinId(30)).unwrap();
+        assert_eq!(order(&wm), vec![40, 20, 10, 30]);
+
+        wm.lower_client(&Selector::WinId(10)).unwrap();
+        assert_eq!(order(&wm), vec![40, 10, 20, 30]);
+
+        // tiled clients stay below the floating layer
+        wm.raise_client(&Selector::WinId(40)).unwrap();
+        assert_eq!(order(&wm), vec![40, 10, 20, 30]);
+
+        // focusing a floating client raises it
+        wm.focus_client(&Selector::WinId(20)).unwrap();
+        assert_eq!(order(&wm), vec![40, 10, 30, 20]);
+
+        // sticky clients stay above other floating clients
+        wm.toggle_client_sticky(&Selector::WinId(10)).unwrap();
+        assert_eq!(order(&wm), vec![40, 30, 20, 10]);
+        wm.raise_client(&Selector::WinId(30)).unwrap();
+        assert_eq!(order(&wm), vec![40, 20, 30, 10]);
+    }
+
+    #[test]
+    fn unfocused_clients_are_dimmed_on_focus_change() {
+        let conn = RecordingXConn::init();
+        let conf = Config {
+            unfocused_opacity: Some(0.5),
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 2, 0);
+
+        let opacity_calls = |wm: &WindowManager<RecordingXConn>| -> Vec<Vec<String>> {
+            let calls = wm.conn.calls();
+            wm.conn.clear();
+            calls
+                .into_iter()
+                .filter(|(m, args)| {
+                    m == "change_prop" && args[1] == strings!(Atom::NetWmWindowOpacity.as_ref())[0]
+                })
+                .map(|(_, args)| args)
+                .collect()
+        };
+        let opacity =
+            |id: Xid, val: u32| strings!(id, "_NET_WM_WINDOW_OPACITY", Prop::Cardinal(val));
+        let (half, full) = (0x8000_0000, u32::MAX);
+
+        opacity_calls(&wm);
+        wm.focus_client(&Selector::WinId(10)).unwrap();
+        assert_eq!(
+            opacity_calls(&wm),
+            vec![opacity(20, half), opacity(10, full)]
+        );
+
+        // exempting the focused client leaves it fully opaque
+        wm.set_client_dim_exempt(10, true).unwrap();
+        assert_eq!(opacity_calls(&wm), vec![opacity(10, full)]);
+
+        // exempt clients are not dimmed when they lose focus
+        wm.focus_client(&Selector::WinId(20)).unwrap();
+        assert_eq!(opacity_calls(&wm), vec![opacity(20, full)]);
+
+        // clients that are managed without taking focus are dimmed straight away
+        wm.handle_iconify_request(30).unwrap();
+        wm.handle_map_request(30).unwrap();
+        assert!(opacity_calls(&wm).contains(&opacity(30, half)));
+    }
+
+    #[test]
+    fn workspaces_use_the_default_layout_of_the_screen_they_are_first_shown_on() {
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let layout = |symbol| Layout::new(symbol, LayoutConf::default(), mock_layout, 1, 0.6);
+        let conf = Config {
+            layouts: vec![layout("side"), layout("stack")],
+            screen_layouts: vec![ScreenLayout::new(1, "stack")],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        let symbol =
+            |wm: &WindowManager<_>, wix: usize| wm.workspaces[wix].layout_symbol().to_string();
+
+        assert_eq!(symbol(&wm, 0), "side");
+        assert_eq!(symbol(&wm, 1), "stack");
+
+        // moving workspaces between screens keeps their current layout
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert_eq!(symbol(&wm, 0), "side");
+        assert_eq!(symbol(&wm, 1), "stack");
+
+        // workspaces shown for the first time pick up the default for their screen
+        wm.cycle_screen(Forward).unwrap();
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        assert_eq!(wm.screens.focused_index(), 1);
+        assert_eq!(symbol(&wm, 2), "stack");
+    }
+
+    #[test]
+    fn screen_bars_only_reserve_space_on_configured_screens() {
+        let conn = MockXConn::new(test_screens(), vec![], vec![]);
+        let conf = Config {
+            layouts: focus_test_layouts(false),
+            bar_height: 18,
+            screen_bars: vec![
+                ScreenBar::new(0, true, false),
+                ScreenBar::new(1, false, true),
+            ],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        let (x, y, w, h) = wm.screens.get(0).unwrap().region(false).values();
+        let second = wm.screens.get(1).unwrap().region(false);
+
+        assert_eq!(wm.screen_size(0), Some(Region::new(x, y, w, h - 18)));
+        assert_eq!(wm.screen_size(1), Some(second));
+    }
+
+    #[test]
+    fn bounded_floating_layouts_clamp_clients_to_the_screen() {
+        let conn = GeometryXConn::default();
+        let conf = Config {
+            layouts: vec![Layout::floating_bounded("float")],
+            show_bar: false,
+            border_px: 2,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        assert_eq!(wm.conn.positions.borrow().get(&10), None);
+
+        // the screen is 800x600
+        let off_screen = Region::new(700, 550, 200, 100);
+        wm.conn.positions.borrow_mut().insert(10, off_screen);
+        wm.apply_layout(0).unwrap();
+
+        assert_eq!(
+            wm.conn.positions.borrow()[&10],
+            Region::new(596, 496, 200, 100)
+        );
+    }
+
+    #[test]
+    fn fullscreen_uses_the_whole_screen_and_restores_on_exit() {
+        let conn = GeometryXConn::default();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        for id in [10, 20, 30] {
+            wm.handle_map_request(id).unwrap();
+        }
+        wm.toggle_client_floating(&Selector::WinId(30)).unwrap();
+        let floating_region = Region::new(50, 50, 200, 100);
+        wm.handle_move_if_floating(30, floating_region).unwrap();
+        let before = wm.conn.positions.borrow().clone();
+
+        // The full screen region is used rather than the region left free by the bar
+        let screen = wm.screens.focused().region(false);
+        assert_ne!(screen, wm.screens.focused().region(true));
+
+        for id in [20, 30] {
+            wm.toggle_client_fullscreen(&Selector::WinId(id)).unwrap();
+            assert_eq!(wm.conn.positions.borrow()[&id], screen);
+
+            // the layout is suspended while a client is fullscreen
+            wm.apply_layout(0).unwrap();
+            assert_eq!(wm.conn.positions.borrow()[&id], screen);
+
+            wm.toggle_client_fullscreen(&Selector::WinId(id)).unwrap();
+            assert_eq!(*wm.conn.positions.borrow(), before);
+        }
+    }
+
+    fn grid_layout(
+        clients: &[&Client],
+        _: Option<Xid>,
+        r: &Region,
+        _: u32,
+        _: f32,
+    ) -> Vec<ResizeAction> {
+        clients
+            .iter()
+            .zip(r.quadrants())
+            .map(|(c, q)| ResizeAction::new(c.id(), Some(q)))
+            .collect()
+    }
+
+    fn wm_with_grid_of_clients() -> WindowManager<GeometryXConn> {
+        let conn = GeometryXConn::default();
+        let conf = Config {
+            layouts: vec![Layout::new(
+                "grid",
+                LayoutConf::default(),
+                grid_layout,
+                1,
+                0.6,
+            )],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        for id in [10, 20, 30, 40] {
+            wm.handle_map_request(id).unwrap();
+        }
+
+        wm // [40, 30] on top, [20, 10] below: 40 focused
+    }
+
+    #[test]
+    fn focus_direction() {
+        use RelativePosition::*;
+        let mut wm = wm_with_grid_of_clients();
+        assert_eq!(wm.focused_client_id(), Some(40));
+
+        for (direction, expected) in [(Right, 30), (Below, 10), (Left, 20), (Above, 40)] {
+            wm.focus_direction(direction).unwrap();
+            assert_eq!(wm.focused_client_id(), Some(expected));
+        }
+
+        // no client in that direction
+        wm.focus_direction(Above).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(40));
+    }
+
+    #[test]
+    fn swap_direction() {
+        use RelativePosition::*;
+        let mut wm = wm_with_grid_of_clients();
+        let original = wm.active_workspace().client_ids();
+        assert_eq!(original, vec![40, 30, 20, 10]);
+
+        wm.swap_direction(Right).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![30, 40, 20, 10]);
+        assert_eq!(wm.focused_client_id(), Some(40));
+
+        // nothing further to the right
+        wm.swap_direction(Right).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![30, 40, 20, 10]);
+
+        wm.swap_direction(Left).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), original);
+        assert_eq!(wm.focused_client_id(), Some(40));
+    }
+
+    #[test]
+    fn cycle_layout() {
+        let mut wm = test_windowmanager(1, vec![]);
+
+        assert_eq!(wm.current_layout_symbol(), "first");
+        wm.cycle_layout(Forward).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "second");
+        wm.cycle_layout(Forward).unwrap();
+        assert_eq!(wm.current_layout_symbol(), "first");
+    }
+
+    #[test]
+    fn focus_workspace() {
+        let mut wm = test_windowmanager(1, vec![]);
+
+        assert_eq!(wm.active_workspace().name(), "1");
+        wm.focus_workspace(&Selector::Index(3)).unwrap();
+        assert_eq!(wm.active_workspace().name(), "4");
+        wm.focus_workspace(&Selector::Condition(&|ws| ws.name() == "9"))
+            .unwrap();
+        assert_eq!(wm.active_workspace().name(), "9");
+    }
+
+    #[test]
+    fn toggle_workspace() {
+        let mut wm = test_windowmanager(1, vec![]);
+
+        // nothing to toggle back to yet
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.active_workspace().name(), "1");
+
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert_eq!(wm.active_workspace().name(), "2");
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.active_workspace().name(), "1");
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.active_workspace().name(), "2");
+        wm.cycle_workspace(Forward).unwrap();
+        assert_eq!(wm.active_workspace().name(), "3");
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.active_workspace().name(), "2");
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.active_workspace().name(), "3");
+    }
+
+    #[test]
+    fn toggle_workspace_is_tracked_per_screen() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+
+        wm.focus_workspace(&Selector::Index(4)).unwrap();
+        wm.cycle_screen(Forward).unwrap();
+        wm.focus_workspace(&Selector::Index(6)).unwrap();
+        assert_eq!(wm.screens.visible_workspaces(), vec![4, 6]);
+
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.screens.visible_workspaces(), vec![4, 1]);
+
+        wm.cycle_screen(Backward).unwrap();
+        wm.toggle_workspace().unwrap();
+        assert_eq!(wm.screens.visible_workspaces(), vec![0, 1]);
+    }
+
+    #[test]
+    fn client_to_workspace() {
+        let mut wm = test_windowmanager(1, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
+        (0..3).for_each(|_| wm.client_to_workspace(&Selector::Index(1)).unwrap());
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn client_to_screen() {
+        let mut wm = test_windowmanager(2, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
+        assert_eq!(wm.active_screen_index(), 0);
+        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
+        wm.client_to_screen(&Selector::Index(1)).unwrap();
+        assert_eq!(wm.active_workspace().client_ids(), vec![1, 0]);
+        wm.cycle_screen(Forward).unwrap();
+        assert_eq!(wm.active_screen_index(), 1);
+        assert_eq!(wm.active_workspace().client_ids(), vec![2]);
+    }
+
+    #[test]
+    fn toggle_client_fullscreen() {
+        let mut wm = test_windowmanager(1, n_clients(1));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert!(!wm.client(&Selector::Focused).unwrap().is_fullscreen(),);
+        wm.toggle_client_fullscreen(&Selector::Focused).unwrap();
+        assert!(wm.client(&Selector::Focused).unwrap().is_fullscreen());
+        wm.toggle_client_fullscreen(&Selector::Focused).unwrap();
+        assert!(!wm.client(&Selector::Focused).unwrap().is_fullscreen(),);
+    }
+
+    #[test]
+    fn screen() {
+        let mut wm = test_windowmanager(2, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(wm.active_workspace().client_ids(), vec![2, 1, 0]);
+        assert_eq!(wm.focused_workspaces(), vec![0, 1]);
+
+        assert_eq!(
+            wm.screen(&Selector::Index(0)),
+            wm.screen(&Selector::WinId(0)),
+        );
+
+        wm.client_to_screen(&Selector::Index(1)).unwrap();
+
+        assert_eq!(
+            wm.screen(&Selector::WinId(2)),
+            wm.screen(&Selector::Index(1)),
+        );
+    }
+
+    #[test]
+    fn client() {
+        let mut wm = test_windowmanager(1, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(wm.client(&Selector::Focused).unwrap().id(), 2);
+        assert_eq!(wm.client(&Selector::Index(2)).unwrap().id(), 0);
+    }
+
+    #[test]
+    fn client_mut() {
+        let mut wm = test_windowmanager(1, n_clients(3));
+        wm.init().unwrap();
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(
+            wm.client(&Selector::Focused).map(|c| c.workspace()),
+            Some(0)
+        );
+        wm.client_mut(&Selector::Focused).unwrap().set_workspace(5);
+        assert_eq!(
+            wm.client(&Selector::Focused).map(|c| c.workspace()),
+            Some(5)
+        );
+    }
+
+    fn tap_test_wm(events: Vec<XEvent>) -> (TestWM, Rc<Cell<usize>>) {
+        let mut wm = test_windowmanager(1, events);
+        let taps = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&taps);
+        wm.set_modifier_tap_bindings(map! {
+            TAP_CODE => Box::new(move |_: &mut TestWM| {
+                counter.set(counter.get() + 1);
+                Ok(())
+            }) as TestKeyHandler,
+        });
+
+        (wm, taps)
+    }
+
+    const TAP_CODE: KeyCode = KeyCode { mask: 0, code: 133 };
+    const TAP_MASK: u16 = 1 << 6;
+
+    #[test]
+    fn modifier_tap_runs_on_clean_release() {
+        let release = KeyCode {
+            mask: TAP_MASK,
+            code: TAP_CODE.code,
+        };
+        let (mut wm, taps) = tap_test_wm(vec![
+            XEvent::KeyPress(TAP_CODE),
+            XEvent::KeyRelease(release),
+        ]);
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(taps.get(), 1);
+    }
+
+    #[test]
+    fn modifier_tap_does_not_run_when_used_as_part_of_a_binding() {
+        let chord = KeyCode {
+            mask: TAP_MASK,
+            code: 44,
+        };
+        let release = KeyCode {
+            mask: TAP_MASK,
+            code: TAP_CODE.code,
+        };
+        let (mut wm, taps) = tap_test_wm(vec![
+            XEvent::KeyPress(TAP_CODE),
+            XEvent::KeyPress(chord),
+            XEvent::KeyRelease(chord),
+            XEvent::KeyRelease(release),
+        ]);
+        wm.grab_keys_and_run(test_key_bindings(), test_mouse_bindings())
+            .unwrap();
+
+        assert_eq!(taps.get(), 0);
+    }
+
+    fn click_test_wm(double_click_ms: u64) -> (TestWM, Rc<Cell<usize>>, Rc<Cell<usize>>) {
+        let click = |x| {
+            let state = MouseState::new(MouseButton::Left, vec![ModifierKey::Meta]);
+            XEvent::MouseEvent(MouseEvent::new(
+                0,
+                x,
+                10,
+                x,
+                10,
+                state,
+                MouseEventKind::Press,
+            ))
+        };
+        let mut wm = test_windowmanager(1, vec![click(10), click(12)]);
+        wm.config.double_click_ms = double_click_ms;
+
+        let (singles, doubles) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let (s, d) = (Rc::clone(&singles), Rc::clone(&doubles));
+        let state = MouseState::new(MouseButton::Left, vec![ModifierKey::Meta]);
+        let bindings: TestMouseBindings = map! {
+            (MouseEventKind::Press, state.clone()) => Box::new(move |_: &mut TestWM, _: &MouseEvent| {
+                s.set(s.get() + 1);
+                Ok(())
+            }) as MouseEventHandler<TestXConn>,
+            (MouseEventKind::DoubleClick, state) => Box::new(move |_: &mut TestWM, _: &MouseEvent| {
+                d.set(d.get() + 1);
+                Ok(())
+            }) as MouseEventHandler<TestXConn>,
+        };
+        wm.grab_keys_and_run(test_key_bindings(), bindings).unwrap();
+
+        (wm, singles, doubles)
+    }
+
+    #[test]
+    fn quick_clicks_run_double_click_binding() {
+        let (_, singles, doubles) = click_test_wm(10_000);
+
+        assert_eq!(singles.get(), 1);
+        assert_eq!(doubles.get(), 1);
+    }
+
+    #[test]
+    fn slow_clicks_run_press_binding_twice() {
+        let (_, singles, doubles) = click_test_wm(0);
+
+        assert_eq!(singles.get(), 2);
+        assert_eq!(doubles.get(), 0);
+    }
+
+    #[test]
+    fn motion_bindings_wait_for_drag_threshold() {
+        let state = MouseState::new(MouseButton::Left, vec![ModifierKey::Meta]);
+        let event =
+            |kind, x| XEvent::MouseEvent(MouseEvent::new(0, x, 10, x, 10, state.clone(), kind));
+        let mut wm = test_windowmanager(
+            1,
+            vec![
+                event(MouseEventKind::Press, 10),
+                event(MouseEventKind::Motion, 11),
+                event(MouseEventKind::Motion, 13),
+                event(MouseEventKind::Motion, 20),
+                event(MouseEventKind::Motion, 12),
+            ],
+        );
+
+        let seen = Rc::new(Cell::new(vec![]));
+        let s = Rc::clone(&seen);
+        let bindings: TestMouseBindings = map! {
+            (MouseEventKind::Motion, state.clone()) => Box::new(move |_: &mut TestWM, e: &MouseEvent| {
+                let mut xs = s.take();
+                xs.push(e.rpt.x);
+                s.set(xs);
+                Ok(())
+            }) as MouseEventHandler<TestXConn>,
+        };
+        wm.grab_keys_and_run(test_key_bindings(), bindings).unwrap();
+
+        // once the threshold has been crossed all motion is dispatched
+        assert_eq!(seen.take(), vec![20, 12]);
+    }
+
+    test_cases! {
+        closing_a_parent_closes_its_transient_children;
+        args: (supports_delete: bool, expected: Vec<&str>);
+
+        case: asks_supporting_clients_to_close => (true, vec!["delete_window"]);
+        case: kills_unsupporting_clients => (false, vec!["kill_client"]);
+
+        body: {
+            let conn = CloseXConn { supports_delete, calls: RefCell::new(vec![]) };
+            let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            wm.clients.modify(20, |c| c.transient_for = Some(10));
+
+            wm.remove_client(10).unwrap();
+
+            assert_eq!(*wm.conn.calls.borrow(), expected);
+            assert_eq!(wm.pending_close.contains_key(&20), supports_delete);
+        }
+    }
+
+    #[test]
+    fn orphaned_transients_can_be_left_floating() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        wm.config.orphaned_transients = TransientPolicy::Float;
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.clients.modify(20, |c| c.transient_for = Some(10));
+
+        wm.remove_client(10).unwrap();
+
+        let c = wm.client(&Selector::WinId(20)).unwrap();
+        assert!(c.floating);
+        assert_eq!(c.transient_for(), None);
+    }
+
+    #[test]
+    fn floating_orphaned_transients_relayout_their_workspace() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.config.orphaned_transients = TransientPolicy::Float;
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+        wm.move_client_to_workspace(10, 1).unwrap();
+        wm.clients.modify(20, |c| c.transient_for = Some(10));
+        wm.conn.clear();
+
+        wm.remove_client(10).unwrap();
+
+        let calls = wm.conn.calls();
+        let repositioned = |id: &str| {
+            calls
+                .iter()
+                .any(|(m, args)| m == "position_client" && args[0] == id)
+        };
+        assert!(repositioned("20"));
+        assert!(repositioned("30"));
+    }
+
+    fn dock_conn(struts: Vec<(Xid, Vec<u32>)>) -> GeometryXConn {
+        struts
+            .into_iter()
+            .fold(GeometryXConn::default(), |conn, (id, vals)| {
+                conn.with_prop(
+                    id,
+                    Atom::NetWmWindowType,
+                    Prop::Atom(vec![Atom::NetWindowTypeDock.as_ref().into()]),
+                )
+                .with_prop(id, Atom::NetWmStrutPartial, Prop::Bytes(vals))
+            })
+    }
+
+    test_cases! {
+        docks_reserve_space_while_mapped;
+        args: (docks: Vec<Xid>, expected: Region);
+
+        case: no_docks => (vec![], Region::new(0, 0, 800, 600));
+        case: top_dock => (vec![1], Region::new(0, 30, 800, 570));
+        case: top_and_bottom_docks => (vec![1, 2], Region::new(0, 30, 800, 550));
+
+        body: {
+            let conn = dock_conn(vec![
+                (1, vec![0, 0, 30, 0, 0, 0, 0, 0, 0, 799, 0, 0]),
+                (2, vec![0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, 799]),
+            ]);
+            let conf = Config {
+                bar_height: 0,
+                gap_px: 0,
+                outer_gap_px: 0,
+                border_px: 0,
+                ..Default::default()
+            };
+            let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+
+            let mut events: Vec<XEvent> = docks.iter().map(|&id| XEvent::MapRequest(id, false)).collect();
+            events.push(XEvent::MapRequest(10, false));
+            process_events(&mut wm, events);
+
+            assert_eq!(wm.screens.focused().region(true), expected);
+            assert_eq!(wm.conn.positions.borrow().get(&10), Some(&expected));
+
+            // unmapping the docks releases their space
+            process_events(&mut wm, docks.iter().map(|&id| XEvent::UnmapNotify(id)).collect());
+            let full = Region::new(0, 0, 800, 600);
+            assert_eq!(wm.screens.focused().region(true), full);
+            assert_eq!(wm.conn.positions.borrow().get(&10), Some(&full));
+        }
+    }
+
+    #[test]
+    fn docks_reserve_space_when_the_bar_is_not_shown() {
+        let conn = dock_conn(vec![(1, vec![0, 0, 30, 0, 0, 0, 0, 0, 0, 799, 0, 0])]);
+        let conf = Config {
+            show_bar: false,
+            bar_height: 18,
+            gap_px: 0,
+            outer_gap_px: 0,
+            border_px: 0,
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        process_events(
+            &mut wm,
+            vec![XEvent::MapRequest(1, false), XEvent::MapRequest(10, false)],
+        );
+
+        let expected = Region::new(0, 30, 800, 570);
+        assert_eq!(wm.screens.focused().region(true), expected);
+        assert_eq!(wm.conn.positions.borrow().get(&10), Some(&expected));
+    }
+
+    struct CloseXConn {
+        supports_delete: bool,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    __impl_stub_xcon! {
+        for CloseXConn;
+
+        atom_queries: {
+            fn mock_atom_id(&self, _name: &str) -> crate::core::xconnection::Result<Xid> {
+                Ok(0)
+            }
+        }
+        client_properties: {
+            fn mock_get_prop(&self, id: Xid, name: &str) -> crate::core::xconnection::Result<Prop> {
+                if name == Atom::WmProtocols.as_ref() && self.supports_delete {
+                    Ok(Prop::Atom(vec![Atom::WmDeleteWindow.as_ref().into()]))
+                } else {
+                    Err(XError::MissingProperty(name.into(), id))
+                }
+            }
+        }
+        client_handler: {
+            fn mock_kill_client(&self, _id: Xid) -> crate::core::xconnection::Result<()> {
+                self.calls.borrow_mut().push("kill_client");
+                Ok(())
+            }
+        }
+        client_config: {}
+        event_handler: {
+            fn mock_wait_for_event(&self) -> crate::core::xconnection::Result<XEvent> {
+                Ok(XEvent::KeyPress(crate::__test_helpers::EXIT_CODE))
+            }
+
+            fn mock_wait_for_event_timeout(
+                &self,
+                timeout: Duration,
+            ) -> crate::core::xconnection::Result<Option<XEvent>> {
+                // Simulate an idle X server: nothing arrives before the timeout
+                self.calls.borrow_mut().push("timed_out");
+                std::thread::sleep(timeout);
+                Ok(None)
+            }
+
+            fn mock_send_client_event(
+                &self,
+                _msg: ClientMessage,
+            ) -> crate::core::xconnection::Result<()> {
+                self.calls.borrow_mut().push("delete_window");
+                Ok(())
+            }
+        }
+        state: {
+            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
+                Ok(vec![Screen::new(Region::new(0, 0, 800, 600), 0)])
+            }
+        }
+        conn: {}
+    }
+
+    test_cases! {
+        close_focused;
+        args: (supports_delete: bool, timeout_ms: u64, n_closes: usize, expected: Vec<&str>);
+
+        case: asks_supporting_clients_to_close => (true, 3000, 1, vec!["delete_window"]);
+        case: kills_unsupporting_clients => (false, 3000, 1, vec!["kill_client"]);
+        case: repeat_before_timeout => (true, 3000, 2, vec!["delete_window", "delete_window"]);
+        case: repeat_after_timeout => (true, 0, 2, vec!["delete_window", "kill_client"]);
+
+        body: {
+            let conn = CloseXConn { supports_delete, calls: RefCell::new(vec![]) };
+            let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.config.close_timeout_ms = timeout_ms;
+            add_n_clients(&mut wm, 1, 0);
+
+            for _ in 0..n_closes {
+                wm.close_focused().unwrap();
+            }
+
+            assert_eq!(*wm.conn.calls.borrow(), expected);
+        }
+    }
+
+    test_cases! {
+        unresponsive_clients_are_killed;
+        args: (timeout_ms: u64, removed: bool, expected: Vec<&str>);
+
+        case: after_timeout => (0, false, vec!["delete_window", "kill_client"]);
+        case: not_before_timeout => (3000, false, vec!["delete_window"]);
+        case: not_once_closed => (0, true, vec!["delete_window"]);
+
+        body: {
+            let conn = CloseXConn { supports_delete: true, calls: RefCell::new(vec![]) };
+            let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.config.close_timeout_ms = timeout_ms;
+            add_n_clients(&mut wm, 1, 0);
+
+            wm.close_focused().unwrap();
+            if removed {
+                wm.remove_client(10).unwrap();
+            }
+            wm.kill_unresponsive_clients().unwrap();
+
+            assert_eq!(*wm.conn.calls.borrow(), expected);
+        }
+    }
+
+    #[test]
+    fn unresponsive_clients_are_killed_while_the_x_server_is_idle() {
+        let conn = CloseXConn {
+            supports_delete: true,
+            calls: RefCell::new(vec![]),
+        };
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.config.close_timeout_ms = 10;
+        add_n_clients(&mut wm, 1, 0);
+
+        let exit = crate::__test_helpers::EXIT_CODE;
+        let key_bindings: KeyBindings<CloseXConn> = map! {
+            exit => Box::new(|wm: &mut WindowManager<CloseXConn>| wm.exit()) as crate::core::bindings::KeyEventHandler<_>,
+        };
+
+        wm.close_focused().unwrap();
+        wm.grab_keys_and_run(key_bindings, HashMap::new()).unwrap();
+
+        assert_eq!(
+            *wm.conn.calls.borrow(),
+            vec!["delete_window", "timed_out", "kill_client"]
+        );
+    }
+
+    #[cfg(feature = "ipc")]
+    struct IdleXConn {
+        timeouts: Cell<usize>,
+    }
+
+    // Gives up and exits after this many timeouts so that a regression can't hang the test
+    #[cfg(feature = "ipc")]
+    const MAX_IDLE_TIMEOUTS: usize = 100;
+
+    #[cfg(feature = "ipc")]
+    __impl_stub_xcon! {
+        for IdleXConn;
+
+        atom_queries: {}
+        client_properties: {}
+        client_handler: {}
+        client_config: {}
+        event_handler: {
+            fn mock_wait_for_event(&self) -> crate::core::xconnection::Result<XEvent> {
+                Ok(XEvent::KeyPress(crate::__test_helpers::EXIT_CODE))
+            }
+
+            fn mock_wait_for_event_timeout(
+                &self,
+                timeout: Duration,
+            ) -> crate::core::xconnection::Result<Option<XEvent>> {
+                self.timeouts.set(self.timeouts.get() + 1);
+                if self.timeouts.get() >= MAX_IDLE_TIMEOUTS {
+                    return Ok(Some(XEvent::KeyPress(crate::__test_helpers::EXIT_CODE)));
+                }
+                std::thread::sleep(timeout);
+                Ok(None)
+            }
+        }
+        state: {
+            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
+                Ok(vec![Screen::new(Region::new(0, 0, 800, 600), 0)])
+            }
+        }
+        conn: {}
+    }
+
+    #[cfg(feature = "ipc")]
+    #[test]
+    fn ipc_requests_are_handled_while_the_x_server_is_idle() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+
+        let conn = IdleXConn {
+            timeouts: Cell::new(0),
+        };
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("penrose-idle-ipc-{}.sock", std::process::id()));
+        wm.set_ipc_server(IpcServer::bind(&path).unwrap());
+
+        let client = std::thread::spawn(move || {
+            let mut stream = UnixStream::connect(path).unwrap();
+            writeln!(stream, "focus-workspace 2\nexit").unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            (
+                lines.next().unwrap().unwrap(),
+                lines.next().unwrap().unwrap(),
+            )
+        });
+
+        let exit = crate::__test_helpers::EXIT_CODE;
+        let key_bindings: KeyBindings<IdleXConn> = map! {
+            exit => Box::new(|wm: &mut WindowManager<IdleXConn>| wm.exit()) as crate::core::bindings::KeyEventHandler<_>,
+        };
+        wm.grab_keys_and_run(key_bindings, HashMap::new()).unwrap();
+
+        assert!(wm.conn.timeouts.get() < MAX_IDLE_TIMEOUTS);
+        assert_eq!(wm.screens.active_ws_index(), 2);
+        assert_eq!(client.join().unwrap(), ("ok".to_string(), "ok".to_string()));
+    }
+
+    #[test]
+    fn layout_stacking_hints_are_applied() {
+        let conn = RecordingXConn::init();
+        let conf = Config {
+            layouts: vec![Layout::new(
+                "[mono]",
+                LayoutConf::default(),
+                crate::core::layout::layouts::monocle,
+                1,
+                0.6,
+            )],
+            ..Default::default()
+        };
+        let mut wm = WindowManager::new(conf, conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.conn.clear();
+
+        wm.apply_layout(0).unwrap();
+
+        let raised: RecordedCall = ("raise_client".into(), strings!(20));
+        assert!(wm.conn.calls().contains(&raised));
+    }
+
+    #[test]
+    fn current_layout_conf_follows_the_active_layout() {
+        let conn = RecordingXConn::init();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        // default layouts are side_stack followed by floating
+        let conf = wm.current_layout_conf().unwrap();
+        assert_eq!(conf, LayoutConf::default());
+        assert!(!conf.floating);
+
+        wm.cycle_layout(Forward).unwrap();
+        assert!(wm.current_layout_conf().unwrap().floating);
+    }
+
+    fn process_events<X: XConn>(wm: &mut WindowManager<X>, events: Vec<XEvent>) {
+        let (mut kb, mut mb) = (HashMap::new(), HashMap::new());
+        for event in events {
+            for action in process_next_event(event, wm) {
+                wm.handle_event_action(action, Some(&mut kb), Some(&mut mb))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn enter(id: Xid, x: i32) -> XEvent {
+        XEvent::Enter(PointerChange {
+            id,
+            abs: Point::new(x, 0),
+            relative: Point::new(0, 0),
+        })
+    }
+
+    fn left_press() -> XEvent {
+        let state = MouseState::new(MouseButton::Left, vec![ModifierKey::Meta]);
+        XEvent::MouseEvent(MouseEvent::new(0, 5, 5, 5, 5, state, MouseEventKind::Press))
+    }
+
+    test_cases! {
+        focus_follows_mouse;
+        args: (enabled: bool, events: Vec<XEvent>, expected: Xid);
+
+        case: enter_other_client => (true, vec![enter(10, 50)], 10);
+        case: disabled => (false, vec![enter(10, 50)], 20);
+        case: not_while_dragging => (true, vec![left_press(), enter(10, 50)], 20);
+
+        body: {
+            let mut wm = wm_with_mock_conn(vec![], vec![]);
+            wm.config.focus_follows_mouse = enabled;
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            assert_eq!(wm.focused_client_id(), Some(20));
+
+            process_events(&mut wm, events);
+            assert_eq!(wm.focused_client_id(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn focus_does_not_follow_clients_mapped_under_the_cursor() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 2, 0); // [20, 10]
+        wm.focus_workspace(&Selector::Index(2)).unwrap();
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+
+        // MockXConn reports the cursor at (0, 0)
+        let cursor = wm.conn.cursor_position().unwrap();
+        process_events(&mut wm, vec![enter(10, cursor.x)]);
+        assert_eq!(wm.focused_client_id(), Some(20));
+
+        // once the pointer moves focus follows it again
+        process_events(&mut wm, vec![enter(10, cursor.x + 5)]);
+        assert_eq!(wm.focused_client_id(), Some(10));
+    }
+
+    struct WarpXConn {
+        cursor: Point,
+        warps: Cell<usize>,
+    }
+
+    __impl_stub_xcon! {
+        for WarpXConn;
+
+        atom_queries: {}
+        client_properties: {}
+        client_handler: {}
+        client_config: {}
+        event_handler: {}
+        state: {
+            fn mock_current_screens(&self) -> crate::core::xconnection::Result<Vec<Screen>> {
+                Ok(vec![Screen::new(Region::new(0, 0, 800, 600), 0)])
+            }
+
+            fn mock_cursor_position(&self) -> crate::core::xconnection::Result<Point> {
+                Ok(self.cursor)
+            }
+
+            fn mock_warp_cursor(
+                &self,
+                _win_id: Option<Xid>,
+                _screen: &Screen,
+            ) -> crate::core::xconnection::Result<()> {
+                self.warps.set(self.warps.get() + 1);
+                Ok(())
+            }
+
+            fn mock_client_geometry(&self, _id: Xid) -> crate::core::xconnection::Result<Region> {
+                Ok(Region::new(100, 100, 200, 200))
+            }
+        }
+        conn: {}
+    }
+
+    test_cases! {
+        focus_warps_cursor;
+        args: (enabled: bool, cursor: Point, events: Vec<XEvent>, expected: usize);
+
+        case: focus_change => (true, Point::new(0, 0), vec![], 1);
+        case: disabled => (false, Point::new(0, 0), vec![], 0);
+        case: not_while_dragging => (true, Point::new(0, 0), vec![left_press()], 0);
+        case: not_when_already_inside => (true, Point::new(150, 150), vec![], 0);
+
+        body: {
+            let conn = WarpXConn { cursor, warps: Cell::new(0) };
+            let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            wm.config.focus_warps_cursor = enabled;
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            process_events(&mut wm, events);
+            wm.conn.warps.set(0);
+
+            wm.cycle_client(Direction::Forward).unwrap();
+            assert_eq!(wm.focused_client_id(), Some(10));
+            assert_eq!(wm.conn.warps.get(), expected);
+        }
+    }
+
+    test_cases! {
+        focus_warps_cursor_on_every_focus_change;
+        args: (action: fn(&mut WindowManager<WarpXConn>) -> Result<()>, expected: Option<Xid>);
+
+        case: removing_the_focused_client => (|wm| wm.remove_client(20), Some(10));
+        case: switching_workspace => (
+            |wm| {
+                wm.focus_workspace(&Selector::Index(1))?;
+                wm.focus_workspace(&Selector::Index(0))
+            },
+            Some(20)
+        );
+
+        body: {
+            let conn = WarpXConn { cursor: Point::new(0, 0), warps: Cell::new(0) };
+            let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+            wm.init().unwrap();
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            wm.conn.warps.set(0);
+
+            action(&mut wm).unwrap();
+            assert_eq!(wm.focused_client_id(), expected);
+            assert_eq!(wm.conn.warps.get(), 1);
+        }
+    }
+
+    fn iconify(id: Xid) -> XEvent {
+        let mask = ClientEventMask::NoEventMask;
+        let data = [3u32, 0, 0, 0, 0];
+        XEvent::ClientMessage(ClientMessage::new(id, mask, "WM_CHANGE_STATE", data.into()))
+    }
+
+    #[test]
+    fn clients_iconifying_themselves_while_focused_lose_focus() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        add_n_clients(&mut wm, 3, 0); // [30, 20, 10]
+        assert_eq!(wm.focused_client_id(), Some(30));
+
+        process_events(&mut wm, vec![iconify(30)]);
+        assert!(wm.clients.get(30).unwrap().is_minimized());
+        assert_eq!(wm.focused_client_id(), Some(20));
+
+        wm.cycle_client(Backward).unwrap();
+        assert_eq!(wm.focused_client_id(), Some(10));
+    }
+
+    test_cases! {
+        iconify_requests_are_ordering_independent;
+        args: (events: Vec<XEvent>);
+
+        case: map_then_iconify => (vec![XEvent::MapRequest(30, false), iconify(30)]);
+        case: iconify_then_map => (vec![iconify(30), XEvent::MapRequest(30, false)]);
+
+        body: {
+            let mut wm = wm_with_mock_conn(vec![], vec![]);
+            add_n_clients(&mut wm, 2, 0); // [20, 10]
+            process_events(&mut wm, events);
+
+            let c = wm.clients.get(30).unwrap();
+            assert!(c.is_minimized());
+            assert!(!c.mapped);
+            assert_eq!(wm.workspaces[0].client_ids(), vec![30, 20, 10]);
+            assert!(wm.pending_iconify.is_empty());
+        }
     }
 }
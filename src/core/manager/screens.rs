@@ -1,7 +1,8 @@
 //! State and management of screens being layed out by Penrose.
 use crate::{
     core::{
-        data_types::Region,
+        config::ScreenBar,
+        data_types::{Region, Strut},
         hooks::HookName,
         manager::event::EventAction,
         ring::{Direction, Ring, Selector},
@@ -10,13 +11,21 @@ use crate::{
     },
     Result,
 };
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 #[derive(Debug)]
 pub struct Screens {
     pub(super) inner: Ring<Screen>,
     bar_height: u32,
+    show_bar: bool,
     top_bar: bool,
+    screen_bars: Vec<ScreenBar>,
+    // Set when the space reserved for bars has been toggled off on every screen
+    bars_hidden: bool,
+    struts: Vec<Strut>,
+    // Workspaces that were visible on a screen that has since been removed, in the order that
+    // they should be re-shown if screens are added again.
+    detached: Vec<usize>,
 }
 
 impl Screens {
@@ -24,7 +33,55 @@ impl Screens {
         Self {
             inner: Ring::default(),
             bar_height,
+            show_bar: true,
             top_bar,
+            screen_bars: vec![],
+            bars_hidden: false,
+            struts: vec![],
+            detached: vec![],
+        }
+    }
+
+    /// Set whether or not space is reserved for a status bar on each screen, using `show_bar`
+    /// for screens that do not have a [ScreenBar] rule.
+    pub fn set_screen_bars(&mut self, show_bar: bool, screen_bars: Vec<ScreenBar>) {
+        self.show_bar = show_bar;
+        self.screen_bars = screen_bars;
+    }
+
+    // Recalculate the region available to clients on the screen at the given index
+    fn update_screen_region(&self, ix: usize, s: &mut Screen) {
+        let (show_bar, top_bar) = match self.screen_bars.iter().find(|b| b.screen == ix) {
+            Some(b) => (b.show_bar, b.top_bar),
+            None => (self.show_bar, self.top_bar),
+        };
+        s.set_show_bar(show_bar && !self.bars_hidden);
+        s.update_effective_region(self.bar_height, top_bar);
+    }
+
+    /// Toggle the space reserved for status bars off on every screen, or back to the configured
+    /// settings for each screen if it is currently toggled off.
+    pub fn toggle_bars(&mut self) -> Vec<EventAction> {
+        self.bars_hidden = !self.bars_hidden;
+        self.update_regions()
+    }
+
+    /// Set the workspaces to show on each screen the first time that screens are assigned
+    /// workspaces. This has no effect once screens have been detected.
+    pub fn set_initial_workspaces(&mut self, workspaces: &[usize]) {
+        if self.inner.is_empty() {
+            self.detached = workspaces.to_vec();
+        }
+    }
+
+    /// Drop the workspace at `removed` from the workspaces waiting to be re-shown and shift any
+    /// that were after it down by one to match the new workspace indices.
+    pub fn remove_detached_workspace(&mut self, removed: usize) {
+        self.detached.retain(|&wix| wix != removed);
+        for wix in self.detached.iter_mut() {
+            if *wix > removed {
+                *wix -= 1;
+            }
         }
     }
 
@@ -63,8 +120,8 @@ impl Screens {
         self.inner.len()
     }
 
-    pub fn screen_size(&self, index: usize, bar_visible: bool) -> Option<Region> {
-        self.inner.get(index).map(|s| s.region(bar_visible))
+    pub fn screen_size(&self, index: usize) -> Option<Region> {
+        self.inner.get(index).map(|s| s.region(true))
     }
 
     pub fn active_screen_index(&self) -> usize {
@@ -75,6 +132,11 @@ impl Screens {
         self.inner.focused_unchecked().wix
     }
 
+    // The active workspace index, or None if no screens have been detected yet
+    pub fn active_ws_index_checked(&self) -> Option<usize> {
+        self.inner.focused().map(|s| s.wix)
+    }
+
     pub fn update_known_screens<S>(
         &mut self,
         state: &S,
@@ -83,26 +145,24 @@ impl Screens {
     where
         S: XState,
     {
-        let mut workspace_ordering = self.visible_workspaces();
-        workspace_ordering.append(
-            &mut (0..n_workspaces)
-                .filter(|w| !workspace_ordering.contains(w))
-                .collect(),
-        );
-
-        debug!(?workspace_ordering, "current workspace ordering");
-
         let mut detected: Vec<Screen> = state.current_screens()?;
+        if detected.is_empty() {
+            warn!("no screens detected: keeping the current screen layout");
+            return Ok(vec![]);
+        }
 
         // sort the screens to be in the correct order
         detected.sort_by_key(|s| s.true_region.x);
 
-        let detected = detected
+        let (workspace_ordering, focused) = self.assign_workspaces(detected.len(), n_workspaces);
+        debug!(?workspace_ordering, ?self.detached, "current workspace ordering");
+
+        let mut detected: Vec<Screen> = detected
             .into_iter()
             .zip(workspace_ordering)
             .enumerate()
             .map(|(ix, (mut s, wix))| {
-                s.update_effective_region(self.bar_height, self.top_bar);
+                self.update_screen_region(ix, &mut s);
                 trace!(screen = ix, workspace = wix, "setting workspace for screen");
                 s.wix = wix;
 
@@ -111,9 +171,11 @@ impl Screens {
                 s
             })
             .collect();
+        reserve_struts(&mut detected, &self.struts);
 
         Ok(if self.inner.as_vec() != detected {
             self.inner = Ring::new(detected);
+            self.inner.focus(&Selector::Index(focused));
             vec![
                 EventAction::LayoutVisible,
                 EventAction::RunHook(HookName::ScreenUpdated),
@@ -123,6 +185,76 @@ impl Screens {
         })
     }
 
+    // Pick the workspace to show on each of `n_screens` screens along with the index of the
+    // screen that should be focused. Screens that still exist keep their workspace, and the
+    // focused workspace stays visible (on the last remaining screen) if its screen is removed.
+    // Workspaces that lose their screen are remembered and re-shown first when screens are added.
+    fn assign_workspaces(&mut self, n_screens: usize, n_workspaces: usize) -> (Vec<usize>, usize) {
+        let previous = self.visible_workspaces();
+        let mut focused = self.inner.focused_index();
+        let mut assigned: Vec<usize> = previous.iter().take(n_screens).copied().collect();
+        let mut lost: Vec<usize> = previous.iter().skip(n_screens).copied().collect();
+
+        if focused >= n_screens && !assigned.is_empty() {
+            let focused_wix = previous[focused];
+            lost.retain(|&wix| wix != focused_wix);
+            lost.push(assigned[n_screens - 1]);
+            assigned[n_screens - 1] = focused_wix;
+            focused = n_screens - 1;
+        }
+
+        let mut candidates = self.detached.clone();
+        candidates.extend(0..n_workspaces);
+        for wix in candidates {
+            if assigned.len() == n_screens {
+                break;
+            }
+            if wix < n_workspaces && !assigned.contains(&wix) {
+                assigned.push(wix);
+            }
+        }
+
+        lost.extend(self.detached.iter().copied());
+        self.detached = lost
+            .into_iter()
+            .filter(|wix| *wix < n_workspaces && !assigned.contains(wix))
+            .fold(vec![], |mut acc, wix| {
+                if !acc.contains(&wix) {
+                    acc.push(wix);
+                }
+                acc
+            });
+
+        (assigned, focused.min(n_screens - 1))
+    }
+
+    // Replace the current set of struts reserved by docks and recalculate the effective region
+    // of each screen.
+    pub fn set_struts(&mut self, struts: Vec<Strut>) -> Vec<EventAction> {
+        self.struts = struts;
+        self.update_regions()
+    }
+
+    // Recalculate the effective region of each screen, requesting a new layout if any changed
+    fn update_regions(&mut self) -> Vec<EventAction> {
+        let mut screens = self.inner.as_vec();
+        for (ix, s) in screens.iter_mut().enumerate() {
+            self.update_screen_region(ix, s);
+        }
+        reserve_struts(&mut screens, &self.struts);
+
+        let changed = self.inner.iter().zip(screens.iter()).any(|(a, b)| a != b);
+        for (current, updated) in self.inner.iter_mut().zip(screens) {
+            *current = updated;
+        }
+
+        if changed {
+            vec![EventAction::LayoutVisible]
+        } else {
+            vec![]
+        }
+    }
+
     pub fn focus_screen(&mut self, sel: &Selector<'_, Screen>) -> Vec<EventAction> {
         match self.inner.focus(sel) {
             Some((true, focused)) => vec![
@@ -152,6 +284,27 @@ impl Screens {
     }
 }
 
+// Struts are given relative to the root window, which spans all connected screens
+fn reserve_struts(screens: &mut [Screen], struts: &[Strut]) {
+    if struts.is_empty() || screens.is_empty() {
+        return;
+    }
+
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for s in screens.iter() {
+        let (x, y, w, h) = s.region(false).values();
+        x0 = x0.min(x);
+        y0 = y0.min(y);
+        x1 = x1.max(x + w);
+        y1 = y1.max(y + h);
+    }
+
+    let root = Region::new(x0, y0, x1 - x0, y1 - y0);
+    for s in screens.iter_mut() {
+        s.reserve_struts(struts, root);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,7 +441,12 @@ mod tests {
                     ).collect()
                 ),
                 bar_height,
-                top_bar
+                show_bar: true,
+                top_bar,
+                screen_bars: vec![],
+                bars_hidden: false,
+                struts: vec![],
+                detached: vec![],
             };
 
             s.update_known_screens(&conn, n_workspaces).unwrap();
@@ -297,4 +455,56 @@ mod tests {
             assert_eq!(focused, expected);
         }
     }
+
+    fn n_outputs(n: u32) -> OutputsXConn {
+        OutputsXConn(
+            (0..n)
+                .map(|i| Screen::new(Region::new(1000 * i, 0, 1000, 800), i as usize))
+                .collect(),
+        )
+    }
+
+    test_cases! {
+        hotplug;
+        args: (initial: Vec<usize>, focused: usize, n_screens: u32, after: Vec<usize>, restored: Vec<usize>);
+
+        case: unplug_unfocused => (vec![3, 5, 9], 0, 1, vec![3], vec![3, 5, 9]);
+        case: unplug_focused_keeps_it_visible => (vec![3, 5], 1, 1, vec![5], vec![5, 3]);
+        case: unplug_focused_from_three => (vec![3, 5, 9], 2, 2, vec![3, 9], vec![3, 9, 5]);
+
+        body: {
+            let mut s = Screens::new(10, true);
+            s.update_known_screens(&n_outputs(initial.len() as u32), 10).unwrap();
+            for (screen, wix) in s.inner.iter_mut().zip(initial.iter()) {
+                screen.wix = *wix;
+            }
+            s.inner.focus(&Selector::Index(focused));
+            let focused_wix = s.active_ws_index();
+
+            s.update_known_screens(&n_outputs(n_screens), 10).unwrap();
+            assert_eq!(s.visible_workspaces(), after);
+            assert_eq!(s.active_ws_index(), focused_wix);
+
+            s.update_known_screens(&n_outputs(initial.len() as u32), 10).unwrap();
+            assert_eq!(s.visible_workspaces(), restored);
+            assert_eq!(s.active_ws_index(), focused_wix);
+        }
+    }
+
+    #[test]
+    fn removing_a_workspace_updates_detached_workspaces() {
+        let mut s = Screens::new(10, true);
+        s.update_known_screens(&n_outputs(3), 10).unwrap();
+        for (screen, wix) in s.inner.iter_mut().zip([3, 5, 9]) {
+            screen.wix = wix;
+        }
+        s.update_known_screens(&n_outputs(1), 10).unwrap();
+        assert_eq!(s.detached, vec![5, 9]);
+
+        s.remove_detached_workspace(5);
+        assert_eq!(s.detached, vec![8]);
+
+        s.update_known_screens(&n_outputs(3), 9).unwrap();
+        assert_eq!(s.visible_workspaces(), vec![3, 8, 0]);
+    }
 }
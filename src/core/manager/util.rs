@@ -1,24 +1,59 @@
 use crate::{
     core::{
-        data_types::Region,
+        client::Client,
+        config::{Config, WindowMatch, WindowRule},
+        data_types::{Point, Region, RelativePosition},
         xconnection::{XClientConfig, XState, Xid},
     },
     Result,
 };
 
-pub(super) fn pad_region(region: &Region, gapless: bool, gap_px: u32, border_px: u32) -> Region {
-    let gpx = if gapless { 0 } else { gap_px };
-    let padding = 2 * (border_px + gpx);
+// The fraction of the screen used for the width or height of a centered window that has no size
+const DEFAULT_CENTERED_FRACTION: f64 = 0.5;
+
+// A region of the given size centered on the screen, shrunk to fit if it is larger than the
+// screen. A width or height of zero is replaced with a fraction of the screen size.
+pub(super) fn center_on_screen(screen_region: &Region, w: u32, h: u32) -> Region {
+    let (sx, sy, sw, sh) = screen_region.values();
+    let or_default = |len: u32, screen_len: u32| match len {
+        0 => (screen_len as f64 * DEFAULT_CENTERED_FRACTION) as u32,
+        len => len.min(screen_len),
+    };
+    let (w, h) = (or_default(w, sw), or_default(h, sh));
+
+    Region::new(sx + (sw - w) / 2, sy + (sh - h) / 2, w, h)
+}
+
+// Edges of 'region' that touch the edge of 'screen' are padded by 'outer_gap_px' and all others
+// by 'gap_px'.
+pub(super) fn pad_region(
+    region: &Region,
+    screen: &Region,
+    gapless: bool,
+    gap_px: u32,
+    outer_gap_px: u32,
+    border_px: u32,
+) -> Region {
     let (x, y, w, h) = region.values();
+    let (sx, sy, sw, sh) = screen.values();
+    let gap = |on_edge: bool| match (gapless, on_edge) {
+        (true, _) => 0,
+        (false, true) => outer_gap_px,
+        (false, false) => gap_px,
+    };
+
+    let (left, top) = (gap(x <= sx), gap(y <= sy));
+    let (right, bottom) = (gap(x + w >= sx + sw), gap(y + h >= sy + sh));
+    let (pad_w, pad_h) = (left + right + 2 * border_px, top + bottom + 2 * border_px);
 
     // Check that the resulting size would not be zero or negative
     // Do not allow zero-size as this is chosen by the WM
-    if w <= padding || h <= padding {
+    if w <= pad_w || h <= pad_h {
         warn!("not padding region to avoid integer underflow");
         return *region;
     }
 
-    Region::new(x + gpx, y + gpx, w - padding, h - padding)
+    Region::new(x + left, y + top, w - pad_w, h - pad_h)
 }
 
 pub(super) fn position_floating_client<X>(
@@ -31,10 +66,7 @@ where
     X: XClientConfig + XState,
 {
     let default_position = conn.client_geometry(id)?;
-    let (mut x, mut y, w, h) = default_position.values();
-    let (sx, sy, _, _) = screen_region.values();
-    x = if x < sx { sx } else { x };
-    y = if y < sy { sy } else { y };
+    let (x, y, w, h) = default_position.values();
 
     // Check that the resulting size would not be negative
     // Allow zero-size here as it is chosen by the client
@@ -50,21 +82,218 @@ where
         Region::new(x, y, w, h)
     };
 
+    // Keep the whole window (including its border) on screen, shifting it up / left if it
+    // extends past the right or bottom edges and only shrinking it if it is larger than the
+    // screen itself.
+    let reg = clamp_region_with_border(reg, screen_region, border_px);
+
     Ok(conn.position_client(id, reg, border_px, false)?)
 }
 
+// Move a client back within 'bounds' (including its border) if any part of it lies outside.
+pub(super) fn clamp_client_to_region<X>(
+    conn: &X,
+    id: Xid,
+    bounds: Region,
+    border_px: u32,
+) -> Result<()>
+where
+    X: XClientConfig + XState,
+{
+    let r = conn.client_geometry(id)?;
+    let clamped = clamp_region_with_border(r, bounds, border_px);
+
+    if clamped != r {
+        conn.position_client(id, clamped, border_px, false)?;
+    }
+
+    Ok(())
+}
+
+// Move (and if needed shrink) the region of a client so that it lies within 'bounds' once its
+// border has been included.
+pub(super) fn clamp_region_with_border(r: Region, bounds: Region, border_px: u32) -> Region {
+    let (x, y, w, h) = r.values();
+    let outer = Region::new(x, y, w + 2 * border_px, h + 2 * border_px);
+    let (x, y, w, h) = outer.clamped_within(&bounds).values();
+
+    Region::new(
+        x,
+        y,
+        w.saturating_sub(2 * border_px),
+        h.saturating_sub(2 * border_px),
+    )
+}
+
+// Offset the position and size of 'r' by the given deltas, keeping each dimension no smaller than
+// 'min_px' and clamping the result to lie within 'bounds' (including the border).
+pub(super) fn adjust_floating_region(
+    r: Region,
+    (dx, dy, dw, dh): (i32, i32, i32, i32),
+    min_px: u32,
+    bounds: Region,
+    border_px: u32,
+) -> Region {
+    let shift = |v: u32, d: i32| (v as i64 + d as i64).max(0) as u32;
+    let (x, y, w, h) = r.values();
+    let adjusted = Region::new(
+        shift(x, dx),
+        shift(y, dy),
+        shift(w, dw).max(min_px),
+        shift(h, dh).max(min_px),
+    );
+
+    clamp_region_with_border(adjusted, bounds, border_px)
+}
+
+// Apply the rules from the user's config that match a newly managed client, returning the initial
+// region to position it in if one was specified. Transient clients and floating_window_types
+// always float, then floating_classes are treated as rules that only float the client and are
+// applied before the user's window_rules.
+pub(super) fn apply_window_rules(
+    config: &Config,
+    client: &mut Client,
+    n_workspaces: usize,
+) -> Option<Region> {
+    client.floating = client.transient_for.is_some()
+        || client
+            .wm_type
+            .iter()
+            .any(|t| config.floating_window_types.contains(t));
+
+    if config
+        .floating_classes
+        .iter()
+        .any(|c| WindowMatch::Class(c.clone()).matches(client))
+    {
+        client.floating = true;
+    }
+
+    let matching: Vec<&WindowRule> = config
+        .window_rules
+        .iter()
+        .filter(|r| r.matches.matches(client))
+        .collect();
+
+    let mut region = None;
+    for rule in matching {
+        if let Some(wix) = rule.workspace {
+            if wix < n_workspaces {
+                client.set_workspace(wix);
+            } else {
+                warn!("window rule references unknown workspace {}", wix);
+            }
+        }
+        if rule.floating {
+            client.floating = true;
+        }
+        if rule.region.is_some() {
+            region = rule.region;
+        }
+    }
+
+    region
+}
+
+// The client whose center is closest to the center of 'from' in the given direction. Candidates
+// are scored by their distance along the direction of travel plus twice their offset across it so
+// that clients which are roughly in line are preferred. Returns None if there are no candidates or
+// if the best score is shared by more than one client.
+pub(super) fn neighbour_in_direction(
+    from: Region,
+    candidates: &[(Xid, Region)],
+    direction: RelativePosition,
+) -> Option<Xid> {
+    let Point { x: fx, y: fy } = from.center();
+
+    let mut scored: Vec<(i64, Xid)> = candidates
+        .iter()
+        .filter_map(|&(id, r)| {
+            let Point { x, y } = r.center();
+            let (dx, dy) = (x as i64 - fx as i64, y as i64 - fy as i64);
+            let (along, across) = match direction {
+                RelativePosition::Left => (-dx, dy),
+                RelativePosition::Right => (dx, dy),
+                RelativePosition::Above => (-dy, dx),
+                RelativePosition::Below => (dy, dx),
+            };
+
+            if along > 0 {
+                Some((along + 2 * across.abs(), id))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    scored.sort_unstable();
+    match scored.as_slice() {
+        [(best, id), rest @ ..] if rest.first().map(|(s, _)| s) != Some(best) => Some(*id),
+        _ => None,
+    }
+}
+
+// Whether or not two points are within 'px' pixels of each other along both axes
+pub(super) fn points_within(a: Point, b: Point, px: u32) -> bool {
+    let (dx, dy) = (a.x as i64 - b.x as i64, a.y as i64 - b.y as i64);
+    dx.abs() <= px as i64 && dy.abs() <= px as i64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::__test_helpers::*;
 
+    #[test]
+    fn neighbour_in_direction_ignores_ties() {
+        let from = Region::new(100, 100, 100, 100);
+        let candidates = vec![
+            (1, Region::new(250, 0, 100, 100)),
+            (2, Region::new(250, 200, 100, 100)),
+        ];
+
+        assert_eq!(
+            neighbour_in_direction(from, &candidates, RelativePosition::Right),
+            None
+        );
+        assert_eq!(
+            neighbour_in_direction(from, &candidates[..1], RelativePosition::Right),
+            Some(1)
+        );
+        assert_eq!(
+            neighbour_in_direction(from, &candidates, RelativePosition::Left),
+            None
+        );
+    }
+
+    test_cases! {
+        center_on_screen;
+        args: (w: u32, h: u32, expected: Region);
+
+        case: small => (200, 100, Region::new(500, 370, 200, 100));
+        case: oversized => (2000, 100, Region::new(100, 370, 1000, 100));
+        case: oversized_both => (2000, 2000, Region::new(100, 20, 1000, 800));
+        case: default_size => (0, 0, Region::new(350, 220, 500, 400));
+
+        body: {
+            let screen = Region::new(100, 20, 1000, 800);
+            assert_eq!(center_on_screen(&screen, w, h), expected);
+        }
+    }
+
     #[test]
     fn pad_region_centered() {
         let r = Region::new(0, 0, 200, 100);
         let g = 10;
         let b = 3;
-        assert_eq!(pad_region(&r, false, g, b), Region::new(10, 10, 174, 74));
-        assert_eq!(pad_region(&r, true, g, b), Region::new(0, 0, 194, 94));
+        assert_eq!(
+            pad_region(&r, &r, false, g, g, b),
+            Region::new(10, 10, 174, 74)
+        );
+        assert_eq!(
+            pad_region(&r, &r, true, g, g, b),
+            Region::new(0, 0, 194, 94)
+        );
     }
 
     #[test]
@@ -72,8 +301,24 @@ mod tests {
         let r = Region::new(0, 0, 3, 3);
         let g = 10;
         let b = 3;
-        assert_eq!(pad_region(&r, false, g, b), r);
-        assert_eq!(pad_region(&r, true, g, b), r);
+        assert_eq!(pad_region(&r, &r, false, g, g, b), r);
+        assert_eq!(pad_region(&r, &r, true, g, g, b), r);
+    }
+
+    #[test]
+    fn pad_region_outer_gaps_only_apply_at_screen_edges() {
+        let screen = Region::new(0, 0, 200, 100);
+        let (left, right) = (Region::new(0, 0, 100, 100), Region::new(100, 0, 100, 100));
+        let (g, og) = (5, 20);
+
+        assert_eq!(
+            pad_region(&left, &screen, false, g, og, 0),
+            Region::new(20, 20, 75, 60)
+        );
+        assert_eq!(
+            pad_region(&right, &screen, false, g, og, 0),
+            Region::new(105, 20, 75, 60)
+        );
     }
 
     #[test]
@@ -82,7 +327,7 @@ mod tests {
         conn.position_client(0, Region::new(0, 0, 400, 300), 2, false)
             .unwrap();
 
-        position_floating_client(&conn, 0, Region::default(), 2).unwrap();
+        position_floating_client(&conn, 0, Region::new(0, 0, 1000, 800), 2).unwrap();
 
         assert_eq!(
             conn.client_geometry(0).unwrap(),
@@ -96,8 +341,34 @@ mod tests {
         conn.position_client(0, Region::new(0, 0, 4, 3), 2, false)
             .unwrap();
 
-        position_floating_client(&conn, 0, Region::default(), 2).unwrap();
+        position_floating_client(&conn, 0, Region::new(0, 0, 1000, 800), 2).unwrap();
 
         assert_eq!(conn.client_geometry(0).unwrap(), Region::new(0, 0, 4, 3));
     }
+
+    #[test]
+    fn position_floating_past_the_right_edge() {
+        let conn = TestXConn::new(1, vec![], vec![]);
+        conn.position_client(0, Region::new(900, 700, 400, 300), 2, false)
+            .unwrap();
+
+        position_floating_client(&conn, 0, Region::new(0, 0, 1000, 800), 2).unwrap();
+
+        let (x, y, w, h) = conn.client_geometry(0).unwrap().values();
+        assert_eq!((x, y, w, h), (600, 500, 396, 296));
+        assert_eq!((x + w + 4, y + h + 4), (1000, 800)); // outer frame is on screen
+    }
+
+    #[test]
+    fn position_floating_oversized() {
+        let conn = TestXConn::new(1, vec![], vec![]);
+        conn.position_client(0, Region::new(100, 100, 2000, 1500), 2, false)
+            .unwrap();
+
+        position_floating_client(&conn, 0, Region::new(0, 0, 1000, 800), 2).unwrap();
+
+        let (x, y, w, h) = conn.client_geometry(0).unwrap().values();
+        assert_eq!((x, y, w, h), (0, 0, 996, 796));
+        assert_eq!((x + w + 4, y + h + 4), (1000, 800)); // outer frame is on screen
+    }
 }
@@ -137,6 +137,25 @@ impl Workspaces {
         });
     }
 
+    // Move the given clients from one workspace to the end of another, preserving their order
+    pub fn merge(&mut self, from: usize, into: usize, ids: &[Xid]) -> Result<Vec<EventAction>> {
+        self.get_workspace(into)?;
+        self.get_workspace(from)?;
+        let mut actions = Vec::with_capacity(ids.len());
+
+        for &id in ids {
+            self.remove_client(from, id);
+            if let Some(ws) = self.inner.get_mut(into) {
+                ws.add_client(id, &InsertPoint::Last)?;
+                actions.push(EventAction::RunHook(HookName::ClientAddedToWorkspace(
+                    id, into,
+                )));
+            }
+        }
+
+        Ok(actions)
+    }
+
     pub fn add_workspace(&mut self, ix: usize, ws: Workspace) {
         self.inner.insert(ix, ws);
     }
@@ -185,6 +204,26 @@ impl Workspaces {
         self.inner.focused_index()
     }
 
+    // The index of the next workspace from 'from' in 'direction' that holds at least one client,
+    // wrapping at the ends. None if no other workspace is occupied.
+    pub fn next_occupied(&self, from: usize, direction: Direction) -> Option<usize> {
+        let n = self.inner.len();
+
+        (1..n)
+            .map(|step| match direction {
+                Direction::Forward => (from + step) % n,
+                Direction::Backward => (from + n - step) % n,
+            })
+            .find(|&ix| !self.inner[ix].is_empty())
+    }
+
+    pub fn swap_clients(&mut self, wix: usize, a: Xid, b: Xid) -> bool {
+        self.inner
+            .get_mut(wix)
+            .map(|ws| ws.swap_clients(a, b))
+            .unwrap_or(false)
+    }
+
     pub fn cycle_client(&mut self, wix: usize, direction: Direction) -> Option<(Xid, Xid)> {
         self.inner
             .get_mut(wix)
@@ -197,6 +236,16 @@ impl Workspaces {
         });
     }
 
+    pub fn toggle_master(&mut self, wix: usize) {
+        self.inner.apply_to(&Selector::Index(wix), |ws| {
+            ws.toggle_master();
+        });
+    }
+
+    pub fn promote_focused(&mut self, wix: usize) -> Option<Xid> {
+        self.inner.get_mut(wix).and_then(|ws| ws.promote_focused())
+    }
+
     pub fn rotate_clients(&mut self, wix: usize, direction: Direction) {
         self.inner.apply_to(&Selector::Index(wix), |ws| {
             ws.rotate_clients(direction);
@@ -209,6 +258,12 @@ impl Workspaces {
         });
     }
 
+    pub fn cycle_layout_filtered(&mut self, wix: usize, symbols: &[&str]) {
+        self.inner.apply_to(&Selector::Index(wix), |ws| {
+            ws.cycle_layout_filtered(symbols);
+        });
+    }
+
     pub fn update_max_main(&mut self, wix: usize, change: Change) {
         self.inner.apply_to(&Selector::Index(wix), |ws| {
             ws.update_max_main(change);
@@ -322,4 +377,27 @@ mod tests {
         let res = wss.add_client(0, 0);
         assert!(res.is_err());
     }
+
+    test_cases! {
+        next_occupied;
+        args: (from: usize, direction: Direction, occupied: &[usize], expected: Option<usize>);
+
+        case: forward => (0, Direction::Forward, &[0, 3, 5], Some(3));
+        case: backward => (5, Direction::Backward, &[0, 3, 5], Some(3));
+        case: forward_wraps => (5, Direction::Forward, &[1, 5], Some(1));
+        case: backward_wraps => (1, Direction::Backward, &[1, 5], Some(5));
+        case: current_workspace_is_skipped => (2, Direction::Forward, &[2], None);
+        case: all_empty => (0, Direction::Forward, &[], None);
+
+        body: {
+            let wss = Workspaces::new(
+                (0..9)
+                    .map(|ix| test_workspace(format!("{}", ix), occupied.contains(&ix) as u32))
+                    .collect(),
+                0.1,
+            );
+
+            assert_eq!(wss.next_occupied(from, direction), expected);
+        }
+    }
 }
@@ -1,5 +1,5 @@
 //! Information on connected displays
-use crate::core::data_types::{Point, Region};
+use crate::core::data_types::{Edge, Point, Region, Strut};
 
 /// Display information for a connected screen
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -8,6 +8,7 @@ pub struct Screen {
     pub wix: usize,
     pub(crate) true_region: Region,
     effective_region: Region,
+    show_bar: bool,
 }
 
 impl Screen {
@@ -16,23 +17,49 @@ impl Screen {
         Screen {
             true_region: region,
             effective_region: region,
+            show_bar: true,
             wix,
         }
     }
 
+    /// Whether or not space should be reserved for a status bar on this screen
+    pub fn shows_bar(&self) -> bool {
+        self.show_bar
+    }
+
+    /// Set whether or not space should be reserved for a status bar on this screen
+    pub fn set_show_bar(&mut self, show_bar: bool) {
+        self.show_bar = show_bar;
+    }
+
     /// Cache the current effective region of this screen based on whether or not a bar is
     /// displayed and if that bar is positioned at the top or bottom of the screen.
+    ///
+    /// Space reserved by docks and panels needs to be applied again using
+    /// [reserve_struts][Screen::reserve_struts] after calling this.
     pub fn update_effective_region(&mut self, bar_height: u32, top_bar: bool) {
-        let (x, y, w, h) = self.true_region.values();
-        self.effective_region = if top_bar {
-            Region::new(x, y + bar_height, w, h - bar_height)
-        } else {
-            Region::new(x, y, w, h - bar_height)
+        if !self.show_bar {
+            self.effective_region = self.true_region;
+            return;
+        }
+
+        let edge = if top_bar { Edge::Top } else { Edge::Bottom };
+        let (_, usable) = self.true_region.split_reserving(edge, bar_height);
+        self.effective_region = usable;
+    }
+
+    /// Shrink the cached effective region of this screen so that it does not overlap space
+    /// reserved by docks and panels. `root` is the region covered by all connected screens.
+    ///
+    /// Struts are reserved whether or not this screen shows a bar.
+    pub fn reserve_struts(&mut self, struts: &[Strut], root: Region) {
+        for strut in struts {
+            self.effective_region = strut.reserve_within(self.effective_region, root);
         }
     }
 
     /// The available space for displaying clients on this screen. If 'effective_only' then the
-    /// returned Region will account for space taken up by a bar.
+    /// returned Region will account for space taken up by a bar (if one is shown) and by docks.
     pub fn region(&self, effective_only: bool) -> Region {
         if effective_only {
             self.effective_region
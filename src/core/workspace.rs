@@ -10,7 +10,7 @@
 use crate::{
     core::{
         client::Client,
-        data_types::{Change, Region, ResizeAction},
+        data_types::{Change, LayoutState, Region, ResizeAction, WorkspaceLayoutState},
         layout::{Layout, LayoutConf},
         ring::{Direction, InsertPoint, Ring, Selector},
         xconnection::Xid,
@@ -38,6 +38,7 @@ pub struct Workspace {
     name: String,
     clients: Ring<Xid>,
     layouts: Ring<Layout>,
+    promoted_from: Option<(Xid, usize)>,
 }
 
 impl Workspace {
@@ -53,6 +54,7 @@ impl Workspace {
             name: name.into(),
             clients: Ring::new(Vec::new()),
             layouts: Ring::new(layouts),
+            promoted_from: None,
         }
     }
 
@@ -231,9 +233,35 @@ impl Workspace {
         &self,
         screen_region: Region,
         managed_workspace_clients: &[&Client],
+    ) -> ArrangeActions {
+        self.arrange_using(
+            self.layouts.focused_unchecked(),
+            screen_region,
+            managed_workspace_clients,
+        )
+    }
+
+    // Run the layout function for the layout matching 'symbol' without making it the active
+    // layout for this workspace. Returns None if there is no matching layout.
+    pub(crate) fn preview_arrange(
+        &self,
+        symbol: &str,
+        screen_region: Region,
+        managed_workspace_clients: &[&Client],
+    ) -> Option<(LayoutConf, ArrangeActions)> {
+        let layout = self.layouts.iter().find(|l| l.symbol == symbol)?;
+        let actions = self.arrange_using(layout, screen_region, managed_workspace_clients);
+
+        Some((layout.conf, actions))
+    }
+
+    fn arrange_using(
+        &self,
+        layout: &Layout,
+        screen_region: Region,
+        managed_workspace_clients: &[&Client],
     ) -> ArrangeActions {
         if !self.clients.is_empty() {
-            let layout = self.layouts.focused_unchecked();
             let (floating, tiled): (Vec<&Client>, Vec<&Client>) =
                 managed_workspace_clients.iter().partition(|c| c.floating);
 
@@ -300,6 +328,45 @@ impl Workspace {
         self.layout_symbol()
     }
 
+    /// Cycle forward through the layouts on this workspace whose symbols are in `symbols`,
+    /// skipping all others. If the current layout is not one of them then the first layout in
+    /// `symbols` that this workspace has is used instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use penrose::__test_helpers::*;
+    /// # fn example(mut workspace: Workspace) -> Result<()> {
+    /// assert_eq!(workspace.layout_symbol(), "first");
+    /// assert_eq!(workspace.cycle_layout_filtered(&["second"]), "second");
+    /// assert_eq!(workspace.cycle_layout_filtered(&["second"]), "second");
+    /// # Ok(())
+    /// # }
+    /// # example(test_workspace("example", 2)).unwrap();
+    /// ```
+    pub fn cycle_layout_filtered(&mut self, symbols: &[&str]) -> &str {
+        let n = self.layouts.len();
+        let current = self.layouts.focused_index();
+        let is_wanted = |l: &Layout| symbols.contains(&l.symbol.as_str());
+
+        let target = if is_wanted(self.layouts.focused_unchecked()) {
+            (1..n)
+                .map(|offset| (current + offset) % n)
+                .find(|&i| self.layouts.get(i).map(is_wanted).unwrap_or(false))
+        } else {
+            symbols.iter().find_map(|&s| {
+                self.layouts
+                    .index(&Selector::Condition(&|l: &Layout| l.symbol == s))
+            })
+        };
+
+        if let Some(i) = target {
+            self.layouts.focus(&Selector::Index(i));
+        }
+
+        self.layout_symbol()
+    }
+
     /// The symbol of the currently used layout (passed on creation)
     ///
     /// # Example
@@ -333,6 +400,11 @@ impl Workspace {
         self.layouts.focused_unchecked().conf
     }
 
+    // Whether the active layout moves clients it does not position back within the screen
+    pub(crate) fn bounds_floating(&self) -> bool {
+        self.layouts.focused_unchecked().bound_floating
+    }
+
     /// Cycle focus through the clients on this workspace, returning the previous and new focused
     /// client ids.
     ///
@@ -392,6 +464,129 @@ impl Workspace {
         self.clients.drag_focused(direction).copied()
     }
 
+    /// Promote the focused client to the head of the stack, or if it was promoted by a previous
+    /// call, return it to the position it was promoted from. Focus stays with the moved client.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use penrose::__test_helpers::*;
+    /// # fn example(mut workspace: Workspace) -> Result<()> {
+    /// workspace.focus_client(2);
+    ///
+    /// assert_eq!(workspace.toggle_master(), Some(2));
+    /// assert_eq!(workspace.client_ids(), vec![2, 0, 1, 3]);
+    /// assert_eq!(workspace.focused_client(), Some(2));
+    ///
+    /// assert_eq!(workspace.toggle_master(), Some(2));
+    /// assert_eq!(workspace.client_ids(), vec![0, 1, 2, 3]);
+    /// assert_eq!(workspace.focused_client(), Some(2));
+    /// # Ok(())
+    /// # }
+    /// # example(test_workspace("example", 4)).unwrap();
+    /// ```
+    pub fn toggle_master(&mut self) -> Option<Xid> {
+        let id = *self.clients.focused()?;
+        let ix = self.clients.focused_index();
+
+        match self.promoted_from.take() {
+            Some((prev, orig)) if prev == id && ix == 0 => {
+                self.clients.remove(&Selector::Index(0));
+                let orig = orig.min(self.clients.len());
+                self.clients.insert(orig, id);
+                self.clients.focus(&Selector::Index(orig));
+            }
+
+            _ if ix == 0 => return None,
+
+            _ => {
+                self.clients.remove(&Selector::Index(ix));
+                self.clients.insert(0, id);
+                self.clients.focus(&Selector::Index(0));
+                self.promoted_from = Some((id, ix));
+            }
+        }
+
+        Some(id)
+    }
+
+    /// Move the focused client into the master position at the head of the stack, or if it is
+    /// already there, swap it with the first client in the rest of the stack. Focus moves to the
+    /// client that is now the master, which is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use penrose::__test_helpers::*;
+    /// # fn example(mut workspace: Workspace) -> Result<()> {
+    /// workspace.focus_client(2);
+    ///
+    /// assert_eq!(workspace.promote_focused(), Some(2));
+    /// assert_eq!(workspace.client_ids(), vec![2, 0, 1, 3]);
+    /// assert_eq!(workspace.focused_client(), Some(2));
+    ///
+    /// assert_eq!(workspace.promote_focused(), Some(0));
+    /// assert_eq!(workspace.client_ids(), vec![0, 2, 1, 3]);
+    /// assert_eq!(workspace.focused_client(), Some(0));
+    /// # Ok(())
+    /// # }
+    /// # example(test_workspace("example", 4)).unwrap();
+    /// ```
+    pub fn promote_focused(&mut self) -> Option<Xid> {
+        let ix = match self.clients.focused_index() {
+            0 if self.clients.len() > 1 => 1,
+            0 => return None,
+            ix => ix,
+        };
+
+        let id = self.clients.remove(&Selector::Index(ix))?;
+        self.clients.insert(0, id);
+        self.clients.focus(&Selector::Index(0));
+        self.promoted_from = None;
+
+        Some(id)
+    }
+
+    /// Swap the positions of two clients in the stack. Focus stays with the focused client.
+    ///
+    /// Returns false without modifying the stack if either client is not on this workspace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use penrose::__test_helpers::*;
+    /// # fn example(mut workspace: Workspace) -> Result<()> {
+    /// assert!(workspace.swap_clients(0, 2));
+    /// assert_eq!(workspace.client_ids(), vec![2, 1, 0, 3]);
+    /// assert_eq!(workspace.focused_client(), Some(0));
+    ///
+    /// assert!(!workspace.swap_clients(0, 42));
+    /// assert_eq!(workspace.client_ids(), vec![2, 1, 0, 3]);
+    /// # Ok(())
+    /// # }
+    /// # example(test_workspace("example", 4)).unwrap();
+    /// ```
+    pub fn swap_clients(&mut self, a: Xid, b: Xid) -> bool {
+        let index_of = |id: Xid| self.clients.index(&Selector::Condition(&|c| *c == id));
+        let (ia, ib) = match (index_of(a), index_of(b)) {
+            (Some(ia), Some(ib)) => (ia, ib),
+            _ => return false,
+        };
+
+        let focused = self.clients.focused().copied();
+        for (ix, id) in [(ia, b), (ib, a)] {
+            if let Some(c) = self.clients.get_mut(ix) {
+                *c = id;
+            }
+        }
+        if let Some(id) = focused {
+            self.clients.focus(&Selector::Condition(&|c| *c == id));
+        }
+        self.promoted_from = None;
+
+        true
+    }
+
     /// Rotate the client stack in the given direction
     ///
     /// # Example
@@ -430,6 +625,39 @@ impl Workspace {
             layout.update_main_ratio(change, step);
         }
     }
+
+    /// The current layout settings of this workspace so that they can be restored later using
+    /// [restore_layout_state][Workspace::restore_layout_state].
+    pub fn layout_state(&self) -> WorkspaceLayoutState {
+        WorkspaceLayoutState {
+            name: self.name.clone(),
+            active: self.layout_symbol().to_string(),
+            layouts: self
+                .layouts
+                .iter()
+                .map(|l| LayoutState {
+                    symbol: l.symbol.clone(),
+                    max_main: l.max_main(),
+                    ratio: l.ratio(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Restore layout settings previously generated by
+    /// [layout_state][Workspace::layout_state].
+    ///
+    /// Layouts are matched by symbol: any that are not available on this workspace are ignored.
+    /// The name of the saved state is not checked.
+    pub fn restore_layout_state(&mut self, state: &WorkspaceLayoutState) {
+        for saved in state.layouts.iter() {
+            if let Some(l) = self.layouts.iter_mut().find(|l| l.symbol == saved.symbol) {
+                l.restore(saved.max_main, saved.ratio);
+            }
+        }
+
+        self.try_set_layout(&state.active);
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +43,9 @@ pub enum Atom {
     /// WM_STATE
     #[strum(serialize = "WM_STATE")]
     WmState,
+    /// WM_CHANGE_STATE
+    #[strum(serialize = "WM_CHANGE_STATE")]
+    WmChangeState,
     /// WM_NAME
     #[strum(serialize = "WM_NAME")]
     WmName,
@@ -100,9 +103,21 @@ pub enum Atom {
     /// _NET_WM_STATE_FULLSCREEN
     #[strum(serialize = "_NET_WM_STATE_FULLSCREEN")]
     NetWmStateFullscreen,
+    /// _NET_WM_STRUT
+    #[strum(serialize = "_NET_WM_STRUT")]
+    NetWmStrut,
+    /// _NET_WM_STRUT_PARTIAL
+    #[strum(serialize = "_NET_WM_STRUT_PARTIAL")]
+    NetWmStrutPartial,
+    /// _NET_WM_WINDOW_OPACITY
+    #[strum(serialize = "_NET_WM_WINDOW_OPACITY")]
+    NetWmWindowOpacity,
     /// _NET_WM_WINDOW_TYPE
     #[strum(serialize = "_NET_WM_WINDOW_TYPE")]
     NetWmWindowType,
+    /// _MOTIF_WM_HINTS
+    #[strum(serialize = "_MOTIF_WM_HINTS")]
+    MotifWmHints,
     /// _XEMBED
     #[strum(serialize = "_XEMBED")]
     XEmbed,
@@ -176,6 +191,9 @@ pub const UNMANAGED_WINDOW_TYPES: &[Atom] = &[
 ];
 
 /// Currently supported EWMH atoms
+///
+/// These are published to the root window in _NET_SUPPORTED on startup so this list should be
+/// kept in sync with the EWMH hints that penrose actually implements.
 pub const EWMH_SUPPORTED_ATOMS: &[Atom] = &[
     Atom::NetActiveWindow,
     Atom::NetClientList,
@@ -188,8 +206,23 @@ pub const EWMH_SUPPORTED_ATOMS: &[Atom] = &[
     // Atom::NetSystemTrayS0,
     // Atom::NetSystemTrayOpcode,
     // Atom::NetSystemTrayOrientationHorz,
+    Atom::NetWindowTypeCombo,
+    Atom::NetWindowTypeDesktop,
+    Atom::NetWindowTypeDialog,
+    Atom::NetWindowTypeDock,
+    Atom::NetWindowTypeDropdownMenu,
+    Atom::NetWindowTypeMenu,
+    Atom::NetWindowTypeNormal,
+    Atom::NetWindowTypeNotification,
+    Atom::NetWindowTypePopupMenu,
+    Atom::NetWindowTypeSplash,
+    Atom::NetWindowTypeToolbar,
+    Atom::NetWindowTypeUtility,
+    Atom::NetWmDesktop,
     Atom::NetWmName,
     Atom::NetWmState,
     Atom::NetWmStateFullscreen,
+    Atom::NetWmStrut,
+    Atom::NetWmStrutPartial,
     Atom::NetWmWindowType,
 ];
@@ -30,6 +30,8 @@ pub enum XEvent {
     Destroy(Xid),
     /// A grabbed key combination has been entered by the user
     KeyPress(KeyCode),
+    /// A grabbed key has been released by the user
+    KeyRelease(KeyCode),
     /// The mouse pointer has left the current client window
     Leave(PointerChange),
     /// A client window is requesting to be positioned and rendered on the screen.
@@ -57,6 +59,7 @@ impl std::fmt::Display for XEvent {
             XEvent::FocusIn(_) => write!(f, "FocusIn"),
             XEvent::Destroy(_) => write!(f, "Destroy"),
             XEvent::KeyPress(_) => write!(f, "KeyPress"),
+            XEvent::KeyRelease(_) => write!(f, "KeyRelease"),
             XEvent::Leave(_) => write!(f, "Leave"),
             XEvent::MapRequest(_, _) => write!(f, "MapRequest"),
             XEvent::MouseEvent(_) => write!(f, "MouseEvent"),
@@ -9,7 +9,7 @@
 //! [1]: crate::core::manager::WindowManager
 use crate::{
     core::{
-        bindings::{KeyBindings, KeyPress, MouseBindings},
+        bindings::{KeyBindings, KeyCode, KeyPress, MouseBindings},
         client::Client,
         data_types::{Point, Region},
         screen::Screen,
@@ -18,6 +18,7 @@ use crate::{
 };
 
 use penrose_proc::stubbed_companion_trait;
+use std::time::Duration;
 
 pub mod atom;
 pub mod event;
@@ -31,8 +32,8 @@ pub use event::{
     ExposeEvent, PointerChange, PropertyEvent, XEvent,
 };
 pub use property::{
-    MapState, Prop, WindowAttributes, WindowClass, WindowState, WmHints, WmNormalHints,
-    WmNormalHintsFlags,
+    MapState, Prop, WindowAttributes, WindowClass, WindowState, WmHints, WmHintsFlags,
+    WmNormalHints, WmNormalHintsFlags,
 };
 
 /// An X resource ID
@@ -104,6 +105,8 @@ pub enum ClientConfig {
     Position(Region),
     /// Mark this window as stacking on top of its peers
     StackAbove,
+    /// Mark this window as stacking below its peers
+    StackBelow,
 }
 
 /// Attributes for an X11 client window (not all are curently implemented)
@@ -182,6 +185,17 @@ pub trait XEventHandler {
     #[stub(Err(XError::Raw("mocked".into())))]
     fn wait_for_event(&self) -> Result<XEvent>;
 
+    /// Wait up to `timeout` for the next event from the X server, returning `None` if no event
+    /// arrived in time.
+    ///
+    /// Implementations that are unable to time out should block until the next event as
+    /// [wait_for_event][XEventHandler::wait_for_event] does.
+    #[allow(unused_variables)]
+    fn wait_for_event_timeout(&self, timeout: Duration) -> Result<Option<XEvent>> {
+        let event = self.wait_for_event()?;
+        Ok(Some(event))
+    }
+
     /// Send an X event to the target client
     ///
     /// The `msg` being sent can be composed by hand or, for known common message types, generated
@@ -385,10 +399,36 @@ pub trait XClientConfig {
         self.configure_client(id, &[ClientConfig::StackAbove])
     }
 
+    /// Lower the window to the bottom of the stack so it renders below peers
+    fn lower_client(&self, id: Xid) -> Result<()> {
+        self.configure_client(id, &[ClientConfig::StackBelow])
+    }
+
     /// Change the border color for the given client
     fn set_client_border_color(&self, id: Xid, color: Color) -> Result<()> {
         self.set_client_attributes(id, &[ClientAttr::BorderColor(color.rgb_u32())])
     }
+
+    /// Draw the border for the given client using a pixmap of `w` x `h` pixels, filling each
+    /// [Region] of the pixmap with the paired [Color] (see [BorderStyle::border_fills]).
+    ///
+    /// Implementations that are unable to draw pixmaps should fall back to setting a solid
+    /// border using the first of the given colors.
+    ///
+    /// [BorderStyle::border_fills]: crate::draw::BorderStyle::border_fills
+    #[allow(unused_variables)]
+    fn set_client_border_pixmap(
+        &self,
+        id: Xid,
+        w: u32,
+        h: u32,
+        fills: &[(Region, Color)],
+    ) -> Result<()> {
+        match fills.first() {
+            Some(&(_, color)) => self.set_client_border_color(id, color),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Keyboard input for created clients
@@ -460,6 +500,37 @@ pub trait XConn:
         mouse_bindings: &MouseBindings<Self>,
     ) -> Result<()>;
 
+    /// Notify the X server that we are intercepting presses of the given bare modifier keys so
+    /// that both the press and release events are sent through to the WindowManager.
+    ///
+    /// This is used to support [ModifierTapBindings][1].
+    ///
+    /// [1]: crate::core::bindings::ModifierTapBindings
+    ///
+    /// The default implementation does nothing: modifier taps will never fire.
+    fn grab_modifier_taps(&self, _codes: &[KeyCode]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Grab the X server so that no requests from other X clients are processed until
+    /// [ungrab_server][XConn::ungrab_server] is called.
+    ///
+    /// This is used to batch up a series of requests (such as repositioning all clients when
+    /// applying a layout) so that they are applied atomically.
+    ///
+    /// The default implementation does nothing.
+    fn grab_server(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Release a grab made using [grab_server][XConn::grab_server] and flush any pending
+    /// requests to the X server.
+    ///
+    /// The default implementation does nothing.
+    fn ungrab_server(&self) -> Result<()> {
+        Ok(())
+    }
+
     /*
      *  The following default implementations should used if possible.
      *
@@ -483,11 +554,9 @@ pub trait XConn:
                 Prop::Window(vec![check_win]),
             )?;
 
-            self.change_prop(
-                win,
-                Atom::WmName.as_ref(),
-                Prop::UTF8String(vec![WM_NAME.into()]),
-            )?;
+            for name in &[Atom::WmName, Atom::NetWmName] {
+                self.change_prop(win, name.as_ref(), Prop::UTF8String(vec![WM_NAME.into()]))?;
+            }
         }
 
         // EWMH support
@@ -521,16 +590,21 @@ pub trait XConn:
         )
     }
 
-    /// Update the root window properties with the current client details
+    /// Update the root window properties with the current client details: `clients` should be
+    /// in the order that they were first managed.
     fn update_known_clients(&self, clients: &[Xid]) -> Result<()> {
-        let root = self.root();
         self.change_prop(
-            root,
+            self.root(),
             Atom::NetClientList.as_ref(),
             Prop::Window(clients.to_vec()),
-        )?;
+        )
+    }
+
+    /// Update the root window properties with the current stacking order of clients, from bottom
+    /// to top.
+    fn update_client_stacking(&self, clients: &[Xid]) -> Result<()> {
         self.change_prop(
-            root,
+            self.root(),
             Atom::NetClientListStacking.as_ref(),
             Prop::Window(clients.to_vec()),
         )
@@ -554,7 +628,7 @@ pub trait XConn:
         )
     }
 
-    /// Update which desktop a client is currently on
+    /// Update which desktop a client is currently on by setting its _NET_WM_DESKTOP property
     fn set_client_workspace(&self, id: Xid, wix: usize) -> Result<()> {
         self.change_prop(id, Atom::NetWmDesktop.as_ref(), Prop::Cardinal(wix as u32))
     }
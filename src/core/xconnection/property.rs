@@ -202,7 +202,7 @@ impl WmHints {
             initial_state,
             icon_pixmap: raw[3],
             icon_win: raw[4],
-            icon_position: Point::new(raw[5], raw[6]),
+            icon_position: Point::new(raw[5] as i32, raw[6] as i32),
             icon_mask: raw[7],
             window_group: raw[8],
         })
@@ -212,13 +212,12 @@ impl WmHints {
 /// Client requested hints about window geometry.
 ///
 /// See the ICCCM [spec][1] for further details or the [Xlib manual][2] for more details of the
-/// data fromat but note that Penrose does not honour the following hints:
-///   - gravity
-///   - increment
-///   - aspect ratio
+/// data fromat but note that Penrose does not honour the gravity hint. Resize increments and
+/// aspect ratios are only honoured for tiled clients when [respect_size_hints][3] is set.
 ///
 /// [1]: https://www.x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_normal_hints_property
 /// [2]: https://tronche.com/gui/x/xlib/ICC/client-to-window-manager/wm-normal-hints.html
+/// [3]: crate::core::config::Config::respect_size_hints
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct WmNormalHints {
     pub(crate) flags: WmNormalHintsFlags,
@@ -226,6 +225,8 @@ pub struct WmNormalHints {
     pub(crate) min: Option<Region>,
     pub(crate) max: Option<Region>,
     pub(crate) user_specified: Option<Region>,
+    pub(crate) inc: Option<(u32, u32)>,
+    pub(crate) aspect: Option<((u32, u32), (u32, u32))>,
 }
 
 impl WmNormalHints {
@@ -243,9 +244,122 @@ impl WmNormalHints {
             min,
             max,
             user_specified,
+            inc: None,
+            aspect: None,
         }
     }
 
+    /// Set the width and height increments that the client should be resized in.
+    pub fn with_resize_increments(mut self, w: u32, h: u32) -> Self {
+        self.inc = if w > 0 && h > 0 { Some((w, h)) } else { None };
+        self
+    }
+
+    /// Set the minimum and maximum aspect ratios (as `(numerator, denominator)` pairs of width
+    /// over height) that the client should be kept within.
+    pub fn with_aspect_ratios(mut self, min: (u32, u32), max: (u32, u32)) -> Self {
+        let valid = [min.0, min.1, max.0, max.1].iter().all(|&n| n > 0);
+        self.aspect = if valid { Some((min, max)) } else { None };
+        self
+    }
+
+    /// Snap the size of `r` to the closest size no larger than `r` that satisfies these hints,
+    /// centering the result within `r`.
+    ///
+    /// Aspect ratios and resize increments are applied relative to the base size of the client
+    /// before clamping to the minimum and maximum sizes. The minimum size is always respected,
+    /// even if that means that the result no longer fits within `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::{
+    ///     data_types::Region,
+    ///     xconnection::{WmNormalHints, WmNormalHintsFlags},
+    /// };
+    ///
+    /// let base = Region::new(0, 0, 4, 2);
+    /// let hints = WmNormalHints::new(WmNormalHintsFlags::empty(), Some(base), None, None, None)
+    ///     .with_resize_increments(10, 20);
+    ///
+    /// let snapped = hints.constrain(Region::new(0, 0, 500, 300));
+    ///
+    /// assert_eq!(snapped, Region::new(3, 9, 494, 282));
+    /// ```
+    pub fn constrain(&self, r: Region) -> Region {
+        let (x, y, mut w, mut h) = r.values();
+        let size = |reg: Option<Region>| reg.map(|r| (r.w, r.h));
+
+        // ICCCM: the base and min sizes are used as fallbacks for one another
+        let (base_w, base_h) = size(self.base.or(self.min)).unwrap_or((0, 0));
+        let (min_w, min_h) = size(self.min.or(self.base)).unwrap_or((1, 1));
+
+        if let Some(((min_n, min_d), (max_n, max_d))) = self.aspect {
+            let aw = w.saturating_sub(base_w) as f64;
+            let ah = h.saturating_sub(base_h) as f64;
+            let (min_aspect, max_aspect) =
+                (min_n as f64 / min_d as f64, max_n as f64 / max_d as f64);
+
+            if ah > 0.0 && aw / ah > max_aspect {
+                w = base_w + (ah * max_aspect).round() as u32;
+            } else if ah > 0.0 && aw / ah < min_aspect {
+                h = base_h + (aw / min_aspect).round() as u32;
+            }
+        }
+
+        // Only the snapped size is used: the result is centered within r below
+        let (_, _, mut w, mut h) = self.snap_to_increments(Region::new(x, y, w, h)).values();
+
+        w = w.max(min_w);
+        h = h.max(min_h);
+        if let Some((max_w, max_h)) = size(self.max) {
+            w = w.min(max_w.max(min_w));
+            h = h.min(max_h.max(min_h));
+        }
+
+        Region::new(
+            x + r.w.saturating_sub(w) / 2,
+            y + r.h.saturating_sub(h) / 2,
+            w,
+            h,
+        )
+    }
+
+    /// Snap the size of `r` down to the closest size that satisfies the resize increments of
+    /// these hints (relative to the base size of the client), centering the result within `r`.
+    ///
+    /// Unlike [constrain][WmNormalHints::constrain], no other hints are taken into account so the
+    /// result always fits within `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::{
+    ///     data_types::Region,
+    ///     xconnection::{WmNormalHints, WmNormalHintsFlags},
+    /// };
+    ///
+    /// let hints = WmNormalHints::new(WmNormalHintsFlags::empty(), None, None, None, None)
+    ///     .with_resize_increments(10, 10);
+    ///
+    /// let snapped = hints.snap_to_increments(Region::new(0, 0, 105, 57));
+    ///
+    /// assert_eq!(snapped, Region::new(2, 3, 100, 50));
+    /// ```
+    pub fn snap_to_increments(&self, r: Region) -> Region {
+        let (inc_w, inc_h) = match self.inc {
+            Some(inc) => inc,
+            None => return r,
+        };
+
+        let (x, y, w, h) = r.values();
+        let (base_w, base_h) = self.base.or(self.min).map(|b| (b.w, b.h)).unwrap_or((0, 0));
+        let w = w - w.saturating_sub(base_w) % inc_w;
+        let h = h - h.saturating_sub(base_h) % inc_h;
+
+        Region::new(x + (r.w - w) / 2, y + (r.h - h) / 2, w, h)
+    }
+
     /// Try to construct a [WmNormalHints] instance from raw bytes.
     ///
     /// This method expects a slice of 18 u32s corresponding to the C struct layout shown below.
@@ -284,10 +398,11 @@ impl WmNormalHints {
 
         let (min_w, min_h) = (raw[5], raw[6]);
         let (max_w, max_h) = (raw[7], raw[8]);
+        let (inc_w, inc_h) = (raw[9], raw[10]);
+        let (min_aspect, max_aspect) = ((raw[11], raw[12]), (raw[13], raw[14]));
         let (base_w, base_h) = (raw[15], raw[16]);
 
-        // ignoring increment, aspect ratio, gravity as they are not used in
-        // the main WindowManager logic
+        // ignoring gravity as it is not used in the main WindowManager logic
 
         let if_set = |x, y, w, h| {
             if w > 0 && h > 0 {
@@ -297,13 +412,22 @@ impl WmNormalHints {
             }
         };
 
-        Ok(Self {
+        let mut hints = Self::new(
             flags,
-            base: if_set(x, y, base_w, base_h),
-            min: if_set(x, y, min_w, min_h),
-            max: if_set(x, y, max_w, max_h),
-            user_specified: if_set(x, y, user_w, user_h),
-        })
+            if_set(x, y, base_w, base_h),
+            if_set(x, y, min_w, min_h),
+            if_set(x, y, max_w, max_h),
+            if_set(x, y, user_w, user_h),
+        );
+
+        if flags.contains(WmNormalHintsFlags::P_RESIZE_INC) {
+            hints = hints.with_resize_increments(inc_w, inc_h);
+        }
+        if flags.contains(WmNormalHintsFlags::P_ASPECT) {
+            hints = hints.with_aspect_ratios(min_aspect, max_aspect);
+        }
+
+        Ok(hints)
     }
 }
 
@@ -352,3 +476,89 @@ impl WindowAttributes {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(min: Option<(u32, u32)>, max: Option<(u32, u32)>) -> WmNormalHints {
+        let r = |(w, h)| Region::new(0, 0, w, h);
+        WmNormalHints::new(
+            WmNormalHintsFlags::empty(),
+            None,
+            min.map(r),
+            max.map(r),
+            None,
+        )
+    }
+
+    test_cases! {
+        constrain_size_hints;
+        args: (h: WmNormalHints, expected: Region);
+
+        case: no_hints => (hints(None, None), Region::new(0, 0, 100, 50));
+        case: increments => (
+            hints(None, None).with_resize_increments(30, 20),
+            Region::new(5, 5, 90, 40)
+        );
+        case: min_larger_than_region => (hints(Some((150, 60)), None), Region::new(0, 0, 150, 60));
+        case: max_smaller_than_region => (hints(None, Some((60, 30))), Region::new(20, 10, 60, 30));
+        case: aspect_too_wide => (
+            hints(None, None).with_aspect_ratios((1, 1), (1, 1)),
+            Region::new(25, 0, 50, 50)
+        );
+        case: aspect_too_tall => (
+            hints(None, None).with_aspect_ratios((4, 1), (4, 1)),
+            Region::new(0, 12, 100, 25)
+        );
+
+        body: {
+            assert_eq!(h.constrain(Region::new(0, 0, 100, 50)), expected);
+        }
+    }
+
+    test_cases! {
+        snap_to_increments;
+        args: (h: WmNormalHints, expected: Region);
+
+        case: no_increments => (hints(None, None), Region::new(0, 0, 105, 57));
+        case: increments => (
+            hints(None, None).with_resize_increments(10, 10),
+            Region::new(2, 3, 100, 50)
+        );
+        case: increments_relative_to_base => (
+            WmNormalHints::new(
+                WmNormalHintsFlags::empty(),
+                Some(Region::new(0, 0, 4, 2)),
+                None,
+                None,
+                None,
+            )
+            .with_resize_increments(10, 10),
+            Region::new(0, 2, 104, 52)
+        );
+        case: aspect_is_ignored => (
+            hints(None, None)
+                .with_resize_increments(10, 10)
+                .with_aspect_ratios((1, 1), (1, 1)),
+            Region::new(2, 3, 100, 50)
+        );
+
+        body: {
+            assert_eq!(h.snap_to_increments(Region::new(0, 0, 105, 57)), expected);
+        }
+    }
+
+    #[test]
+    fn increments_and_aspect_are_parsed_when_flagged() {
+        let flags = WmNormalHintsFlags::P_RESIZE_INC | WmNormalHintsFlags::P_ASPECT;
+        let mut raw = [0; 18];
+        raw[0] = flags.bits();
+        raw[9..15].copy_from_slice(&[7, 13, 1, 2, 3, 1]);
+
+        let h = WmNormalHints::try_from_bytes(&raw).unwrap();
+
+        assert_eq!(h.inc, Some((7, 13)));
+        assert_eq!(h.aspect, Some(((1, 2), (3, 1))));
+    }
+}
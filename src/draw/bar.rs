@@ -48,6 +48,7 @@
 //! ```
 use crate::{
     core::{
+        config::ScreenBar,
         data_types::{Region, WinType},
         hooks::Hook,
         manager::WindowManager,
@@ -131,9 +132,11 @@ where
     position: Position,
     /// The widgets contained within this status bar
     pub widgets: Vec<Box<dyn HookableWidget<X>>>,
-    screens: Vec<(Xid, f64)>, // window and width
+    screens: Vec<(usize, Xid, f64)>, // screen index, window and width
+    screen_bars: Vec<ScreenBar>,
     hpx: usize,
     h: f64,
+    margin: usize,
     bg: Color,
     active_screen: usize,
 }
@@ -150,7 +153,9 @@ where
             .field("position", &self.position)
             .field("widgets", &stringify!(self.widgets))
             .field("screens", &self.screens)
+            .field("screen_bars", &self.screen_bars)
             .field("hpx", &self.hpx)
+            .field("margin", &self.margin)
             .field("bg", &self.bg)
             .field("active_screen", &self.active_screen)
             .finish()
@@ -177,8 +182,10 @@ where
             position,
             widgets,
             screens: vec![],
+            screen_bars: vec![],
             hpx: h,
             h: h as f64,
+            margin: 0,
             bg: bg.into(),
             active_screen: 0,
         };
@@ -188,19 +195,51 @@ where
         Ok(bar)
     }
 
+    /// Inset this bar from the edges of each screen by `px` pixels, recreating its windows.
+    ///
+    /// This should match [bar_margin_px][crate::core::config::Config::bar_margin_px] in the
+    /// [Config][crate::core::config::Config] used by the
+    /// [WindowManager][crate::core::manager::WindowManager] so that the space reserved for the
+    /// bar is correct.
+    pub fn set_margin(&mut self, px: usize) -> Result<()> {
+        self.margin = px;
+        self.destroy_windows()?;
+        self.init_for_screens()
+    }
+
+    /// Override whether or not this bar is shown (and its position) on individual screens,
+    /// recreating its windows. Screens without a [ScreenBar] rule use the position this bar was
+    /// created with.
+    ///
+    /// This should match [screen_bars][crate::core::config::Config::screen_bars] in the
+    /// [Config][crate::core::config::Config] used by the
+    /// [WindowManager][crate::core::manager::WindowManager] so that the bar is only drawn where
+    /// space has been reserved for it.
+    pub fn set_screen_bars(&mut self, screen_bars: Vec<ScreenBar>) -> Result<()> {
+        self.screen_bars = screen_bars;
+        self.destroy_windows()?;
+        self.init_for_screens()
+    }
+
+    fn destroy_windows(&mut self) -> Result<()> {
+        for (_, id, _) in self.screens.iter() {
+            self.drw.destroy_client(*id)?;
+        }
+
+        Ok(())
+    }
+
     fn init_for_screens(&mut self) -> Result<()> {
         let screen_sizes = self.drw.screen_sizes()?;
         self.screens = screen_sizes
             .iter()
-            .map(|r| {
-                let (sx, sy, sw, sh) = r.values();
-                let y = match self.position {
-                    Position::Top => sy as usize,
-                    Position::Bottom => sh as usize - self.hpx,
-                };
+            .enumerate()
+            .flat_map(|(i, r)| bar_position(&self.screen_bars, self.position, i).map(|p| (i, r, p)))
+            .map(|(i, r, position)| {
+                let region = bar_region(*r, position, self.hpx, self.margin);
                 let id = self.drw.new_window(
                     WinType::InputOutput(Atom::NetWindowTypeDock),
-                    Region::new(sx, y as u32, sw, self.hpx as u32),
+                    region,
                     false,
                 )?;
 
@@ -210,16 +249,16 @@ where
                 }
 
                 self.drw.flush(id)?;
-                Ok((id, sw as f64))
+                Ok((i, id, region.w as f64))
             })
-            .collect::<Result<Vec<(u32, f64)>>>()?;
+            .collect::<Result<Vec<(usize, u32, f64)>>>()?;
 
         Ok(())
     }
 
     /// Re-render all widgets in this status bar
     pub fn redraw(&mut self) -> Result<()> {
-        for (i, &(id, w)) in self.screens.clone().iter().enumerate() {
+        for &(i, id, w) in self.screens.clone().iter() {
             let screen_has_focus = self.active_screen == i;
             let mut ctx = self.drw.context_for(id)?;
 
@@ -272,7 +311,7 @@ where
     fn redraw_if_needed(&mut self) -> Result<()> {
         if self.widgets.iter().any(|w| w.require_draw()) {
             self.redraw()?;
-            for (id, _) in self.screens.iter() {
+            for (_, id, _) in self.screens.iter() {
                 self.drw.flush(*id)?;
             }
         }
@@ -281,6 +320,28 @@ where
     }
 }
 
+// The position of a bar on the screen with the given index, or None if it is not shown there
+fn bar_position(screen_bars: &[ScreenBar], default: Position, ix: usize) -> Option<Position> {
+    match screen_bars.iter().find(|b| b.screen == ix) {
+        Some(b) if !b.show_bar => None,
+        Some(b) if b.top_bar => Some(Position::Top),
+        Some(_) => Some(Position::Bottom),
+        None => Some(default),
+    }
+}
+
+// The region occupied by a bar window on a screen, inset from the edges of the screen by margin
+fn bar_region(screen: Region, position: Position, h: usize, margin: usize) -> Region {
+    let (sx, sy, sw, sh) = screen.values();
+    let (h, m) = (h as u32, margin as u32);
+    let y = match position {
+        Position::Top => sy + m,
+        Position::Bottom => sy + sh - h - m,
+    };
+
+    Region::new(sx + m, y, sw.saturating_sub(2 * m), h)
+}
+
 macro_rules! __impl_status_bar_as_hook {
     {
         $($name:ident => $($a:ident: $t:ty),*;)+
@@ -306,9 +367,7 @@ macro_rules! __impl_status_bar_as_hook {
 
 
             fn screens_updated(&mut self, wm: &mut WindowManager<X>, dimensions: &[Region]) -> crate::Result<()> {
-                for (id, _) in self.screens.iter() {
-                    self.drw.destroy_client(*id)?;
-                }
+                self.destroy_windows()?;
 
                 if let Err(e) = self.init_for_screens() {
                     error!("error removing old status bar windows: {}", e)
@@ -336,13 +395,154 @@ macro_rules! __impl_status_bar_as_hook {
 
 __impl_status_bar_as_hook! {
     client_name_updated => id: Xid, name: &str, is_root: bool;
+    client_title_changed => id: Xid, title: &str;
     client_added_to_workspace => id: Xid, wix: usize;
     focus_change => id: Xid;
+    client_focus_changed => prev: Option<Xid>, new: Option<Xid>;
     layout_applied => workspace_index: usize, screen_index: usize;
     layout_change => workspace_index: usize, screen_index: usize;
     new_client => id: Xid;
     randr_notify => ;
     remove_client => id: Xid;
     workspace_change => prev: usize, new: usize;
+    screen_workspace_change => prev: usize, new: usize, screen_index: usize;
     workspaces_updated => names: &[&str], active: usize;
+    state_changed => ;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::xconnection::{
+        MockXConn, Result as XResult, StubXClientHandler, StubXClientProperties,
+    };
+    use std::cell::RefCell;
+
+    struct BarDraw {
+        screens: Vec<Region>,
+        windows: RefCell<Vec<Region>>,
+    }
+
+    struct NoopContext;
+
+    impl DrawContext for NoopContext {
+        fn font(&mut self, _: &str, _: i32) -> Result<()> {
+            Ok(())
+        }
+        fn color(&mut self, _: &Color) {}
+        fn clear(&mut self) -> Result<()> {
+            Ok(())
+        }
+        fn translate(&self, _: f64, _: f64) {}
+        fn set_x_offset(&self, _: f64) {}
+        fn set_y_offset(&self, _: f64) {}
+        fn rectangle(&self, _: f64, _: f64, _: f64, _: f64) -> Result<()> {
+            Ok(())
+        }
+        fn text(&self, _: &str, _: f64, _: (f64, f64)) -> Result<(f64, f64)> {
+            Ok((0.0, 0.0))
+        }
+        fn text_extent(&self, _: &str) -> Result<(f64, f64)> {
+            Ok((0.0, 0.0))
+        }
+        fn flush(&self) {}
+    }
+
+    impl StubXClientProperties for BarDraw {
+        fn mock_change_prop(&self, _: Xid, _: &str, _: Prop) -> XResult<()> {
+            Ok(())
+        }
+    }
+
+    impl StubXClientHandler for BarDraw {
+        fn mock_destroy_client(&self, _: Xid) -> XResult<()> {
+            self.windows.borrow_mut().clear();
+            Ok(())
+        }
+    }
+
+    impl Draw for BarDraw {
+        type Ctx = NoopContext;
+
+        fn new_window(&mut self, _: WinType, r: Region, _: bool) -> Result<Xid> {
+            self.windows.borrow_mut().push(r);
+            Ok(self.windows.borrow().len() as Xid)
+        }
+        fn screen_sizes(&self) -> Result<Vec<Region>> {
+            Ok(self.screens.clone())
+        }
+        fn register_font(&mut self, _: &str) {}
+        fn context_for(&self, _: Xid) -> Result<Self::Ctx> {
+            Ok(NoopContext)
+        }
+        fn temp_context(&self, _: u32, _: u32) -> Result<Self::Ctx> {
+            Ok(NoopContext)
+        }
+        fn flush(&self, _: Xid) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    test_cases! {
+        screen_bars;
+        args: (screen_bars: Vec<ScreenBar>, expected: Vec<Region>);
+
+        case: all_screens_by_default => (
+            vec![],
+            vec![Region::new(0, 0, 800, 18), Region::new(800, 0, 800, 18)]
+        );
+        case: hidden => (
+            vec![ScreenBar::new(1, false, true)],
+            vec![Region::new(0, 0, 800, 18)]
+        );
+        case: bottom => (
+            vec![ScreenBar::new(0, true, false)],
+            vec![Region::new(0, 582, 800, 18), Region::new(800, 0, 800, 18)]
+        );
+
+        body: {
+            let drw = BarDraw {
+                screens: vec![Region::new(0, 0, 800, 600), Region::new(800, 0, 800, 600)],
+                windows: RefCell::new(vec![]),
+            };
+            let mut bar: StatusBar<_, _, MockXConn> =
+                StatusBar::try_new(drw, Position::Top, 18, 0x000000, &[], vec![]).unwrap();
+
+            bar.set_screen_bars(screen_bars).unwrap();
+            assert_eq!(*bar.drw.windows.borrow(), expected);
+            bar.redraw().unwrap();
+        }
+    }
+
+    test_cases! {
+        bar_region;
+        args: (position: Position, margin: usize, expected: Region);
+
+        case: top_no_margin => (Position::Top, 0, Region::new(0, 0, 800, 18));
+        case: bottom_no_margin => (Position::Bottom, 0, Region::new(0, 582, 800, 18));
+        case: top_with_margin => (Position::Top, 5, Region::new(5, 5, 790, 18));
+        case: bottom_with_margin => (Position::Bottom, 5, Region::new(5, 577, 790, 18));
+
+        body: {
+            let screen = Region::new(0, 0, 800, 600);
+            assert_eq!(bar_region(screen, position, 18, margin), expected);
+        }
+    }
+
+    #[test]
+    fn reserved_region_contains_bar_and_margin() {
+        let conf = crate::core::config::Config {
+            bar_height: 18,
+            bar_margin_px: 5,
+            ..Default::default()
+        };
+        let mut screen = crate::core::screen::Screen::new(Region::new(0, 0, 800, 600), 0);
+        screen.update_effective_region(conf.bar_reserved_px(), true);
+
+        let bar = bar_region(screen.region(false), Position::Top, 18, 5);
+        let usable = screen.region(true);
+
+        assert_eq!(usable, Region::new(0, 28, 800, 572));
+        assert_eq!(usable.y - (bar.y + bar.h), 5);
+    }
 }
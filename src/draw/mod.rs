@@ -5,8 +5,11 @@
 //! for writing a full GUI application, the [Draw] and [DrawContext] traits are enough for setting
 //! up simple text based UI elements such as status bars and menus.
 pub mod bar;
+pub mod preview;
 pub mod widget;
 
+mod color_names;
+
 #[doc(inline)]
 pub use bar::*;
 
@@ -102,6 +105,22 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Look up a standard X11 color name such as "red" or "steel blue".
+    ///
+    /// Matching ignores case and spaces. Returns None if the name is not known.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name: String = name
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        color_names::COLOR_NAMES
+            .binary_search_by_key(&name.as_str(), |&(n, _)| n)
+            .ok()
+            .map(|i| Self::new_from_hex((color_names::COLOR_NAMES[i].1 << 8) + 0xFF))
+    }
+
     /// The RGB information of this color as 0.0-1.0 range floats representing
     /// proportions of 255 for each of R, G, B
     pub fn rgb(&self) -> (f64, f64, f64) {
@@ -128,6 +147,64 @@ impl Color {
     pub fn rgba_u32(&self) -> u32 {
         _f2u!(self.r, 24) + _f2u!(self.g, 16) + _f2u!(self.b, 8) + _f2u!(self.a, 0)
     }
+
+    /// The relative luminance of this color as defined by
+    /// [WCAG 2.0](https://www.w3.org/TR/WCAG20/#relativeluminancedef), ignoring alpha.
+    pub fn luminance(&self) -> f32 {
+        let channel = |c: f64| {
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        (0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)) as f32
+    }
+
+    /// The [WCAG 2.0](https://www.w3.org/TR/WCAG20/#contrast-ratiodef) contrast ratio between
+    /// this color and `other`, ranging from 1.0 (no contrast) to 21.0 (black on white).
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (l1, l2) = (self.luminance(), other.luminance());
+        let (lighter, darker) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
+
+        (lighter + 0.05) / (darker + 0.05)
+    }
+
+    /// Linearly interpolate between this color and `other`, where a `t` of 0.0 is this color
+    /// and 1.0 is `other`. Values of `t` outside of that range are clamped.
+    pub fn interpolate(&self, other: &Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+}
+
+/// Select the color from `candidates` that has the highest contrast against `bg` for use as
+/// a text color. If no candidates are given then the better of black and white is returned.
+pub fn best_text_color(bg: &Color, candidates: &[Color]) -> Color {
+    let default = [Color::from(0x000000FF), Color::from(0xFFFFFFFF)];
+    let candidates = if candidates.is_empty() {
+        &default[..]
+    } else {
+        candidates
+    };
+
+    candidates
+        .iter()
+        .copied()
+        .max_by(|a, b| {
+            a.contrast_ratio(bg)
+                .partial_cmp(&b.contrast_ratio(bg))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or(default[0])
 }
 
 impl From<u32> for Color {
@@ -162,6 +239,10 @@ impl TryFrom<&str> for Color {
     type Error = DrawError;
 
     fn try_from(s: &str) -> Result<Self> {
+        if let Some(color) = Self::from_name(s) {
+            return Ok(color);
+        }
+
         let hex = u32::from_str_radix(s.strip_prefix('#').unwrap_or(s), 16)?;
 
         if s.len() == 7 {
@@ -174,6 +255,228 @@ impl TryFrom<&str> for Color {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("#{:08x}", self.rgba_u32()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Color::try_from(s).map_err(serde::de::Error::custom)
+    }
+}
+
+// The number of distinct colors used when drawing a gradient border
+const GRADIENT_STEPS: u32 = 64;
+
+/// The direction that a [BorderStyle::Gradient] runs in, starting from the top left corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GradientDirection {
+    /// From the left edge of the window to the right edge
+    Horizontal,
+    /// From the top edge of the window to the bottom edge
+    Vertical,
+    /// From the top left corner of the window to the bottom right corner
+    Diagonal,
+}
+
+/// How the border of a client window should be drawn.
+///
+/// Solid borders can be created from anything that can be converted into a [Color], including
+/// hex strings and color names.
+///
+/// # Example
+/// ```
+/// use penrose::draw::{BorderStyle, Color, GradientDirection};
+/// use std::convert::TryFrom;
+///
+/// let solid = BorderStyle::try_from("#cc241d").unwrap();
+/// assert_eq!(solid.primary_color(), Color::try_from("#cc241d").unwrap());
+///
+/// let gradient = BorderStyle::gradient(0x000000FF, 0xFFFFFFFF, GradientDirection::Vertical);
+/// assert_eq!(gradient.color_at(0.5), Color::from((0.5, 0.5, 0.5)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum BorderStyle {
+    /// A single flat color
+    Solid(Color),
+    /// A smooth transition between two colors
+    Gradient {
+        /// The color at the start of the gradient
+        from: Color,
+        /// The color at the end of the gradient
+        to: Color,
+        /// The direction that the gradient runs in
+        direction: GradientDirection,
+    },
+}
+
+impl BorderStyle {
+    /// Create a new gradient running from `from` to `to` in the given direction
+    pub fn gradient(
+        from: impl Into<Color>,
+        to: impl Into<Color>,
+        direction: GradientDirection,
+    ) -> Self {
+        Self::Gradient {
+            from: from.into(),
+            to: to.into(),
+            direction,
+        }
+    }
+
+    /// The color used when this style has to be shown as a single color. For gradients this is
+    /// the starting color.
+    pub fn primary_color(&self) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient { from, .. } => *from,
+        }
+    }
+
+    /// The color of this style at `t` along its length, from 0.0 at the start to 1.0 at the end.
+    pub fn color_at(&self, t: f64) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient { from, to, .. } => from.interpolate(to, t),
+        }
+    }
+
+    /// The rectangles and colors needed to draw this style into a border pixmap for a window
+    /// of `w` x `h` pixels with a border of `border_px`.
+    ///
+    /// X tiles border pixmaps from the origin of the window itself rather than the outer
+    /// corner of its border, so the returned regions are offset to match: the pixmap they are
+    /// drawn into should be the full size of the window including its border.
+    pub fn border_fills(&self, w: u32, h: u32, border_px: u32) -> Vec<(Region, Color)> {
+        if border_px == 0 {
+            return vec![];
+        }
+
+        let (outer_w, outer_h) = (w + 2 * border_px, h + 2 * border_px);
+        let max_step = (GRADIENT_STEPS - 1) as f64;
+        let step = |x: u32, y: u32| {
+            let (n, d) = match self {
+                Self::Solid(_) => (0, 1),
+                Self::Gradient { direction, .. } => match direction {
+                    GradientDirection::Horizontal => (x, outer_w - 1),
+                    GradientDirection::Vertical => (y, outer_h - 1),
+                    GradientDirection::Diagonal => (x + y, outer_w + outer_h - 2),
+                },
+            };
+            (n as f64 / d.max(1) as f64 * max_step).round() as u32
+        };
+        let offset = |v: u32, len: u32| {
+            if v < border_px {
+                v + len - border_px
+            } else {
+                v - border_px
+            }
+        };
+
+        // Runs of the same color are extended down from the row above where possible so that
+        // the sides of the border are a handful of tall rectangles rather than one per row
+        let mut fills: Vec<(Region, Color)> = vec![];
+        let mut prev_row = vec![];
+        for y in 0..outer_h {
+            let oy = offset(y, outer_h);
+            let spans = if y < border_px || y >= outer_h - border_px {
+                [(0, border_px), (border_px, outer_w)]
+            } else {
+                [(0, border_px), (outer_w - border_px, outer_w)]
+            };
+
+            let mut row = vec![];
+            for (start, end) in spans {
+                let mut x = start;
+                while x < end {
+                    let s = step(x, y);
+                    let run_end = (x + 1..end).find(|&x| step(x, y) != s).unwrap_or(end);
+                    let color = self.color_at(s as f64 / max_step);
+                    let (ox, w) = (offset(x, outer_w), run_end - x);
+
+                    let above = prev_row.iter().copied().find(|&i: &usize| {
+                        let (r, c): &(Region, Color) = &fills[i];
+                        r.x == ox && r.w == w && r.y + r.h == oy && *c == color
+                    });
+                    match above {
+                        Some(i) => {
+                            fills[i].0.h += 1;
+                            row.push(i);
+                        }
+                        None => {
+                            row.push(fills.len());
+                            fills.push((Region::new(ox, oy, w, 1), color));
+                        }
+                    }
+                    x = run_end;
+                }
+            }
+            prev_row = row;
+        }
+
+        fills
+    }
+}
+
+// Group the regions of a set of border fills by color (in the order each color first appears) so
+// that each color can be drawn using a single request.
+#[cfg(any(feature = "xcb", feature = "x11rb"))]
+pub(crate) fn fills_by_color(fills: &[(Region, Color)]) -> Vec<(Color, Vec<Region>)> {
+    let mut grouped: Vec<(Color, Vec<Region>)> = vec![];
+    for &(r, color) in fills {
+        match grouped.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, regions)) => regions.push(r),
+            None => grouped.push((color, vec![r])),
+        }
+    }
+
+    grouped
+}
+
+impl From<Color> for BorderStyle {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl From<u32> for BorderStyle {
+    fn from(hex: u32) -> Self {
+        Self::Solid(hex.into())
+    }
+}
+
+impl TryFrom<String> for BorderStyle {
+    type Error = DrawError;
+
+    fn try_from(s: String) -> Result<Self> {
+        Color::try_from(s).map(Self::Solid)
+    }
+}
+
+impl TryFrom<&str> for BorderStyle {
+    type Error = DrawError;
+
+    fn try_from(s: &str) -> Result<Self> {
+        Color::try_from(s).map(Self::Solid)
+    }
+}
+
 /// A simple drawing abstraction
 ///
 /// `Draw` is not intended for use in writing full GUI interfaces, rather it is a simple
@@ -265,6 +568,27 @@ mod tests {
         }
     }
 
+    test_cases! {
+        color_from_name;
+        args: (name: &str, hex: &str);
+
+        case: white => ("white", "#ffffff");
+        case: black => ("black", "#000000");
+        case: red => ("red", "#ff0000");
+        case: steelblue => ("steelblue", "#4682b4");
+        case: mixed_case_with_spaces => ("Steel Blue", "#4682b4");
+
+        body: {
+            assert_eq!(Color::try_from(name).unwrap(), Color::try_from(hex).unwrap());
+        }
+    }
+
+    #[test]
+    fn unknown_color_names_are_rejected() {
+        assert!(Color::from_name("not-a-color").is_none());
+        assert!(Color::try_from("not-a-color").is_err());
+    }
+
     test_cases! {
         color_from_str_or_string_no_alpha;
         args: (s: &str, floats: (f64, f64, f64, f64));
@@ -296,6 +620,95 @@ mod tests {
         }
     }
 
+    test_cases! {
+        color_contrast_ratio;
+        args: (a: &str, b: &str, expected: f32);
+
+        case: black_white => ("#000000", "#FFFFFF", 21.0);
+        case: white_black => ("#FFFFFF", "#000000", 21.0);
+        case: same_color => ("#cc241d", "#cc241d", 1.0);
+        case: red_white => ("#FF0000", "#FFFFFF", 3.998);
+
+        body: {
+            let ratio = Color::try_from(a).unwrap().contrast_ratio(&Color::try_from(b).unwrap());
+            assert!((ratio - expected).abs() < 0.01, "{} != {}", ratio, expected);
+        }
+    }
+
+    test_cases! {
+        best_text_color_picks_highest_contrast;
+        args: (bg: &str, candidates: &[&str], expected: &str);
+
+        case: dark_bg => ("#282828", &["#3c3836", "#ebdbb2", "#cc241d"], "#ebdbb2");
+        case: light_bg => ("#fbf1c7", &["#3c3836", "#ebdbb2", "#cc241d"], "#3c3836");
+        case: no_candidates_dark => ("#282828", &[], "#FFFFFF");
+        case: no_candidates_light => ("#fbf1c7", &[], "#000000");
+
+        body: {
+            let bg = Color::try_from(bg).unwrap();
+            let candidates: Vec<Color> = candidates
+                .iter()
+                .map(|c| Color::try_from(*c).unwrap())
+                .collect();
+            assert_eq!(best_text_color(&bg, &candidates), Color::try_from(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn gradient_midpoint_is_interpolated() {
+        let gradient = BorderStyle::gradient(0xFF0000FF, 0x0000FFFF, GradientDirection::Diagonal);
+
+        assert_eq!(gradient.color_at(0.5), Color::from((0.5, 0.0, 0.5)));
+        assert_eq!(gradient.color_at(0.5).rgb_u32(), 0x7F007F);
+        assert_eq!(gradient.color_at(0.0), Color::from(0xFF0000FF));
+        assert_eq!(gradient.color_at(1.0), Color::from(0x0000FFFF));
+    }
+
+    test_cases! {
+        border_fills_cover_the_border_once;
+        args: (direction: GradientDirection);
+
+        case: horizontal => (GradientDirection::Horizontal);
+        case: vertical => (GradientDirection::Vertical);
+        case: diagonal => (GradientDirection::Diagonal);
+
+        body: {
+            let (w, h, bpx) = (40, 30, 3);
+            let gradient = BorderStyle::gradient(0x000000FF, 0xFFFFFFFF, direction);
+            let fills = gradient.border_fills(w, h, bpx);
+
+            let mut covered = vec![vec![0; (w + 2 * bpx) as usize]; (h + 2 * bpx) as usize];
+            for (r, _) in fills.iter() {
+                let (x, y, rw, rh) = r.values();
+                for row in covered.iter_mut().skip(y as usize).take(rh as usize) {
+                    for px in row.iter_mut().skip(x as usize).take(rw as usize) {
+                        *px += 1;
+                    }
+                }
+            }
+
+            // pixmap coordinates are relative to the window origin so the client window itself
+            // covers the top left of the pixmap
+            for (y, row) in covered.iter().enumerate() {
+                for (x, n) in row.iter().enumerate() {
+                    let inside = x < w as usize && y < h as usize;
+                    assert_eq!(*n, if inside { 0 } else { 1 }, "pixel ({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn border_fills_are_merged_vertically() {
+        let solid = BorderStyle::from(0xFF0000FF).border_fills(1000, 800, 2);
+        let gradient = BorderStyle::gradient(0x000000FF, 0xFFFFFFFF, GradientDirection::Horizontal)
+            .border_fills(1000, 800, 2);
+
+        // The side of the border that wraps around the pixmap origin is split in two
+        assert_eq!(solid.len(), 5);
+        assert!(gradient.len() < 2 * GRADIENT_STEPS as usize + 8);
+    }
+
     test_cases! {
         color_rgba_u32;
         args: (s: &str, expected: u32);
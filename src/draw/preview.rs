@@ -0,0 +1,206 @@
+//! Temporary overlays for previewing where windows will be placed
+use crate::{
+    core::{
+        bindings::KeyPress,
+        data_types::{Region, WinType},
+        xconnection::{Atom, KeyPressParseAttempt, Xid},
+    },
+    draw::{Color, DrawContext, KeyPressDraw, Result},
+};
+
+// The width of the outline drawn around each region
+const OUTLINE_PX: u32 = 3;
+
+/// Outline each of `regions` using four thin windows and block until the user either confirms
+/// (Return) or cancels (Escape) the preview, returning whether or not it was confirmed.
+///
+/// The outline windows are always destroyed and the keyboard released before returning, even
+/// if drawing the outlines or reading key presses fails.
+pub fn preview_regions<D>(drw: &mut D, regions: &[Region], color: Color) -> Result<bool>
+where
+    D: KeyPressDraw,
+{
+    let mut ids = vec![];
+    let result = draw_outlines(drw, regions, color, &mut ids).and_then(|_| {
+        drw.grab_keyboard()?;
+        let confirmed = wait_for_confirmation(drw);
+        drw.ungrab_keyboard()?;
+        confirmed
+    });
+
+    for id in ids {
+        if let Err(e) = drw.destroy_client(id) {
+            warn!(id, %e, "unable to destroy preview window");
+        }
+    }
+
+    result
+}
+
+fn draw_outlines<D>(drw: &mut D, regions: &[Region], color: Color, ids: &mut Vec<Xid>) -> Result<()>
+where
+    D: KeyPressDraw,
+{
+    for r in regions {
+        let (x, y, w, h) = r.values();
+        let t = OUTLINE_PX.min(w / 2).min(h / 2).max(1);
+        let edges = [
+            Region::new(x, y, w, t),
+            Region::new(x, y + h - t, w, t),
+            Region::new(x, y, t, h),
+            Region::new(x + w - t, y, t, h),
+        ];
+
+        for e in edges.iter() {
+            let id = drw.new_window(WinType::InputOutput(Atom::NetWindowTypeDialog), *e, false)?;
+            ids.push(id);
+            let mut ctx = drw.context_for(id)?;
+            ctx.color(&color);
+            ctx.rectangle(0.0, 0.0, e.w as f64, e.h as f64)?;
+            ctx.flush();
+            drw.map_client(id)?;
+            drw.flush(id)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn wait_for_confirmation<D>(drw: &D) -> Result<bool>
+where
+    D: KeyPressDraw,
+{
+    loop {
+        match drw.next_keypress_blocking()? {
+            KeyPressParseAttempt::KeyPress(KeyPress::Return) => return Ok(true),
+            KeyPressParseAttempt::KeyPress(KeyPress::Escape) => return Ok(false),
+            _ => continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::xconnection::{
+            Result as XResult, StubXClientHandler, StubXClientProperties, StubXKeyboardHandler,
+        },
+        draw::Draw,
+    };
+    use std::cell::RefCell;
+
+    struct PreviewDraw {
+        keys: RefCell<Vec<KeyPress>>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl PreviewDraw {
+        fn record(&self, call: impl Into<String>) {
+            self.calls.borrow_mut().push(call.into());
+        }
+    }
+
+    struct NoopContext;
+
+    impl DrawContext for NoopContext {
+        fn font(&mut self, _: &str, _: i32) -> Result<()> {
+            Ok(())
+        }
+        fn color(&mut self, _: &Color) {}
+        fn clear(&mut self) -> Result<()> {
+            Ok(())
+        }
+        fn translate(&self, _: f64, _: f64) {}
+        fn set_x_offset(&self, _: f64) {}
+        fn set_y_offset(&self, _: f64) {}
+        fn rectangle(&self, _: f64, _: f64, _: f64, _: f64) -> Result<()> {
+            Ok(())
+        }
+        fn text(&self, _: &str, _: f64, _: (f64, f64)) -> Result<(f64, f64)> {
+            Ok((0.0, 0.0))
+        }
+        fn text_extent(&self, _: &str) -> Result<(f64, f64)> {
+            Ok((0.0, 0.0))
+        }
+        fn flush(&self) {}
+    }
+
+    impl StubXClientProperties for PreviewDraw {}
+
+    impl StubXClientHandler for PreviewDraw {
+        fn mock_destroy_client(&self, id: Xid) -> XResult<()> {
+            self.record(format!("destroy {}", id));
+            Ok(())
+        }
+    }
+
+    impl StubXKeyboardHandler for PreviewDraw {
+        fn mock_grab_keyboard(&self) -> XResult<()> {
+            self.record("grab");
+            Ok(())
+        }
+
+        fn mock_ungrab_keyboard(&self) -> XResult<()> {
+            self.record("ungrab");
+            Ok(())
+        }
+
+        // Runs out of key presses with an error, as if the connection was lost
+        fn mock_next_keypress_blocking(&self) -> XResult<KeyPressParseAttempt> {
+            match self.keys.borrow_mut().pop() {
+                Some(k) => Ok(KeyPressParseAttempt::KeyPress(k)),
+                None => Err(crate::core::xconnection::XError::Raw("no more keys".into())),
+            }
+        }
+    }
+
+    impl Draw for PreviewDraw {
+        type Ctx = NoopContext;
+
+        fn new_window(&mut self, _: WinType, _: Region, _: bool) -> Result<Xid> {
+            Ok(self.calls.borrow().len() as Xid)
+        }
+        fn screen_sizes(&self) -> Result<Vec<Region>> {
+            Ok(vec![])
+        }
+        fn register_font(&mut self, _: &str) {}
+        fn context_for(&self, _: Xid) -> Result<Self::Ctx> {
+            Ok(NoopContext)
+        }
+        fn temp_context(&self, _: u32, _: u32) -> Result<Self::Ctx> {
+            Ok(NoopContext)
+        }
+        fn flush(&self, id: Xid) -> Result<()> {
+            self.record(format!("draw {}", id));
+            Ok(())
+        }
+    }
+
+    test_cases! {
+        preview_regions;
+        args: (keys: Vec<KeyPress>, expected: Option<bool>);
+
+        case: confirmed => (vec![KeyPress::Return], Some(true));
+        case: cancelled => (vec![KeyPress::Escape], Some(false));
+        case: other_keys_are_ignored => (vec![KeyPress::Escape, KeyPress::Tab], Some(false));
+        case: failure_reading_keys => (vec![], None);
+
+        body: {
+            let mut drw = PreviewDraw {
+                keys: RefCell::new(keys),
+                calls: RefCell::new(vec![]),
+            };
+
+            let res = preview_regions(&mut drw, &[Region::new(0, 0, 100, 100)], Color::from(0));
+
+            assert_eq!(res.ok(), expected);
+            let calls = drw.calls.borrow();
+            assert_eq!(calls[..5], ["draw 0", "draw 1", "draw 2", "draw 3", "grab"]);
+            assert_eq!(
+                calls[5..],
+                ["ungrab", "destroy 0", "destroy 1", "destroy 2", "destroy 3"]
+            );
+        }
+    }
+}
@@ -207,6 +207,11 @@ pub enum PenroseError {
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    /// A command received over the [IPC socket][crate::core::ipc] could not be parsed
+    #[cfg(feature = "ipc")]
+    #[error("invalid IPC command: '{0}'")]
+    InvalidIpcCommand(String),
+
     /// Wm(Normal)Hints received from the X server were invalid
     #[error("Invalid window hints property: {0}")]
     InvalidHints(String),
@@ -234,6 +239,11 @@ pub enum PenroseError {
     #[error(transparent)]
     Strum(#[from] strum::ParseError),
 
+    /// Parsing a [Config] from TOML failed
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
     /// An attempt was made to reference a client that is not known to penrose
     #[error("{0} is not a known client")]
     UnknownClient(Xid),
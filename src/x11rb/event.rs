@@ -1,6 +1,8 @@
 use crate::{
     core::{
-        bindings::{KeyCode, ModifierKey, MouseButton, MouseEvent, MouseEventKind, MouseState},
+        bindings::{
+            KeyCode, ModifierKey, MouseButton, MouseEvent, MouseEventKind, MouseState, LOCK_MASKS,
+        },
         data_types::{Point, Region},
         xconnection::{
             event::ClientEventMask, ClientMessage, ClientMessageData, ConfigureEvent, ExposeEvent,
@@ -15,7 +17,7 @@ use strum::IntoEnumIterator;
 use x11rb::{
     connection::Connection,
     protocol::{
-        xproto::{ClientMessageEvent, ConnectionExt as _, ModMask},
+        xproto::{ClientMessageEvent, ConnectionExt as _},
         Event,
     },
 };
@@ -66,9 +68,15 @@ pub(crate) fn convert_event<C: Connection>(
                 mask: event.state,
                 code: event.detail,
             };
-            let numlock = ModMask::M2;
-            Ok(Some(XEvent::KeyPress(
-                code.ignoring_modifier(numlock.into()),
+            Ok(Some(XEvent::KeyPress(code.ignoring_modifiers(LOCK_MASKS))))
+        }
+        Event::KeyRelease(event) => {
+            let code = KeyCode {
+                mask: event.state,
+                code: event.detail,
+            };
+            Ok(Some(XEvent::KeyRelease(
+                code.ignoring_modifiers(LOCK_MASKS),
             )))
         }
         Event::MapRequest(event) => {
@@ -83,13 +91,13 @@ pub(crate) fn convert_event<C: Connection>(
         }
         Event::EnterNotify(event) => Ok(Some(XEvent::Enter(PointerChange {
             id: event.event,
-            abs: Point::new(event.root_x as u32, event.root_y as u32),
-            relative: Point::new(event.event_x as u32, event.event_y as u32),
+            abs: Point::new(event.root_x as i32, event.root_y as i32),
+            relative: Point::new(event.event_x as i32, event.event_y as i32),
         }))),
         Event::LeaveNotify(event) => Ok(Some(XEvent::Leave(PointerChange {
             id: event.event,
-            abs: Point::new(event.root_x as u32, event.root_y as u32),
-            relative: Point::new(event.event_x as u32, event.event_y as u32),
+            abs: Point::new(event.root_x as i32, event.root_y as i32),
+            relative: Point::new(event.event_x as i32, event.event_y as i32),
         }))),
         Event::DestroyNotify(event) => Ok(Some(XEvent::Destroy(event.window))),
         Event::ConfigureNotify(event) => Ok(Some(XEvent::ConfigureNotify(ConfigureEvent {
@@ -151,7 +159,7 @@ fn to_mouse_state(detail: u8, state: u16) -> Option<MouseState> {
         }
     };
     let modifiers = ModifierKey::iter().filter(|m| is_held(m, state)).collect();
-    Some(MouseState { button, modifiers })
+    Some(MouseState::new(button, modifiers))
 }
 
 fn to_client_message<C: Connection>(
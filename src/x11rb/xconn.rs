@@ -9,7 +9,7 @@
 
 use crate::{
     core::{
-        bindings::{KeyBindings, KeyCode, MouseBindings, MouseState},
+        bindings::{lock_mask_combinations, KeyBindings, KeyCode, MouseBindings, MouseState},
         data_types::{Point, Region},
         screen::Screen,
         xconnection::{
@@ -19,20 +19,26 @@ use crate::{
             XEventHandler, XState, Xid,
         },
     },
+    draw::{fills_by_color, Color},
     x11rb::{atom::Atoms, X11rbError},
 };
 
-use std::{convert::TryFrom, str::FromStr};
+use std::{
+    convert::TryFrom,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 
 use x11rb::{
     connection::Connection,
     protocol::{
         randr::{self, ConnectionExt as _},
         xproto::{
-            AtomEnum, ButtonIndex, ChangeWindowAttributesAux, ClientMessageData,
-            ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, CreateWindowAux, EventMask,
-            Grab, GrabMode, InputFocus, MapState, ModMask, PropMode, StackMode, WindowClass,
-            CLIENT_MESSAGE_EVENT,
+            AtomEnum, ButtonIndex, ChangeGCAux, ChangeWindowAttributesAux, ClientMessageData,
+            ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, CreateGCAux,
+            CreateWindowAux, EventMask, Grab, GrabMode, InputFocus, MapState, ModMask, PropMode,
+            Rectangle, StackMode, WindowClass, CLIENT_MESSAGE_EVENT,
         },
     },
     wrapper::ConnectionExt as _,
@@ -145,6 +151,7 @@ impl<C: Connection> XClientConfig for X11rbConnection<C> {
                     aux = aux.x(x as i32).y(y as i32).width(w).height(h);
                 }
                 ClientConfig::StackAbove => aux = aux.stack_mode(StackMode::ABOVE),
+                ClientConfig::StackBelow => aux = aux.stack_mode(StackMode::BELOW),
             }
         }
         self.conn.configure_window(id, &aux)?;
@@ -174,6 +181,43 @@ impl<C: Connection> XClientConfig for X11rbConnection<C> {
         Ok(())
     }
 
+    fn set_client_border_pixmap(
+        &self,
+        id: Xid,
+        w: u32,
+        h: u32,
+        fills: &[(Region, Color)],
+    ) -> Result<()> {
+        let depth = self.conn.get_geometry(id)?.reply()?.depth;
+        let pixmap = self.conn.generate_id()?;
+        self.conn
+            .create_pixmap(depth, pixmap, id, w as u16, h as u16)?;
+        let gc = self.conn.generate_id()?;
+        self.conn.create_gc(gc, pixmap, &CreateGCAux::new())?;
+
+        for (color, regions) in fills_by_color(fills) {
+            self.conn
+                .change_gc(gc, &ChangeGCAux::new().foreground(color.rgb_u32()))?;
+            let rects: Vec<Rectangle> = regions
+                .iter()
+                .map(|r| Rectangle {
+                    x: r.x as i16,
+                    y: r.y as i16,
+                    width: r.w as u16,
+                    height: r.h as u16,
+                })
+                .collect();
+            self.conn.poly_fill_rectangle(pixmap, gc, &rects)?;
+        }
+
+        // The server keeps its own reference to the pixmap once it is set as the border
+        let aux = ChangeWindowAttributesAux::new().border_pixmap(pixmap);
+        self.conn.change_window_attributes(id, &aux)?;
+        self.conn.free_gc(gc)?;
+        self.conn.free_pixmap(pixmap)?;
+        Ok(())
+    }
+
     fn get_window_attributes(&self, id: Xid) -> Result<WindowAttributes> {
         let win_attrs = self.conn.get_window_attributes(id)?.reply()?;
         let override_redirect = win_attrs.override_redirect;
@@ -258,6 +302,9 @@ impl<C: Connection> XClientProperties for X11rbConnection<C> {
                     .collect::<Result<Vec<String>>>()?,
             ),
 
+            // Multi-valued cardinal props such as _NET_WM_STRUT_PARTIAL are returned as raw values
+            "CARDINAL" if r.value_len > 1 => Prop::Bytes(r.value32().unwrap().collect()),
+
             // This uses unwrap() for symmetry with penrose::xcb (which does value()[0] to "panic")
             "CARDINAL" => Prop::Cardinal(r.value32().unwrap().next().unwrap()),
 
@@ -402,6 +449,26 @@ impl<C: Connection> XEventHandler for X11rbConnection<C> {
         }
     }
 
+    // The connection is generic so there is no file descriptor to wait on: poll in short
+    // intervals instead. This is only used while a deadline is pending.
+    fn wait_for_event_timeout(&self, timeout: Duration) -> Result<Option<XEvent>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            while let Some(event) = self.conn.poll_for_event()? {
+                if let Some(event) = super::event::convert_event(self, event)? {
+                    return Ok(Some(event));
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_millis(0) {
+                return Ok(None);
+            }
+            thread::sleep(remaining.min(Duration::from_millis(10)));
+        }
+    }
+
     fn send_client_event(&self, msg: ClientMessage) -> Result<()> {
         let type_ = self.atom_id(&msg.dtype)?;
         let data = match msg.data() {
@@ -471,7 +538,7 @@ impl<C: Connection> XState for X11rbConnection<C> {
 
     fn cursor_position(&self) -> Result<Point> {
         let reply = self.conn.query_pointer(self.root)?.reply()?;
-        Ok(Point::new(reply.root_x as u32, reply.root_y as u32))
+        Ok(Point::new(reply.root_x as i32, reply.root_y as i32))
     }
 
     fn warp_cursor(&self, win_id: Option<Xid>, screen: &Screen) -> Result<()> {
@@ -550,14 +617,34 @@ impl<C: Connection> XConn for X11rbConnection<C> {
 
         Ok(())
     }
+
+    fn grab_modifier_taps(&self, codes: &[KeyCode]) -> Result<()> {
+        self.grab_key_bindings(&codes.iter().collect::<Vec<_>>())?;
+        self.flush();
+
+        Ok(())
+    }
+
+    fn grab_server(&self) -> Result<()> {
+        self.conn.grab_server()?;
+
+        Ok(())
+    }
+
+    fn ungrab_server(&self) -> Result<()> {
+        self.conn.ungrab_server()?;
+        self.conn.flush()?;
+
+        Ok(())
+    }
 }
 
 impl<C: Connection> X11rbConnection<C> {
     fn grab_key_bindings(&self, keys: &[&KeyCode]) -> Result<()> {
-        // We need to explicitly grab NumLock as an additional modifier and then drop it later on
-        // when we are passing events through to the WindowManager as NumLock alters the modifier
-        // mask when it is active.
-        let modifiers = &[0, u16::from(ModMask::M2)];
+        // We need to explicitly grab NumLock and CapsLock as additional modifiers and then drop
+        // them later on when we are passing events through to the WindowManager as they alter the
+        // modifier mask when they are active.
+        let modifiers = lock_mask_combinations();
         let mode = GrabMode::ASYNC;
 
         for m in modifiers.iter() {
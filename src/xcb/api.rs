@@ -1,7 +1,10 @@
 //! A wrapper around the underlying xcb api layer that only exposes Penrose types
 use crate::{
     core::{
-        bindings::{KeyCode, KeyCodeMask, KeyCodeValue, MouseEvent, MouseState},
+        bindings::{
+            lock_mask_combinations, KeyCode, KeyCodeMask, KeyCodeValue, MouseEvent, MouseState,
+            LOCK_MASKS,
+        },
         data_types::{Point, Region, WinType},
         helpers::spawn_for_output,
         screen::Screen,
@@ -12,11 +15,20 @@ use crate::{
             XAtomQuerier, XEvent, Xid,
         },
     },
+    draw::{fills_by_color, Color},
     xcb::{Result, XErrorCode, XcbError, XcbGenericEvent},
 };
 use strum::*;
 
-use std::{collections::HashMap, convert::TryFrom, fmt, str::FromStr};
+use nix::poll::{poll, PollFd, PollFlags};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    os::unix::io::AsRawFd,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 #[cfg(feature = "keysyms")]
 use crate::core::{bindings::KeyPress, xconnection::KeyPressParseAttempt};
@@ -220,6 +232,8 @@ impl Api {
                     .collect::<Result<Vec<String>>>()?,
             ),
 
+            // Multi-valued cardinal props such as _NET_WM_STRUT_PARTIAL are returned as raw values
+            "CARDINAL" if r.value::<u32>().len() > 1 => Prop::Bytes(r.value::<u32>().to_vec()),
             "CARDINAL" => Prop::Cardinal(r.value()[0]),
 
             "STRING" => Prop::UTF8String(
@@ -315,6 +329,20 @@ impl Api {
         Ok(())
     }
 
+    /// Grab the X server, blocking requests from other X clients until it is released
+    pub fn grab_server(&self) -> Result<()> {
+        xcb::grab_server_checked(&self.conn).request_check()?;
+
+        Ok(())
+    }
+
+    /// Release a grab of the X server
+    pub fn ungrab_server(&self) -> Result<()> {
+        xcb::ungrab_server_checked(&self.conn).request_check()?;
+
+        Ok(())
+    }
+
     /// Poll for the next event from the underlying [XCB Connection][::xcb::Connection],
     /// returning it as an [XKeySym] if it was a user keypress, or an [XEvent] if not.
     ///
@@ -371,7 +399,6 @@ impl Api {
 
     fn generic_xcb_to_xevent(&self, event: XcbGenericEvent) -> Result<Option<XEvent>> {
         let xcb_response_type_mask: u8 = 0x7F;
-        let numlock = xcb::MOD_MASK_2 as u16;
 
         let etype = event.response_type() & xcb_response_type_mask;
 
@@ -394,7 +421,11 @@ impl Api {
             }
 
             xcb::KEY_PRESS => Some(XEvent::KeyPress(
-                KeyCode::try_from(event)?.ignoring_modifier(numlock),
+                KeyCode::try_from(event)?.ignoring_modifiers(LOCK_MASKS),
+            )),
+
+            xcb::KEY_RELEASE => Some(XEvent::KeyRelease(
+                KeyCode::try_from(event)?.ignoring_modifiers(LOCK_MASKS),
             )),
 
             xcb::MAP_REQUEST => {
@@ -410,8 +441,8 @@ impl Api {
                 let e: &xcb::EnterNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Some(XEvent::Enter(PointerChange {
                     id: e.event(),
-                    abs: Point::new(e.root_x() as u32, e.root_y() as u32),
-                    relative: Point::new(e.event_x() as u32, e.event_y() as u32),
+                    abs: Point::new(e.root_x() as i32, e.root_y() as i32),
+                    relative: Point::new(e.event_x() as i32, e.event_y() as i32),
                 }))
             }
 
@@ -419,8 +450,8 @@ impl Api {
                 let e: &xcb::LeaveNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Some(XEvent::Leave(PointerChange {
                     id: e.event(),
-                    abs: Point::new(e.root_x() as u32, e.root_y() as u32),
-                    relative: Point::new(e.event_x() as u32, e.event_y() as u32),
+                    abs: Point::new(e.root_x() as i32, e.root_y() as i32),
+                    relative: Point::new(e.event_x() as i32, e.event_y() as i32),
                 }))
             }
 
@@ -793,6 +824,43 @@ impl Api {
         Ok(xcb::change_window_attributes_checked(&self.conn, id, &data).request_check()?)
     }
 
+    /// Draw a border pixmap of `w` x `h` pixels for the target window, filling each region with
+    /// its paired color.
+    pub fn set_client_border_pixmap(
+        &self,
+        id: Xid,
+        w: u32,
+        h: u32,
+        fills: &[(Region, Color)],
+    ) -> Result<()> {
+        let depth = xcb::get_geometry(&self.conn, id).get_reply()?.depth();
+        let pixmap = self.conn.generate_id();
+        xcb::create_pixmap(&self.conn, depth, pixmap, id, w as u16, h as u16);
+        let gc = self.conn.generate_id();
+        xcb::create_gc(&self.conn, gc, pixmap, &[]);
+
+        for (color, regions) in fills_by_color(fills) {
+            xcb::change_gc(&self.conn, gc, &[(xcb::GC_FOREGROUND, color.rgb_u32())]);
+            let rects: Vec<xcb::Rectangle> = regions
+                .iter()
+                .map(|r| xcb::Rectangle::new(r.x as i16, r.y as i16, r.w as u16, r.h as u16))
+                .collect();
+            xcb::poly_fill_rectangle(&self.conn, pixmap, gc, &rects);
+        }
+
+        // The server keeps its own reference to the pixmap once it is set as the border
+        let res = xcb::change_window_attributes_checked(
+            &self.conn,
+            id,
+            &[(xcb::CW_BORDER_PIXMAP, pixmap)],
+        )
+        .request_check();
+        xcb::free_gc(&self.conn, gc);
+        xcb::free_pixmap(&self.conn, pixmap);
+
+        Ok(res?)
+    }
+
     /// Find the current size and position of the target window
     pub fn client_geometry(&self, id: Xid) -> Result<Region> {
         let res = xcb::get_geometry(&self.conn, id).get_reply()?;
@@ -855,7 +923,7 @@ impl Api {
     pub fn cursor_position(&self) -> Result<Point> {
         Ok(xcb::query_pointer(&self.conn, self.root)
             .get_reply()
-            .map(|reply| Point::new(reply.root_x() as u32, reply.root_y() as u32))?)
+            .map(|reply| Point::new(reply.root_x() as i32, reply.root_y() as i32))?)
     }
 
     /// Flush pending actions to the X event loop
@@ -871,10 +939,10 @@ impl Api {
 
     /// Register intercepts for each given [KeyCode]
     pub fn grab_keys(&self, keys: &[&KeyCode]) -> Result<()> {
-        // We need to explicitly grab NumLock as an additional modifier and then drop it later on
-        // when we are passing events through to the WindowManager as NumLock alters the modifier
-        // mask when it is active.
-        let modifiers = &[0, xcb::MOD_MASK_2 as u16];
+        // We need to explicitly grab NumLock and CapsLock as additional modifiers and then drop
+        // them later on when we are passing events through to the WindowManager as they alter the
+        // modifier mask when they are active.
+        let modifiers = lock_mask_combinations();
         let mode = xcb::GRAB_MODE_ASYNC as u8;
 
         for m in modifiers.iter() {
@@ -1010,6 +1078,34 @@ impl Api {
         }
     }
 
+    /// Wait up to `timeout` for the next event from the X event loop, returning None if no event
+    /// arrived in time.
+    ///
+    /// This method handles all of the mapping of xcb events to penrose [XEvent] instances,
+    /// returning an Error when the event channel from the X server is closed.
+    pub fn wait_for_event_timeout(&self, timeout: Duration) -> Result<Option<XEvent>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            // Drain anything already buffered by xcb before waiting on the socket
+            while let Some(event) = self.conn.poll_for_event() {
+                if let Some(e) = self.generic_xcb_to_xevent(event)? {
+                    return Ok(Some(e));
+                }
+            }
+            self.conn.has_error()?;
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_millis(0) {
+                return Ok(None);
+            }
+
+            let mut fds = [PollFd::new(self.conn.as_raw_fd(), PollFlags::POLLIN)];
+            let ms = remaining.as_millis().clamp(1, i32::MAX as u128) as i32;
+            poll(&mut fds, ms).map_err(std::io::Error::from)?;
+        }
+    }
+
     /// Return the next event from the X event loop if there is one.
     ///
     /// This method handles all of the mapping of xcb events to penrose [XEvent] instances,
@@ -17,17 +17,6 @@ impl ModifierKey {
     }
 }
 
-impl From<ModifierKey> for u16 {
-    fn from(m: ModifierKey) -> u16 {
-        (match m {
-            ModifierKey::Ctrl => xcb::MOD_MASK_CONTROL,
-            ModifierKey::Alt => xcb::MOD_MASK_1,
-            ModifierKey::Shift => xcb::MOD_MASK_SHIFT,
-            ModifierKey::Meta => xcb::MOD_MASK_4,
-        }) as u16
-    }
-}
-
 impl From<xcb::KeyPressEvent> for KeyCode {
     fn from(e: xcb::KeyPressEvent) -> Self {
         Self {
@@ -51,11 +40,11 @@ impl TryFrom<XcbGenericEvent> for KeyCode {
 
     fn try_from(e: XcbGenericEvent) -> Result<Self> {
         let r = e.response_type();
-        if r == xcb::KEY_PRESS as u8 {
+        if r == xcb::KEY_PRESS || r == xcb::KEY_RELEASE {
             let key_press: &xcb::KeyPressEvent = unsafe { xcb::cast_event(&e) };
             Ok(key_press.into())
         } else {
-            Err(XcbError::Raw("not an xcb key press".into()))
+            Err(XcbError::Raw("not an xcb key press or release".into()))
         }
     }
 }
@@ -65,11 +54,11 @@ impl TryFrom<&XcbGenericEvent> for KeyCode {
 
     fn try_from(e: &XcbGenericEvent) -> Result<Self> {
         let r = e.response_type();
-        if r == xcb::KEY_PRESS as u8 {
+        if r == xcb::KEY_PRESS || r == xcb::KEY_RELEASE {
             let key_press: &xcb::KeyPressEvent = unsafe { xcb::cast_event(e) };
             Ok(key_press.into())
         } else {
-            Err(XcbError::Raw("not an xcb key press".into()))
+            Err(XcbError::Raw("not an xcb key press or release".into()))
         }
     }
 }
@@ -91,10 +80,10 @@ impl TryFrom<u8> for MouseButton {
 
 impl MouseState {
     fn from_detail_and_state(detail: u8, state: u16) -> Result<Self> {
-        Ok(Self {
-            button: MouseButton::try_from(detail)?,
-            modifiers: ModifierKey::iter().filter(|m| m.was_held(state)).collect(),
-        })
+        Ok(Self::new(
+            MouseButton::try_from(detail)?,
+            ModifierKey::iter().filter(|m| m.was_held(state)).collect(),
+        ))
     }
 
     /// The xcb bitmask for this [MouseState]
@@ -190,6 +179,9 @@ impl From<&ClientConfig> for Vec<(u16, u32)> {
             ClientConfig::StackAbove => {
                 vec![(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE)]
             }
+            ClientConfig::StackBelow => {
+                vec![(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_BELOW)]
+            }
         }
     }
 }
@@ -42,3 +42,24 @@ pub fn parse_key_binding(pattern: String, known_codes: &CodeMap) -> Option<KeyCo
         None => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    test_cases! {
+        parse_then_display_round_trips;
+        args: (pattern: &str, expected: &str);
+
+        case: no_modifiers => ("44", "44");
+        case: single_modifier => ("M-44", "M-44");
+        case: multiple_modifiers => ("C-A-S-M-44", "C-A-S-M-44");
+        case: modifier_ordering_is_normalised => ("M-S-44", "S-M-44");
+
+        body: {
+            let codes: CodeMap = map! { "44".to_string() => 44, };
+            let k = parse_key_binding(pattern.to_string(), &codes).unwrap();
+            assert_eq!(k.to_string(), expected);
+        }
+    }
+}
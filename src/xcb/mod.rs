@@ -298,6 +298,13 @@ macro_rules! __xcb_impl_xeventhandler {
                 Ok(self.api.wait_for_event()?)
             }
 
+            fn wait_for_event_timeout(
+                &self,
+                timeout: std::time::Duration,
+            ) -> $crate::core::xconnection::Result<Option<XEvent>> {
+                Ok(self.api.wait_for_event_timeout(timeout)?)
+            }
+
             fn send_client_event(&self, msg: ClientMessage) -> $crate::core::xconnection::Result<()> {
                 Ok(self.api.send_client_event(msg)?)
             }
@@ -383,6 +390,16 @@ macro_rules! __xcb_impl_xclientconfig {
                 Ok(self.api.set_client_attributes(id, data)?)
             }
 
+            fn set_client_border_pixmap(
+                &self,
+                id: Xid,
+                w: u32,
+                h: u32,
+                fills: &[($crate::core::data_types::Region, $crate::draw::Color)],
+            ) -> $crate::core::xconnection::Result<()> {
+                Ok(self.api.set_client_border_pixmap(id, w, h, fills)?)
+            }
+
             fn get_window_attributes(&self, id: Xid) -> $crate::core::xconnection::Result<$crate::core::xconnection::WindowAttributes> {
                 Ok(self.api.get_window_attributes(id)?)
             }
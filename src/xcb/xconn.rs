@@ -13,7 +13,7 @@
  */
 use crate::{
     core::{
-        bindings::{KeyBindings, MouseBindings},
+        bindings::{KeyBindings, KeyCode, MouseBindings},
         data_types::{Point, Region},
         manager::WindowManager,
         screen::Screen,
@@ -131,4 +131,22 @@ impl XConn for XcbConnection {
 
         Ok(())
     }
+
+    fn grab_modifier_taps(&self, codes: &[KeyCode]) -> Result<()> {
+        self.api.grab_keys(&codes.iter().collect::<Vec<_>>())?;
+        self.flush();
+
+        Ok(())
+    }
+
+    fn grab_server(&self) -> Result<()> {
+        Ok(self.api.grab_server()?)
+    }
+
+    fn ungrab_server(&self) -> Result<()> {
+        self.api.ungrab_server()?;
+        self.flush();
+
+        Ok(())
+    }
 }
@@ -122,9 +122,11 @@ macro_rules! __impl_test_hook {
 
 __impl_test_hook! {
     client_name_updated => Xid, &str, bool;
+    client_title_changed => Xid, &str;
     client_added_to_workspace => Xid, usize;
     event_handled => ;
     focus_change => Xid;
+    client_focus_changed => Option<Xid>, Option<Xid>;
     layout_applied => usize, usize;
     layout_change => usize, usize;
     new_client => Xid;
@@ -134,7 +136,9 @@ __impl_test_hook! {
     screens_updated => &[Region];
     startup => ;
     workspace_change => usize, usize;
+    screen_workspace_change => usize, usize, usize;
     workspaces_updated => &[&str], usize;
+    state_changed => ;
 }
 
 test_cases! {
@@ -145,6 +149,10 @@ test_cases! {
         XEvent::PropertyNotify(PropertyEvent { id: 1, atom: "WM_NAME".into(), is_root: false }),
         XEvent::PropertyNotify(PropertyEvent { id: 1, atom: "_NET_WM_NAME".into(), is_root: false }),
     ]);
+    case: client_title_unchanged => ("client_title_changed", 0, vec![
+        XEvent::MapRequest(1, false),
+        XEvent::PropertyNotify(PropertyEvent { id: 1, atom: "_NET_WM_NAME".into(), is_root: false }),
+    ]);
     case: client_added_to_workspace => ("client_added_to_workspace", 2, vec![
         XEvent::MapRequest(1, false),
         XEvent::KeyPress(common::CLIENT_TO_WORKSPACE_CODE)
@@ -155,7 +163,13 @@ test_cases! {
         XEvent::MapRequest(2, false),
         XEvent::KeyPress(common::FOCUS_CHANGE_CODE)
     ]);
-    case: layout_applied => ("layout_applied", 3, vec![XEvent::KeyPress(common::LAYOUT_CHANGE_CODE)]);
+    case: client_focus_changed => ("client_focus_changed", 3, vec![
+        XEvent::MapRequest(1, false),
+        XEvent::MapRequest(2, false),
+        XEvent::KeyPress(common::FOCUS_CHANGE_CODE)
+    ]);
+    // includes the initial layout applied to both screens at startup
+    case: layout_applied => ("layout_applied", 5, vec![XEvent::KeyPress(common::LAYOUT_CHANGE_CODE)]);
     case: layout_change => ("layout_change", 1, vec![XEvent::KeyPress(common::LAYOUT_CHANGE_CODE)]);
     case: new_client => ("new_client", 1, vec![XEvent::MapRequest(1, false)]);
     case: randr_notify => ("randr_notify", 1, vec![XEvent::RandrNotify]);
@@ -167,8 +181,15 @@ test_cases! {
     case: screen_change => ("screen_change", 1, vec![XEvent::KeyPress(common::SCREEN_CHANGE_CODE)]);
     case: screens_updated => ("screens_updated", 1, vec![XEvent::RandrNotify]);
     case: startup => ("startup", 1, vec![]);
+    case: screen_workspace_change => ("screen_workspace_change", 2, vec![XEvent::KeyPress(common::WORKSPACE_CHANGE_CODE)]);
     case: workspace_change => ("workspace_change", 1, vec![XEvent::KeyPress(common::WORKSPACE_CHANGE_CODE)]);
     case: workspaces_updated => ("workspaces_updated", 1, vec![XEvent::KeyPress(common::ADD_WORKSPACE_CODE)]);
+    // mapping a client adds it to a workspace, focuses it and lays it out but that is one change
+    case: state_changed => ("state_changed", 2, vec![
+        XEvent::MapRequest(1, false),
+        XEvent::MapRequest(2, false),
+    ]);
+    case: state_changed_not_called_without_changes => ("state_changed", 0, vec![XEvent::ScreenChange]);
 
     body: {
         let calls = Rc::new(RefCell::new(vec![]));